@@ -0,0 +1,446 @@
+//! Adaptor signatures for scriptless scripts
+//!
+//! An adaptor point `T = t*G` is folded into the signing nonce. The session
+//! then produces a pre-signature that is valid against `R + T` except for the
+//! missing `t`: anyone who learns `t` can complete it into an ordinary Grin
+//! kernel signature, and anyone holding both the pre-signature and the
+//! completed signature learns `t`. Tying two signatures to the same `T` makes
+//! them atomic, which is the basis for atomic swaps and conditional payments
+//! without any change to Grin's consensus rules.
+//!
+//! Grin's signatures use the nonce whose y coordinate is a quadratic residue,
+//! so every signer negates its nonce when `R + T` does not qualify. The
+//! secret `t` is negated the same way on completion and extraction.
+//!
+//! # Algorithm
+//! ```text
+//! Round 1-2:  R = sum(R1_i + R2_i)            (as in a plain session)
+//! Nonce:      R' = R + T
+//! Round 3:    s'_i = ±r_i + c * a_i * x_i     (c = H(R', X, m), sign of R')
+//! Aggregate:  s' = sum(s'_i)                  pre-signature (R', s')
+//! Verify:     s' * G = ±R + c * X
+//! Complete:   s = s' ± t                      signature (R'.x, s)
+//! Extract:    t = ±(s - s')
+//! ```
+
+use secp256k1zkp::aggsig;
+use secp256k1zkp::{Message, PublicKey, Secp256k1, SecretKey, Signature};
+
+use crate::error::{Error, Result};
+use crate::scalar;
+use crate::session::Session;
+use crate::types::PartialSignature;
+
+/// Aggregated pre-signature locked to an adaptor point
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreSignature {
+    /// Aggregated nonce `R` from Round 2
+    agg_nonce: PublicKey,
+
+    /// Adaptor point `T`
+    adaptor_point: PublicKey,
+
+    /// Effective nonce `R + T`
+    nonce: PublicKey,
+
+    /// Pre-signature `(R'.x, s')`
+    signature: Signature,
+}
+
+impl PreSignature {
+    /// Get the aggregated nonce `R` from Round 2
+    pub const fn agg_nonce(&self) -> &PublicKey {
+        &self.agg_nonce
+    }
+
+    /// Get the adaptor point `T`
+    pub const fn adaptor_point(&self) -> &PublicKey {
+        &self.adaptor_point
+    }
+
+    /// Get the effective nonce `R + T` the completed signature will carry
+    pub const fn nonce(&self) -> &PublicKey {
+        &self.nonce
+    }
+
+    /// Get the pre-signature `(R'.x, s')`
+    ///
+    /// This is not a valid signature until completed with the adaptor secret.
+    pub const fn signature(&self) -> &Signature {
+        &self.signature
+    }
+}
+
+impl Session {
+    /// Compute the effective nonce `R + T` that pre-signatures commit to
+    ///
+    /// # Errors
+    /// Returns `Error::Crypto` if the points sum to infinity
+    pub fn adaptor_nonce(
+        &self,
+        agg_nonce: &PublicKey,
+        adaptor_point: &PublicKey,
+    ) -> Result<PublicKey> {
+        Ok(PublicKey::from_combination(
+            self.secp(),
+            vec![agg_nonce, adaptor_point],
+        )?)
+    }
+
+    /// Round 3 with an adaptor point: compute a partial pre-signature
+    ///
+    /// # Arguments
+    /// * `message` - Message to sign (32 bytes)
+    /// * `secret_key` - Participant's secret key
+    /// * `secret_nonce` - Participant's effective secret nonce from Round 1
+    /// * `agg_nonce` - Aggregated nonce from Round 2
+    /// * `adaptor_point` - Adaptor point `T`
+    /// * `agg_pubkey` - Aggregated public key
+    ///
+    /// # Returns
+    /// Partial pre-signature `(R_i.x, s'_i)`
+    ///
+    /// # Errors
+    /// Returns `Error::Crypto` if the effective nonce is invalid or signing fails
+    pub fn round3_pre_sign(
+        &self,
+        message: &[u8; 32],
+        secret_key: &SecretKey,
+        secret_nonce: &SecretKey,
+        agg_nonce: &PublicKey,
+        adaptor_point: &PublicKey,
+        agg_pubkey: &PublicKey,
+    ) -> Result<PartialSignature> {
+        let nonce = self.adaptor_nonce(agg_nonce, adaptor_point)?;
+        self.round3_partial_sign(message, secret_key, secret_nonce, &nonce, agg_pubkey)
+    }
+
+    /// Aggregate partial pre-signatures into a pre-signature
+    ///
+    /// Every partial pre-signature is verified first, ordered like the
+    /// session's participants.
+    ///
+    /// # Arguments
+    /// * `message` - Signed message (32 bytes)
+    /// * `partials` - Partial pre-signatures from Round 3
+    /// * `revealed_nonces` - Public nonces revealed in Round 2
+    /// * `agg_nonce` - Aggregated nonce from Round 2
+    /// * `adaptor_point` - Adaptor point `T`
+    ///
+    /// # Errors
+    /// Returns `Error::PartialSignatureCount` if there is not exactly one
+    /// partial pre-signature and one nonce pair per participant
+    /// Returns `Error::InvalidPartialSignature` if any partial pre-signature fails to verify
+    /// Returns `Error::Crypto` if the aggregated pre-signature does not verify
+    pub fn aggregate_pre_signatures(
+        &self,
+        message: &[u8; 32],
+        partials: &[PartialSignature],
+        revealed_nonces: &[(PublicKey, PublicKey)],
+        agg_nonce: &PublicKey,
+        adaptor_point: &PublicKey,
+    ) -> Result<PreSignature> {
+        let agg_pubkey = self.aggregate_pubkeys()?;
+        let participants = self.participants();
+        if partials.len() != participants.len() || revealed_nonces.len() != participants.len() {
+            return Err(Error::PartialSignatureCount {
+                expected: participants.len(),
+                actual: partials.len().min(revealed_nonces.len()),
+            });
+        }
+
+        let nonce = self.adaptor_nonce(agg_nonce, adaptor_point)?;
+        for (i, participant) in participants.iter().enumerate() {
+            if !self.verify_partial_signature(
+                message,
+                &partials[i],
+                participant.public_key(),
+                &revealed_nonces[i],
+                &nonce,
+                &agg_pubkey,
+            ) {
+                return Err(Error::InvalidPartialSignature {
+                    participant_index: i,
+                });
+            }
+        }
+
+        let signatures = partials
+            .iter()
+            .map(|partial| Signature::from_raw_data(partial.as_bytes()))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let signature =
+            aggsig::add_signatures_single(self.secp(), signatures.iter().collect(), &nonce)?;
+        let pre_signature = PreSignature {
+            agg_nonce: *agg_nonce,
+            adaptor_point: *adaptor_point,
+            nonce,
+            signature,
+        };
+
+        if !self.verify_pre_signature(message, &pre_signature, &agg_pubkey) {
+            return Err(Error::Crypto(
+                "aggregated pre-signature does not verify".into(),
+            ));
+        }
+        Ok(pre_signature)
+    }
+
+    /// Verify a pre-signature: `s' * G = ±R + c * X` with `c = H(R + T, X, m)`
+    ///
+    /// A valid pre-signature completes into a valid signature for `pubkey`
+    /// with exactly the discrete logarithm of its adaptor point.
+    ///
+    /// # Arguments
+    /// * `message` - Signed message (32 bytes)
+    /// * `pre_signature` - Pre-signature to verify
+    /// * `pubkey` - Public key the completed signature will be for
+    pub fn verify_pre_signature(
+        &self,
+        message: &[u8; 32],
+        pre_signature: &PreSignature,
+        pubkey: &PublicKey,
+    ) -> bool {
+        let secp = self.secp();
+        let (Ok(msg), Ok(nonce), Ok(square)) = (
+            Message::from_slice(message),
+            self.adaptor_nonce(&pre_signature.agg_nonce, &pre_signature.adaptor_point),
+            has_square_y(secp, &pre_signature.nonce),
+        ) else {
+            return false;
+        };
+        if nonce != pre_signature.nonce {
+            return false;
+        }
+
+        // s' * G - c * X - extra must be the signing nonce ±(R + T); with
+        // extra = ∓T that pins s' to ±R, the only form that completes with t
+        let mut extra = pre_signature.adaptor_point;
+        if square && extra.mul_assign(secp, &minus_one(secp)).is_err() {
+            return false;
+        }
+        aggsig::verify_single(
+            secp,
+            &pre_signature.signature,
+            &msg,
+            Some(&pre_signature.nonce),
+            pubkey,
+            Some(pubkey),
+            Some(&extra),
+            false,
+        )
+    }
+
+    /// Complete a pre-signature with the adaptor secret `t`
+    ///
+    /// # Returns
+    /// Signature `(R'.x, s' ± t)`, valid wherever a plain session's signature is
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidAdaptorSecret` if `t * G` is not the adaptor point
+    /// Returns `Error::Crypto` if the signature cannot be assembled
+    pub fn complete_pre_signature(
+        &self,
+        pre_signature: &PreSignature,
+        adaptor_secret: &SecretKey,
+    ) -> Result<Signature> {
+        let secp = self.secp();
+        if PublicKey::from_secret_key(secp, adaptor_secret)? != pre_signature.adaptor_point {
+            return Err(Error::InvalidAdaptorSecret);
+        }
+
+        let mut tweak = adaptor_secret.clone();
+        if !has_square_y(secp, &pre_signature.nonce)? {
+            tweak.neg_assign(secp)?;
+        }
+        let raw = pre_signature.signature.to_raw_data();
+        let s = scalar::add(secp, &SecretKey::from_slice(secp, &raw[32..])?, &tweak)?;
+
+        let mut completed = raw;
+        completed[32..].copy_from_slice(&s[..]);
+        Ok(Signature::from_raw_data(&completed)?)
+    }
+
+    /// Extract the adaptor secret from a pre-signature and its completion
+    ///
+    /// # Returns
+    /// The secret `t` with `t * G = T`
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidAdaptorSecret` if `signature` is not a completion
+    /// of `pre_signature`
+    pub fn extract_adaptor_secret(
+        &self,
+        pre_signature: &PreSignature,
+        signature: &Signature,
+    ) -> Result<SecretKey> {
+        let secp = self.secp();
+        let pre = pre_signature.signature.to_raw_data();
+        let completed = signature.to_raw_data();
+        if pre[..32] != completed[..32] {
+            return Err(Error::InvalidAdaptorSecret);
+        }
+
+        let s = SecretKey::from_slice(secp, &completed[32..])?;
+        let s_pre = SecretKey::from_slice(secp, &pre[32..])?;
+        let secret = if has_square_y(secp, &pre_signature.nonce)? {
+            scalar::sub(secp, &s, &s_pre)
+        } else {
+            scalar::sub(secp, &s_pre, &s)
+        }
+        .map_err(|_| Error::InvalidAdaptorSecret)?;
+
+        if PublicKey::from_secret_key(secp, &secret)? != pre_signature.adaptor_point {
+            return Err(Error::InvalidAdaptorSecret);
+        }
+        Ok(secret)
+    }
+}
+
+/// The scalar `-1`
+fn minus_one(secp: &Secp256k1) -> SecretKey {
+    let mut one = scalar::from_u32(secp, 1).expect("1 is a valid scalar");
+    one.neg_assign(secp).expect("-1 is a valid scalar");
+    one
+}
+
+/// Whether `point` has a quadratic-residue y, i.e. is used as-is as a nonce
+///
+/// `aggsig` does not expose the check, so it is read off the signer: with
+/// key 1, nonces 1 and 2 sign to `±1 + c` and `±2 + c` for the same `c`, and
+/// their difference is `+1` exactly when the nonce is not negated.
+fn has_square_y(secp: &Secp256k1, point: &PublicKey) -> Result<bool> {
+    let msg = Message::from_slice(&[0u8; 32])?;
+    let one = scalar::from_u32(secp, 1)?;
+    let two = scalar::from_u32(secp, 2)?;
+    let sign = |nonce: &SecretKey| -> Result<SecretKey> {
+        let signature = aggsig::sign_single(
+            secp,
+            &msg,
+            &one,
+            Some(nonce),
+            None,
+            Some(point),
+            Some(point),
+            Some(point),
+        )?;
+        Ok(SecretKey::from_slice(secp, &signature.to_raw_data()[32..])?)
+    };
+    Ok(scalar::sub(secp, &sign(&two)?, &sign(&one)?)? == one)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{keyed_session, sign_all};
+    use rand::thread_rng;
+
+    #[test]
+    fn test_complete_and_extract() {
+        let secp = Secp256k1::new();
+        let (session, secret_keys) = keyed_session(&secp, 3);
+        let agg_pubkey = session.aggregate_pubkeys().unwrap();
+        let message = [0x5au8; 32];
+
+        // Enough runs that both nonce parities are exercised
+        for _ in 0..8 {
+            let t = SecretKey::new(&secp, &mut thread_rng());
+            let adaptor_point = PublicKey::from_secret_key(&secp, &t).unwrap();
+            let (partials, revealed, agg_nonce) =
+                sign_all(&session, &secret_keys, &message, Some(&adaptor_point));
+
+            let pre_signature = session
+                .aggregate_pre_signatures(
+                    &message,
+                    &partials,
+                    &revealed,
+                    &agg_nonce,
+                    &adaptor_point,
+                )
+                .unwrap();
+            assert!(session.verify_pre_signature(&message, &pre_signature, &agg_pubkey));
+            assert!(!session.verify_signature(&message, pre_signature.signature(), &agg_pubkey));
+
+            let signature = session.complete_pre_signature(&pre_signature, &t).unwrap();
+            assert!(session.verify_signature(&message, &signature, &agg_pubkey));
+            assert_eq!(
+                session
+                    .extract_adaptor_secret(&pre_signature, &signature)
+                    .unwrap(),
+                t
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_partial_pre_signature_detected() {
+        let secp = Secp256k1::new();
+        let (session, secret_keys) = keyed_session(&secp, 2);
+        let message = [0x5bu8; 32];
+        let t = SecretKey::new(&secp, &mut thread_rng());
+        let adaptor_point = PublicKey::from_secret_key(&secp, &t).unwrap();
+        let (partials, revealed, agg_nonce) =
+            sign_all(&session, &secret_keys, &message, Some(&adaptor_point));
+
+        // A partial signature on the plain nonce is not a pre-signature
+        let other =
+            PublicKey::from_secret_key(&secp, &SecretKey::new(&secp, &mut thread_rng())).unwrap();
+        assert_eq!(
+            session
+                .aggregate_pre_signatures(&message, &partials, &revealed, &agg_nonce, &other)
+                .unwrap_err(),
+            Error::InvalidPartialSignature {
+                participant_index: 0
+            }
+        );
+
+        let mut forged = partials;
+        let mut bytes = *forged[1].as_bytes();
+        bytes[63] ^= 1;
+        forged[1] = PartialSignature::new(bytes);
+        assert_eq!(
+            session
+                .aggregate_pre_signatures(&message, &forged, &revealed, &agg_nonce, &adaptor_point)
+                .unwrap_err(),
+            Error::InvalidPartialSignature {
+                participant_index: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_wrong_secret_rejected() {
+        let secp = Secp256k1::new();
+        let (session, secret_keys) = keyed_session(&secp, 2);
+        let message = [0x5cu8; 32];
+        let t = SecretKey::new(&secp, &mut thread_rng());
+        let adaptor_point = PublicKey::from_secret_key(&secp, &t).unwrap();
+        let (partials, revealed, agg_nonce) =
+            sign_all(&session, &secret_keys, &message, Some(&adaptor_point));
+        let pre_signature = session
+            .aggregate_pre_signatures(&message, &partials, &revealed, &agg_nonce, &adaptor_point)
+            .unwrap();
+
+        let wrong = SecretKey::new(&secp, &mut thread_rng());
+        assert_eq!(
+            session
+                .complete_pre_signature(&pre_signature, &wrong)
+                .unwrap_err(),
+            Error::InvalidAdaptorSecret
+        );
+
+        // A signature on another nonce reveals nothing about t
+        let (partials, revealed, agg_nonce) =
+            sign_all(&session, &secret_keys, &message, Some(&adaptor_point));
+        let other = session
+            .aggregate_pre_signatures(&message, &partials, &revealed, &agg_nonce, &adaptor_point)
+            .unwrap();
+        let signature = session.complete_pre_signature(&other, &t).unwrap();
+        assert_eq!(
+            session
+                .extract_adaptor_secret(&pre_signature, &signature)
+                .unwrap_err(),
+            Error::InvalidAdaptorSecret
+        );
+    }
+}
@@ -0,0 +1,563 @@
+//! Identifiable abort for threshold signing
+//!
+//! Every signer signs its raw signature share message with a long-term
+//! identity key before sending it. The coordinator checks every response
+//! before aggregating. If any signer misbehaved, the round aborts with a
+//! [`BlameProof`] naming every faulty signer together with its signed
+//! response, so the fault can be checked by anyone holding the group's public
+//! keys and the signers' identity keys:
+//!
+//! 1. **Malformed**: the signed message does not decode to a share from its
+//!    sender
+//! 2. **Invalid share**: the signed share does not verify against the signer's
+//!    verification share
+//!
+//! A signer that sends nothing, or nothing carrying a valid identity
+//! signature, is reported as missing. Silence cannot be proven to a third
+//! party, so missing signers are never part of a blame proof.
+//!
+//! # Algorithm
+//! ```text
+//! Signer i:     sigma_i = Sign(id_i, H("response" || package || i || msg_i))
+//! Coordinator:  no (msg_i, sigma_i) or sigma_i invalid -> missing
+//!               msg_i does not decode to a share from i  -> Malformed
+//!               share does not verify                     -> InvalidShare
+//! Third party:  check sigma_i under ID_i, then re-run the failing check
+//! ```
+
+use std::collections::BTreeMap;
+
+use blake2::{Blake2b512, Digest};
+use secp256k1zkp::{aggsig, Message, PublicKey, Secp256k1, SecretKey, Signature};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::frost::{self, PublicKeyPackage, SignatureShare, SigningPackage};
+use crate::ser::{hex_bytes, hex_signature};
+use crate::types::ParticipantId;
+
+/// Domain separator of the identity signature over a response
+const RESPONSE_DOMAIN: &[u8] = b"grin-multisig/blame/response";
+
+/// A signer's raw share message, signed with its identity key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedResponse {
+    /// Raw message as sent
+    #[serde(with = "hex_bytes")]
+    message: Vec<u8>,
+
+    /// Identity signature over the message, the sender and the signing package
+    #[serde(with = "hex_signature")]
+    signature: Signature,
+}
+
+impl SignedResponse {
+    /// Sign a raw share message for a signing round
+    ///
+    /// # Arguments
+    /// * `secp` - Secp256k1 context
+    /// * `identity_key` - Sender's long-term identity secret key
+    /// * `participant` - Sender of the message
+    /// * `package` - Signing package of the round
+    /// * `message` - Raw message, normally a serialized [`SignatureShare`]
+    ///
+    /// # Errors
+    /// Returns `Error::Crypto` if signing fails
+    pub fn sign(
+        secp: &Secp256k1,
+        identity_key: &SecretKey,
+        participant: ParticipantId,
+        package: &SigningPackage,
+        message: Vec<u8>,
+    ) -> Result<Self> {
+        let msg = response_digest(package, participant, &message)?;
+        let identity = PublicKey::from_secret_key(secp, identity_key)?;
+        let signature = aggsig::sign_single(
+            secp,
+            &msg,
+            identity_key,
+            None,
+            None,
+            None,
+            Some(&identity),
+            None,
+        )?;
+        Ok(Self { message, signature })
+    }
+
+    /// Get the raw message
+    pub fn message(&self) -> &[u8] {
+        &self.message
+    }
+
+    /// Check the identity signature of `participant` over this response
+    fn is_signed_by(
+        &self,
+        secp: &Secp256k1,
+        participant: ParticipantId,
+        package: &SigningPackage,
+        identity: &PublicKey,
+    ) -> bool {
+        response_digest(package, participant, &self.message).is_ok_and(|msg| {
+            aggsig::verify_single(
+                secp,
+                &self.signature,
+                &msg,
+                None,
+                identity,
+                Some(identity),
+                None,
+                false,
+            )
+        })
+    }
+}
+
+/// Evidence against one signer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Evidence {
+    /// The signer's message could not be decoded as its signature share
+    Malformed {
+        /// Sender of the message
+        participant: ParticipantId,
+        /// Signed response as received
+        response: SignedResponse,
+    },
+    /// The signer's share does not verify
+    InvalidShare {
+        /// Sender of the share
+        participant: ParticipantId,
+        /// Signed response carrying the offending share
+        response: SignedResponse,
+    },
+}
+
+impl Evidence {
+    /// Get the signer the evidence is against
+    pub const fn participant(&self) -> ParticipantId {
+        match self {
+            Self::Malformed { participant, .. } | Self::InvalidShare { participant, .. } => {
+                *participant
+            }
+        }
+    }
+
+    /// Get the signed response the evidence rests on
+    pub const fn response(&self) -> &SignedResponse {
+        match self {
+            Self::Malformed { response, .. } | Self::InvalidShare { response, .. } => response,
+        }
+    }
+
+    /// Check this piece of evidence
+    fn verify(
+        &self,
+        secp: &Secp256k1,
+        package: &SigningPackage,
+        public_keys: &PublicKeyPackage,
+        identities: &BTreeMap<ParticipantId, PublicKey>,
+    ) -> Result<()> {
+        let participant = self.participant();
+        package.signer_commitments(participant)?;
+        let identity = identities
+            .get(&participant)
+            .ok_or(Error::UnknownParticipant { participant })?;
+
+        let response = self.response();
+        let holds = response.is_signed_by(secp, participant, package, identity)
+            && match (self, decode_share(participant, &response.message)) {
+                (Self::Malformed { .. }, share) => share.is_none(),
+                (Self::InvalidShare { .. }, Some(share)) => matches!(
+                    frost::verify_signature_share(secp, package, &share, public_keys),
+                    Err(Error::InvalidSignatureShare { .. })
+                ),
+                (Self::InvalidShare { .. }, None) => false,
+            };
+        if holds {
+            Ok(())
+        } else {
+            Err(Error::InvalidBlameProof { participant })
+        }
+    }
+}
+
+/// Transferable proof of which signers caused a signing round to abort
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlameProof {
+    /// Signing package of the failed round
+    package: SigningPackage,
+
+    /// Evidence against every faulty signer, in signer order
+    evidence: Vec<Evidence>,
+}
+
+impl BlameProof {
+    /// Get the signing package of the failed round
+    pub const fn package(&self) -> &SigningPackage {
+        &self.package
+    }
+
+    /// Get the evidence against every faulty signer
+    pub fn evidence(&self) -> &[Evidence] {
+        &self.evidence
+    }
+
+    /// Get the IDs of every faulty signer
+    pub fn faulty(&self) -> Vec<ParticipantId> {
+        self.evidence.iter().map(Evidence::participant).collect()
+    }
+
+    /// Check every piece of evidence against the group's public keys
+    ///
+    /// # Arguments
+    /// * `secp` - Secp256k1 context
+    /// * `public_keys` - Group's public keys
+    /// * `identities` - Every signer's identity public key
+    ///
+    /// # Errors
+    /// Returns `Error::NoParticipants` if the proof accuses nobody
+    /// Returns `Error::UnknownParticipant` if an accused is not a signer or
+    /// has no identity key
+    /// Returns `Error::InvalidBlameProof` naming the first wrongly accused signer
+    pub fn verify(
+        &self,
+        secp: &Secp256k1,
+        public_keys: &PublicKeyPackage,
+        identities: &BTreeMap<ParticipantId, PublicKey>,
+    ) -> Result<()> {
+        if self.evidence.is_empty() {
+            return Err(Error::NoParticipants);
+        }
+        self.evidence
+            .iter()
+            .try_for_each(|e| e.verify(secp, &self.package, public_keys, identities))
+    }
+}
+
+/// Outcome of a signing round
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// Every signer behaved; the group signature
+    Signature(Signature),
+    /// At least one signer misbehaved or did not respond
+    Aborted {
+        /// Proof against the signers that sent bad responses, if any
+        proof: Option<BlameProof>,
+        /// Signers without a validly signed response, which cannot be proven
+        missing: Vec<ParticipantId>,
+    },
+}
+
+/// Check every signer's response and aggregate, or blame the faulty signers
+///
+/// # Arguments
+/// * `secp` - Secp256k1 context
+/// * `package` - Signing package of the round
+/// * `responses` - Signed share messages, keyed by claimed sender
+/// * `public_keys` - Group's public keys
+/// * `identities` - Every signer's identity public key
+///
+/// # Returns
+/// The group signature, or the missing signers and a blame proof naming
+/// every faulty one
+///
+/// # Errors
+/// Returns `Error::NotEnoughSigners` if the package has fewer than `t` signers
+/// Returns `Error::UnknownParticipant` if a signer is not a group member or
+/// has no identity key
+pub fn aggregate(
+    secp: &Secp256k1,
+    package: &SigningPackage,
+    responses: &BTreeMap<ParticipantId, SignedResponse>,
+    public_keys: &PublicKeyPackage,
+    identities: &BTreeMap<ParticipantId, PublicKey>,
+) -> Result<Outcome> {
+    let signers = package.signers();
+    if signers.len() < public_keys.min_signers() {
+        return Err(Error::NotEnoughSigners {
+            threshold: public_keys.min_signers(),
+            actual: signers.len(),
+        });
+    }
+
+    let mut shares = Vec::with_capacity(signers.len());
+    let mut evidence = Vec::new();
+    let mut missing = Vec::new();
+    for participant in signers {
+        let identity = identities
+            .get(&participant)
+            .ok_or(Error::UnknownParticipant { participant })?;
+        let Some(response) = responses
+            .get(&participant)
+            .filter(|r| r.is_signed_by(secp, participant, package, identity))
+        else {
+            missing.push(participant);
+            continue;
+        };
+        let Some(share) = decode_share(participant, &response.message) else {
+            evidence.push(Evidence::Malformed {
+                participant,
+                response: response.clone(),
+            });
+            continue;
+        };
+        match frost::verify_signature_share(secp, package, &share, public_keys) {
+            Ok(()) => shares.push(share),
+            Err(Error::InvalidSignatureShare { .. }) => {
+                evidence.push(Evidence::InvalidShare {
+                    participant,
+                    response: response.clone(),
+                });
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    if evidence.is_empty() && missing.is_empty() {
+        frost::aggregate(secp, package, &shares, public_keys).map(Outcome::Signature)
+    } else {
+        let proof = (!evidence.is_empty()).then(|| BlameProof {
+            package: package.clone(),
+            evidence,
+        });
+        Ok(Outcome::Aborted { proof, missing })
+    }
+}
+
+/// Message the identity key signs for a response
+fn response_digest(
+    package: &SigningPackage,
+    participant: ParticipantId,
+    message: &[u8],
+) -> Result<Message> {
+    let mut hasher = Blake2b512::new();
+    hasher.update(RESPONSE_DOMAIN);
+    hasher.update(serde_json::to_vec(package)?);
+    hasher.update(participant.inner().to_be_bytes());
+    hasher.update(message);
+    Ok(Message::from_slice(&hasher.finalize()[..32])?)
+}
+
+/// Decode a share message, requiring it to come from `participant`
+fn decode_share(participant: ParticipantId, message: &[u8]) -> Option<SignatureShare> {
+    serde_json::from_slice::<SignatureShare>(message)
+        .ok()
+        .filter(|share| share.participant() == participant)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frost::{KeyPackage, SigningNonces};
+    use crate::test_util::split;
+    use rand::thread_rng;
+
+    const MESSAGE: [u8; 32] = [2u8; 32];
+
+    /// Signers of a 3-of-5 key, ready to respond to one round
+    struct Round {
+        keys: Vec<KeyPackage>,
+        identity_keys: Vec<SecretKey>,
+        identities: BTreeMap<ParticipantId, PublicKey>,
+        nonces: Vec<Option<SigningNonces>>,
+        package: SigningPackage,
+        public_keys: PublicKeyPackage,
+    }
+
+    impl Round {
+        /// Split a 3-of-5 key and run round 1 for every member
+        fn new(secp: &Secp256k1) -> Self {
+            let (keys, public_keys) = split(secp, 3, 5);
+            let identity_keys: Vec<_> = keys
+                .iter()
+                .map(|_| SecretKey::new(secp, &mut thread_rng()))
+                .collect();
+            let identities = keys
+                .iter()
+                .zip(&identity_keys)
+                .map(|(k, sk)| {
+                    let pk = PublicKey::from_secret_key(secp, sk).unwrap();
+                    (k.participant(), pk)
+                })
+                .collect();
+            let nonces: Vec<_> = keys
+                .iter()
+                .map(|k| frost::commit(secp, k).unwrap())
+                .collect();
+            let package =
+                SigningPackage::new(MESSAGE, nonces.iter().map(|n| *n.commitments()).collect())
+                    .unwrap();
+            let nonces = nonces.into_iter().map(Some).collect();
+            Self {
+                keys,
+                identity_keys,
+                identities,
+                nonces,
+                package,
+                public_keys,
+            }
+        }
+
+        /// Sign raw bytes as member `i`
+        fn send(&self, secp: &Secp256k1, i: usize, message: Vec<u8>) -> SignedResponse {
+            let participant = self.keys[i].participant();
+            SignedResponse::sign(
+                secp,
+                &self.identity_keys[i],
+                participant,
+                &self.package,
+                message,
+            )
+            .unwrap()
+        }
+
+        /// Member `i`'s share over `message` with its round commitments,
+        /// spending its nonces
+        fn respond(&mut self, secp: &Secp256k1, i: usize, message: [u8; 32]) -> SignedResponse {
+            let package =
+                SigningPackage::new(message, self.package.commitments().to_vec()).unwrap();
+            let nonces = self.nonces[i].take().expect("nonces sign once");
+            let share = frost::sign(secp, &package, nonces, &self.keys[i]).unwrap();
+            self.send(secp, i, serde_json::to_vec(&share).unwrap())
+        }
+
+        /// Responses of `members`, each signing `message`
+        fn responses(
+            &mut self,
+            secp: &Secp256k1,
+            members: &[(usize, [u8; 32])],
+        ) -> BTreeMap<ParticipantId, SignedResponse> {
+            members
+                .iter()
+                .map(|(i, message)| {
+                    (
+                        self.keys[*i].participant(),
+                        self.respond(secp, *i, *message),
+                    )
+                })
+                .collect()
+        }
+
+        fn aggregate(
+            &self,
+            secp: &Secp256k1,
+            responses: &BTreeMap<ParticipantId, SignedResponse>,
+        ) -> Outcome {
+            aggregate(
+                secp,
+                &self.package,
+                responses,
+                &self.public_keys,
+                &self.identities,
+            )
+            .unwrap()
+        }
+    }
+
+    #[test]
+    fn test_honest_round_aggregates() {
+        let secp = Secp256k1::new();
+        let mut round = Round::new(&secp);
+        let honest: Vec<_> = (0..5).map(|i| (i, MESSAGE)).collect();
+        let responses = round.responses(&secp, &honest);
+
+        assert!(matches!(
+            round.aggregate(&secp, &responses),
+            Outcome::Signature(_)
+        ));
+    }
+
+    #[test]
+    fn test_every_fault_identified() {
+        let secp = Secp256k1::new();
+        let mut round = Round::new(&secp);
+
+        // Signer 2 signs another message, 4 sends garbage, 5 stays silent
+        // and a forger impersonates 3 without its identity key
+        let mut responses = round.responses(&secp, &[(0, MESSAGE), (1, [9u8; 32]), (2, MESSAGE)]);
+        responses.insert(
+            ParticipantId::new(4),
+            round.send(&secp, 3, b"not a share".to_vec()),
+        );
+        let forged = responses[&ParticipantId::new(3)].message().to_vec();
+        responses.insert(ParticipantId::new(3), round.send(&secp, 0, forged));
+
+        let Outcome::Aborted {
+            proof: Some(proof),
+            missing,
+        } = round.aggregate(&secp, &responses)
+        else {
+            panic!("round should abort with a proof");
+        };
+        assert_eq!(missing, vec![ParticipantId::new(3), ParticipantId::new(5)]);
+        assert_eq!(
+            proof.faulty(),
+            vec![ParticipantId::new(2), ParticipantId::new(4)]
+        );
+        assert!(matches!(proof.evidence()[0], Evidence::InvalidShare { .. }));
+        assert!(matches!(proof.evidence()[1], Evidence::Malformed { .. }));
+
+        // A third party checks the transported proof with public keys alone
+        let transported: BlameProof =
+            serde_json::from_str(&serde_json::to_string(&proof).unwrap()).unwrap();
+        assert!(transported
+            .verify(&secp, &round.public_keys, &round.identities)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_false_accusation_rejected() {
+        let secp = Secp256k1::new();
+        let mut round = Round::new(&secp);
+        let honest = round.respond(&secp, 0, MESSAGE);
+        // Signer 3's share of another message, to pass off as signer 1's
+        let bad_share = round.respond(&secp, 2, [9u8; 32]).message().to_vec();
+        let p1 = ParticipantId::new(1);
+        let reject = |evidence: Evidence| {
+            let framed = BlameProof {
+                package: round.package.clone(),
+                evidence: vec![evidence],
+            };
+            framed
+                .verify(&secp, &round.public_keys, &round.identities)
+                .unwrap_err()
+        };
+
+        // A valid share relabelled as invalid or malformed
+        for evidence in [
+            Evidence::InvalidShare {
+                participant: p1,
+                response: honest.clone(),
+            },
+            Evidence::Malformed {
+                participant: p1,
+                response: honest,
+            },
+        ] {
+            assert_eq!(
+                reject(evidence),
+                Error::InvalidBlameProof { participant: p1 }
+            );
+        }
+
+        // Garbage or a bad share that signer 1 never signed
+        for (message, malformed) in [(b"not a share".to_vec(), true), (bad_share, false)] {
+            let response = round.send(&secp, 1, message);
+            let evidence = if malformed {
+                Evidence::Malformed {
+                    participant: p1,
+                    response,
+                }
+            } else {
+                Evidence::InvalidShare {
+                    participant: p1,
+                    response,
+                }
+            };
+            assert_eq!(
+                reject(evidence),
+                Error::InvalidBlameProof { participant: p1 }
+            );
+        }
+    }
+}
@@ -0,0 +1,954 @@
+//! Two-party payment channels on a shared output
+//!
+//! A channel is a [`SharedOutput`] of both parties whose split is updated off
+//! chain. Every state has two versions, one per party, and each party only
+//! ever holds its own. A version is a pair of co-signed transactions: a close
+//! moving the funds into a state output, and a settle paying out the balances.
+//! Both are signed in the same kernel session, so they carry the same kernel
+//! excess; their `NoRecentDuplicate` kernels make the settle invalid until
+//! `delay` blocks after the close.
+//!
+//! The state output of a party's version is held by that party's per-state
+//! revocation key and the other party's funding key. Moving to a new state,
+//! each party reveals the revocation key of its previous version. Should it
+//! later broadcast that revoked close anyway, the other party holds both keys
+//! of the state output and sweeps it before the settle's delay runs out.
+//!
+//! # Algorithm
+//! ```text
+//! Funding:  F = v*H + X(P_1, P_2)
+//! State n, version of owner o (other party p):
+//!   S      = (v - fee)*H + X(R_o,n, P_p)
+//!   close  = F -> S,            NRD { relative_height: 1 }       excess E
+//!   settle = S -> balances,     NRD { relative_height: delay }   excess E
+//! Update:   co-sign both versions of n + 1, then reveal r_o,n
+//! Cheat:    close of revoked n on chain -> p sweeps S with r_o,n and its key
+//! Close:    cooperative F -> balances (Plain), or unilateral close + settle
+//! ```
+//!
+//! State 0 plays the role of the funding refund and must be committed before
+//! the funding transaction is signed. The [`Channel`] holds secret revocation
+//! keys, so its persisted form must be stored like a wallet seed.
+
+use rand::thread_rng;
+use secp256k1zkp::pedersen::RangeProof;
+use secp256k1zkp::{ContextFlag, PublicKey, Secp256k1, SecretKey};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::offset::OffsetShare;
+use crate::participant::Participant;
+use crate::ser::{hex_pubkey, hex_seckey};
+use crate::session::Session;
+use crate::spend::{SharedOutput, SpendBuilder};
+use crate::transaction::{KernelFeatures, Output, OutputFeatures, Transaction};
+use crate::validation::validate;
+
+/// Side of the channel a party is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Role {
+    /// First participant of the funding session
+    Initiator,
+    /// Second participant of the funding session
+    Responder,
+}
+
+impl Role {
+    /// Get the other side
+    #[must_use]
+    pub const fn other(self) -> Self {
+        match self {
+            Self::Initiator => Self::Responder,
+            Self::Responder => Self::Initiator,
+        }
+    }
+
+    /// Position in the funding session and in balances
+    pub const fn index(self) -> usize {
+        match self {
+            Self::Initiator => 0,
+            Self::Responder => 1,
+        }
+    }
+}
+
+/// Numbered split of the channel's funds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelState {
+    /// State number, starting at 0
+    number: u64,
+
+    /// Balances of initiator and responder
+    balances: [u64; 2],
+}
+
+impl ChannelState {
+    /// Get the state number
+    pub const fn number(&self) -> u64 {
+        self.number
+    }
+
+    /// Get the balances of initiator and responder
+    pub const fn balances(&self) -> [u64; 2] {
+        self.balances
+    }
+}
+
+/// Revealed revocation key of a superseded state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Revocation {
+    /// Revoked state number
+    number: u64,
+
+    /// Revocation key of the sender's version of that state
+    #[serde(with = "hex_seckey")]
+    secret: SecretKey,
+}
+
+impl Revocation {
+    /// Get the revoked state number
+    pub const fn number(&self) -> u64 {
+        self.number
+    }
+}
+
+/// Transactions to co-sign for one party's version of a state
+#[derive(Debug, Clone)]
+pub struct CommitmentBuilder {
+    /// State the version pays out
+    state: ChannelState,
+
+    /// Output the close creates
+    state_output: SharedOutput,
+
+    /// Funding output to state output
+    close: SpendBuilder,
+
+    /// State output to balances
+    settle: SpendBuilder,
+}
+
+impl CommitmentBuilder {
+    /// Get the state the version pays out
+    pub const fn state(&self) -> &ChannelState {
+        &self.state
+    }
+
+    /// Get the state output, to derive each holder's blinding
+    pub const fn state_output(&self) -> &SharedOutput {
+        &self.state_output
+    }
+
+    /// Get the close, with [`SharedOutput::spend_blinding`] of the funding output
+    pub const fn close(&self) -> &SpendBuilder {
+        &self.close
+    }
+
+    /// Get the settle, signed in the close's kernel session
+    pub const fn settle(&self) -> &SpendBuilder {
+        &self.settle
+    }
+}
+
+/// This party's version of the latest state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LocalCommitment {
+    /// Co-signed close
+    close: Transaction,
+
+    /// Co-signed settle
+    settle: Transaction,
+
+    /// Revocation key, revealed once the state is superseded
+    #[serde(with = "hex_seckey")]
+    revocation_key: SecretKey,
+}
+
+/// The other party's version of a state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RemoteCommitment {
+    /// State number
+    number: u64,
+
+    /// Other party's revocation key for the state
+    #[serde(with = "hex_pubkey")]
+    revocation_key: PublicKey,
+
+    /// State output its close creates
+    output: SharedOutput,
+}
+
+/// The other party's version of a revoked state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RevokedCommitment {
+    /// State number
+    number: u64,
+
+    /// Revealed revocation key
+    #[serde(with = "hex_seckey")]
+    revocation_key: SecretKey,
+
+    /// State output its close creates
+    output: SharedOutput,
+}
+
+/// One party's view of a payment channel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Channel {
+    /// Side this party is on
+    role: Role,
+
+    /// Funding keys of initiator and responder
+    participants: Vec<Participant>,
+
+    /// Funding output
+    funding: SharedOutput,
+
+    /// Fee of every channel transaction
+    fee: u64,
+
+    /// Blocks between a close and its settle
+    delay: u16,
+
+    /// Latest committed state
+    state: Option<ChannelState>,
+
+    /// This party's version of the latest state
+    local: Option<LocalCommitment>,
+
+    /// Other party's version of the latest state
+    remote: Option<RemoteCommitment>,
+
+    /// Other party's previous version, until it is revoked
+    unrevoked: Option<RemoteCommitment>,
+
+    /// Other party's revoked versions
+    revoked: Vec<RevokedCommitment>,
+}
+
+impl Channel {
+    /// Open a channel on the output of a funding session
+    ///
+    /// # Arguments
+    /// * `role` - Side this party is on
+    /// * `funding_session` - Session over both funding keys, initiator first
+    /// * `value` - Value of the funding output
+    /// * `delay` - Blocks between a unilateral close and its settle
+    /// * `fee` - Fee of every channel transaction
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidChannel` if the session does not have two
+    /// participants, `delay` is 0 or the value does not cover the fees
+    /// Returns `Error::Crypto` if the funding commitment cannot be created
+    pub fn new(
+        role: Role,
+        funding_session: &Session,
+        value: u64,
+        delay: u16,
+        fee: u64,
+    ) -> Result<Self> {
+        if funding_session.participant_count() != 2 {
+            return Err(Error::InvalidChannel("a channel has two parties".into()));
+        }
+        if delay == 0 {
+            return Err(Error::InvalidChannel("delay must be at least 1".into()));
+        }
+        if value < 2 * fee {
+            return Err(Error::InvalidChannel(
+                "value does not cover close and settle fees".into(),
+            ));
+        }
+        Ok(Self {
+            role,
+            participants: funding_session.participants().to_vec(),
+            funding: SharedOutput::from_session(funding_session, value)?,
+            fee,
+            delay,
+            state: None,
+            local: None,
+            remote: None,
+            unrevoked: None,
+            revoked: Vec::new(),
+        })
+    }
+
+    /// Get the side this party is on
+    pub const fn role(&self) -> Role {
+        self.role
+    }
+
+    /// Get the funding output
+    pub const fn funding_output(&self) -> &SharedOutput {
+        &self.funding
+    }
+
+    /// Get the latest committed state
+    pub const fn state(&self) -> Option<&ChannelState> {
+        self.state.as_ref()
+    }
+
+    /// Get the total the balances of every state add up to
+    pub const fn capacity(&self) -> u64 {
+        self.funding.value() - 2 * self.fee
+    }
+
+    /// Propose the next state
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidChannel` if the balances do not add up to the
+    /// capacity or the other party has not revoked its previous state
+    pub fn propose(&self, balances: [u64; 2]) -> Result<ChannelState> {
+        if self.unrevoked.is_some() {
+            return Err(Error::InvalidChannel(
+                "previous state is not revoked yet".into(),
+            ));
+        }
+        if balances[0].checked_add(balances[1]) != Some(self.capacity()) {
+            return Err(Error::InvalidChannel(format!(
+                "balances must add up to {}",
+                self.capacity()
+            )));
+        }
+        Ok(ChannelState {
+            number: self.state.map_or(0, |s| s.number + 1),
+            balances,
+        })
+    }
+
+    /// Session holding the state output of `owner`'s version
+    ///
+    /// The owner's funding key is replaced by its revocation key for the state.
+    pub fn state_session(&self, owner: Role, revocation_key: &PublicKey) -> Session {
+        let mut participants = self.participants.clone();
+        let id = participants[owner.index()].id();
+        participants[owner.index()] = Participant::new(id, *revocation_key);
+        Session::new(participants)
+    }
+
+    /// Build the close and settle of one party's version of a state
+    ///
+    /// This party's settle output must open to its balance. The other party's
+    /// output then holds exactly the other balance, since a valid settle
+    /// cannot create value.
+    ///
+    /// # Arguments
+    /// * `state` - State from [`Self::propose`]
+    /// * `state_session` - From [`Self::state_session`] for the version's owner
+    /// * `state_proof` - Range proof for the state output
+    /// * `settle_outputs` - Outputs paying the initiator's and responder's balances
+    /// * `output_blind` - Blinding factor of this party's settle output
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidChannel` if the state is not the next one or this
+    /// party's settle output does not pay its balance
+    /// Returns `Error::Crypto` if the state commitment cannot be created
+    pub fn commitment(
+        &self,
+        state: ChannelState,
+        state_session: &Session,
+        state_proof: RangeProof,
+        settle_outputs: [Output; 2],
+        output_blind: &SecretKey,
+    ) -> Result<CommitmentBuilder> {
+        self.check_next(state)?;
+        let index = self.role.index();
+        let own = state_session
+            .secp()
+            .commit(state.balances[index], output_blind.clone())?;
+        if *settle_outputs[index].commitment() != own {
+            return Err(Error::InvalidChannel(format!(
+                "settle output does not pay this party's balance of {}",
+                state.balances[index]
+            )));
+        }
+
+        let state_output = SharedOutput::from_session(state_session, self.state_value())?;
+        let close = SpendBuilder::new(
+            self.funding,
+            KernelFeatures::NoRecentDuplicate {
+                fee: self.fee,
+                relative_height: 1,
+            },
+        )
+        .output(Output::new(
+            OutputFeatures::Plain,
+            *state_output.commitment(),
+            state_proof,
+        ));
+        let [initiator, responder] = settle_outputs;
+        let settle = SpendBuilder::new(state_output, self.settle_features())
+            .output(initiator)
+            .output(responder);
+        Ok(CommitmentBuilder {
+            state,
+            state_output,
+            close,
+            settle,
+        })
+    }
+
+    /// Store both versions of the proposed state
+    ///
+    /// # Arguments
+    /// * `builder` - This party's version, from [`Self::commitment`]
+    /// * `close` - This party's co-signed close
+    /// * `settle` - This party's co-signed settle
+    /// * `revocation_key` - This party's revocation key for the state
+    /// * `remote_revocation_key` - Other party's revocation key for the state
+    ///
+    /// # Returns
+    /// Revocation of this party's previous version, to send to the other party
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidChannel` if the state is not the next one or the
+    /// transactions are not the builder's close and settle of this party's
+    /// version
+    /// Returns `Error::ValidationFailed` if a transaction does not validate
+    pub fn commit(
+        &mut self,
+        builder: &CommitmentBuilder,
+        close: Transaction,
+        settle: Transaction,
+        revocation_key: SecretKey,
+        remote_revocation_key: PublicKey,
+    ) -> Result<Option<Revocation>> {
+        let state = builder.state;
+        self.check_next(state)?;
+
+        let funding_session = Session::new(self.participants.clone());
+        let secp = funding_session.secp();
+        let local_key = PublicKey::from_secret_key(secp, &revocation_key)?;
+        let local_output = SharedOutput::from_session(
+            &self.state_session(self.role, &local_key),
+            self.state_value(),
+        )?;
+        let remote_output = SharedOutput::from_session(
+            &self.state_session(self.role.other(), &remote_revocation_key),
+            self.state_value(),
+        )?;
+
+        let closes = builder.state_output.commitment() == local_output.commitment()
+            && close.inputs() == [self.funding.input()]
+            && close.outputs() == builder.close.outputs()
+            && close.kernels().len() == 1;
+        let mut settle_outputs = builder.settle.outputs().to_vec();
+        settle_outputs.sort_by_cached_key(Output::hash);
+        let settles = settle.inputs() == [local_output.input()]
+            && settle.outputs() == settle_outputs
+            && settle.kernels().len() == 1
+            && *settle.kernels()[0].features() == self.settle_features();
+        if !closes || !settles || close.kernels()[0].excess() != settle.kernels()[0].excess() {
+            return Err(Error::InvalidChannel(format!(
+                "transactions are not this party's version of state {}",
+                state.number
+            )));
+        }
+        validate(secp, &close).into_result()?;
+        validate(secp, &settle).into_result()?;
+
+        let previous = self.local.replace(LocalCommitment {
+            close,
+            settle,
+            revocation_key,
+        });
+        self.unrevoked = self.remote.replace(RemoteCommitment {
+            number: state.number,
+            revocation_key: remote_revocation_key,
+            output: remote_output,
+        });
+        self.state = Some(state);
+        Ok(previous.map(|local| Revocation {
+            number: state.number - 1,
+            secret: local.revocation_key,
+        }))
+    }
+
+    /// Accept the other party's revocation of its previous version
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidChannel` if no state awaits revocation or the
+    /// key does not match it
+    pub fn revoke(&mut self, revocation: &Revocation) -> Result<()> {
+        let pending = self
+            .unrevoked
+            .as_ref()
+            .ok_or_else(|| Error::InvalidChannel("no state awaits revocation".into()))?;
+        let secp = Secp256k1::with_caps(ContextFlag::Commit);
+        if revocation.number != pending.number
+            || PublicKey::from_secret_key(&secp, &revocation.secret)? != pending.revocation_key
+        {
+            return Err(Error::InvalidChannel(format!(
+                "revocation does not match state {}",
+                pending.number
+            )));
+        }
+
+        self.revoked.push(RevokedCommitment {
+            number: pending.number,
+            revocation_key: revocation.secret.clone(),
+            output: pending.output,
+        });
+        self.unrevoked = None;
+        Ok(())
+    }
+
+    /// Get this party's close and settle of the latest state
+    ///
+    /// The settle can be mined `delay` blocks after the close.
+    pub fn unilateral_close(&self) -> Option<(&Transaction, &Transaction)> {
+        self.local.as_ref().map(|l| (&l.close, &l.settle))
+    }
+
+    /// Build the cooperative close of the latest state, paying out at once
+    ///
+    /// # Arguments
+    /// * `outputs` - Outputs paying the initiator's and responder's balances
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidChannel` if no state is committed yet
+    pub fn cooperative_close(&self, outputs: [Output; 2]) -> Result<SpendBuilder> {
+        if self.state.is_none() {
+            return Err(Error::InvalidChannel("no state committed yet".into()));
+        }
+        let [initiator, responder] = outputs;
+        Ok(
+            SpendBuilder::new(self.funding, KernelFeatures::Plain { fee: 2 * self.fee })
+                .output(initiator)
+                .output(responder),
+        )
+    }
+
+    /// Sweep the state output of a revoked close the other party broadcast
+    ///
+    /// # Arguments
+    /// * `published` - Transaction seen on chain
+    /// * `funding_key` - This party's funding key
+    /// * `output` - This party's output for the state value minus `fee`
+    /// * `output_blind` - Blinding factor of `output`
+    ///
+    /// # Returns
+    /// The signed sweep, or `None` if `published` is no revoked close
+    ///
+    /// # Errors
+    /// Returns `Error::UnbalancedTransaction` if `output` does not balance
+    /// Returns `Error::Crypto` if signing fails
+    pub fn penalty(
+        &self,
+        published: &Transaction,
+        funding_key: &SecretKey,
+        output: Output,
+        output_blind: &SecretKey,
+    ) -> Result<Option<Transaction>> {
+        let Some(revoked) = self.revoked.iter().find(|r| {
+            published
+                .outputs()
+                .iter()
+                .any(|o| o.commitment() == r.output.commitment())
+        }) else {
+            return Ok(None);
+        };
+
+        let secp = &Secp256k1::with_caps(ContextFlag::Commit);
+        let remote_key = PublicKey::from_secret_key(secp, &revoked.revocation_key)?;
+        let state_session = self.state_session(self.role.other(), &remote_key);
+        let blinding = revoked.output.spend_blinding(
+            &state_session,
+            funding_key,
+            &[revoked.output.spend_blinding(
+                &state_session,
+                &revoked.revocation_key,
+                std::slice::from_ref(output_blind),
+            )?],
+        )?;
+        let sweep = SpendBuilder::new(revoked.output, KernelFeatures::Plain { fee: self.fee })
+            .output(output);
+
+        // Both keys are ours now, so the sweep is signed in a session of one
+        let id = self.participants[self.role.index()].id();
+        let excess_key = SecretKey::new(secp, &mut thread_rng());
+        let kernel = Session::new(vec![Participant::new(
+            id,
+            PublicKey::from_secret_key(secp, &excess_key)?,
+        )]);
+        let share = OffsetShare::generate(&kernel, id, &excess_key, &blinding)?;
+        let round1 = kernel.round1_generate_nonces()?;
+        let revealed = [*round1.public_nonces()];
+        let agg_nonce = kernel.round2_aggregate_nonces(&[*round1.commitment()], &revealed)?;
+        let partial = kernel.round3_partial_sign(
+            &sweep.kernel_message(),
+            &excess_key,
+            round1.secret_nonce(),
+            &agg_nonce,
+            &kernel.aggregate_pubkeys()?,
+        )?;
+        sweep
+            .finalize(&kernel, &[share], &[partial], &revealed, &agg_nonce)
+            .map(Some)
+    }
+
+    /// Serialize the channel, secrets included, for persistence
+    ///
+    /// # Errors
+    /// Returns `Error::Serialization` if serialization fails
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Restore a persisted channel
+    ///
+    /// # Errors
+    /// Returns `Error::Serialization` if the JSON is malformed
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Check that `state` is the next state
+    fn check_next(&self, state: ChannelState) -> Result<()> {
+        if self.propose(state.balances)? == state {
+            Ok(())
+        } else {
+            Err(Error::InvalidChannel(format!(
+                "state {} is not the next state",
+                state.number
+            )))
+        }
+    }
+
+    const fn state_value(&self) -> u64 {
+        self.funding.value() - self.fee
+    }
+
+    const fn settle_features(&self) -> KernelFeatures {
+        KernelFeatures::NoRecentDuplicate {
+            fee: self.fee,
+            relative_height: self.delay,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockGrinChain;
+    use crate::swap::GrinChain;
+    use crate::test_util::{finalize_spend, keyed_session};
+
+    const VALUE: u64 = 1_000;
+    const FEE: u64 = 2;
+    const DELAY: u16 = 10;
+
+    /// Run a fresh two-party kernel session and finalize the spend
+    fn sign(secp: &Secp256k1, builder: &SpendBuilder, blindings: &[SecretKey]) -> Transaction {
+        let (kernel, excess_keys) = keyed_session(secp, 2);
+        finalize_spend(&kernel, &excess_keys, builder, blindings).unwrap()
+    }
+
+    /// Both parties' channels on a funding output already on chain
+    struct Setup {
+        secp: Secp256k1,
+        grin: MockGrinChain,
+        funding: Session,
+        funding_keys: Vec<SecretKey>,
+        channels: [Channel; 2],
+    }
+
+    impl Setup {
+        fn open() -> Self {
+            let secp = Secp256k1::with_caps(ContextFlag::Commit);
+            let (funding, funding_keys) = keyed_session(&secp, 2);
+            let channels = [Role::Initiator, Role::Responder]
+                .map(|role| Channel::new(role, &funding, VALUE, DELAY, FEE).unwrap());
+            let mut grin = MockGrinChain::new();
+            grin.add_output(*channels[0].funding_output().commitment());
+            let mut setup = Self {
+                secp,
+                grin,
+                funding,
+                funding_keys,
+                channels,
+            };
+            setup.update([VALUE - 2 * FEE, 0]);
+            setup
+        }
+
+        /// Fresh output for `value`, with its blinding factor
+        fn output(&self, value: u64) -> (Output, SecretKey) {
+            let blind = SecretKey::new(&self.secp, &mut thread_rng());
+            (Output::create(&self.secp, value, &blind).unwrap(), blind)
+        }
+
+        /// Co-sign `owner`'s version of `state`
+        fn sign_version(
+            &self,
+            owner: Role,
+            revocation_key: &SecretKey,
+            state: ChannelState,
+        ) -> (CommitmentBuilder, Transaction, Transaction) {
+            let outputs = state.balances().map(|b| self.output(b));
+            let proof = self.state_proof(owner, revocation_key);
+            self.sign_version_to(owner, revocation_key, state, &proof, outputs)
+        }
+
+        /// Both holders' keys of `owner`'s state output and their weighted shares
+        fn state_keys(
+            &self,
+            owner: Role,
+            revocation_key: &SecretKey,
+        ) -> (Session, [SecretKey; 2], [SecretKey; 2]) {
+            let public = PublicKey::from_secret_key(&self.secp, revocation_key).unwrap();
+            let state_session = self.channels[owner.index()].state_session(owner, &public);
+            let keys = [Role::Initiator, Role::Responder].map(|r| {
+                if r == owner {
+                    revocation_key.clone()
+                } else {
+                    self.funding_keys[r.index()].clone()
+                }
+            });
+            let weighted = keys
+                .clone()
+                .map(|k| state_session.weighted_secret_key(&k).unwrap());
+            (state_session, keys, weighted)
+        }
+
+        /// Stand-in for a joint range proof of `owner`'s state output
+        fn state_proof(&self, owner: Role, revocation_key: &SecretKey) -> RangeProof {
+            let (_, _, weighted) = self.state_keys(owner, revocation_key);
+            let state_blind = self.secp.blind_sum(weighted.to_vec(), vec![]).unwrap();
+            *Output::create(&self.secp, VALUE - FEE, &state_blind)
+                .unwrap()
+                .proof()
+        }
+
+        /// Co-sign `owner`'s version of `state` paying into `outputs`
+        fn sign_version_to(
+            &self,
+            owner: Role,
+            revocation_key: &SecretKey,
+            state: ChannelState,
+            proof: &RangeProof,
+            outputs: [(Output, SecretKey); 2],
+        ) -> (CommitmentBuilder, Transaction, Transaction) {
+            let channel = &self.channels[owner.index()];
+            let (state_session, keys, weighted) = self.state_keys(owner, revocation_key);
+            let [(initiator, initiator_blind), (responder, responder_blind)] = outputs;
+            let own_blind = [&initiator_blind, &responder_blind][owner.index()];
+            let builder = channel
+                .commitment(
+                    state,
+                    &state_session,
+                    *proof,
+                    [initiator, responder],
+                    own_blind,
+                )
+                .unwrap();
+
+            let close_blindings: Vec<_> = self
+                .funding_keys
+                .iter()
+                .zip(&weighted)
+                .map(|(k, w)| {
+                    channel
+                        .funding_output()
+                        .spend_blinding(&self.funding, k, std::slice::from_ref(w))
+                        .unwrap()
+                })
+                .collect();
+            let settle_blindings: Vec<_> = keys
+                .iter()
+                .zip([initiator_blind, responder_blind])
+                .map(|(k, b)| {
+                    builder
+                        .state_output()
+                        .spend_blinding(&state_session, k, &[b])
+                        .unwrap()
+                })
+                .collect();
+
+            // One kernel session for both, so close and settle share an excess
+            let (kernel, excess_keys) = keyed_session(&self.secp, 2);
+            let close =
+                finalize_spend(&kernel, &excess_keys, builder.close(), &close_blindings).unwrap();
+            let settle =
+                finalize_spend(&kernel, &excess_keys, builder.settle(), &settle_blindings).unwrap();
+            (builder, close, settle)
+        }
+
+        /// Move both channels to `balances` and exchange revocations
+        fn update(&mut self, balances: [u64; 2]) -> ChannelState {
+            let state = self.channels[0].propose(balances).unwrap();
+            assert_eq!(self.channels[1].propose(balances).unwrap(), state);
+
+            let revocation_keys = [0, 1].map(|_| SecretKey::new(&self.secp, &mut thread_rng()));
+            let public = revocation_keys
+                .clone()
+                .map(|k| PublicKey::from_secret_key(&self.secp, &k).unwrap());
+            let mut revocations = Vec::new();
+            for role in [Role::Initiator, Role::Responder] {
+                let i = role.index();
+                let (builder, close, settle) = self.sign_version(role, &revocation_keys[i], state);
+                let revocation = self.channels[i]
+                    .commit(
+                        &builder,
+                        close,
+                        settle,
+                        revocation_keys[i].clone(),
+                        public[role.other().index()],
+                    )
+                    .unwrap();
+                revocations.push((role.other(), revocation));
+            }
+            for (to, revocation) in revocations {
+                if let Some(revocation) = revocation {
+                    self.channels[to.index()].revoke(&revocation).unwrap();
+                }
+            }
+            state
+        }
+    }
+
+    #[test]
+    fn test_update_persist_and_cooperative_close() {
+        let mut setup = Setup::open();
+        setup.update([696, 300]);
+        let state = setup.update([596, 400]);
+        assert_eq!(state.number(), 2);
+
+        let json = setup.channels[1].to_json().unwrap();
+        let restored = Channel::from_json(&json).unwrap();
+        assert_eq!(restored.to_json().unwrap(), json);
+        assert_eq!(restored.state(), Some(&state));
+
+        assert!(matches!(
+            setup.channels[0].propose([600, 400]),
+            Err(Error::InvalidChannel(_))
+        ));
+
+        let (initiator, initiator_blind) = setup.output(596);
+        let (responder, responder_blind) = setup.output(400);
+        let close = setup.channels[0]
+            .cooperative_close([initiator, responder])
+            .unwrap();
+        let funding = setup.channels[0].funding_output();
+        let blindings: Vec<_> = setup
+            .funding_keys
+            .iter()
+            .zip([initiator_blind, responder_blind])
+            .map(|(k, b)| funding.spend_blinding(&setup.funding, k, &[b]).unwrap())
+            .collect();
+        let tx = sign(&setup.secp, &close, &blindings);
+        setup.grin.post(&tx).unwrap();
+        assert!(setup.grin.is_unspent(initiator.commitment()));
+        assert!(setup.grin.is_unspent(responder.commitment()));
+    }
+
+    #[test]
+    fn test_unilateral_close_waits_for_delay() {
+        let mut setup = Setup::open();
+        setup.update([696, 300]);
+        let (close, settle) = setup.channels[1].unilateral_close().unwrap();
+        let (close, settle) = (close.clone(), settle.clone());
+
+        setup.grin.post(&close).unwrap();
+        setup.grin.mine(u64::from(DELAY) - 1);
+        assert!(matches!(
+            setup.grin.post(&settle),
+            Err(Error::ChainRejected(_))
+        ));
+        setup.grin.mine(1);
+        setup.grin.post(&settle).unwrap();
+        assert!(settle
+            .outputs()
+            .iter()
+            .all(|o| setup.grin.is_unspent(o.commitment())));
+    }
+
+    #[test]
+    fn test_revoked_close_is_penalized() {
+        let mut setup = Setup::open();
+        let (old_close, old_settle) = setup.channels[0].unilateral_close().unwrap();
+        let (old_close, old_settle) = (old_close.clone(), old_settle.clone());
+        setup.update([496, 500]);
+
+        // Once revocations are exchanged, no further one is accepted
+        let (current_close, _) = setup.channels[0].unilateral_close().unwrap();
+        let current_close = current_close.clone();
+        let replay = Revocation {
+            number: 0,
+            secret: setup.funding_keys[0].clone(),
+        };
+        assert!(matches!(
+            setup.channels[1].revoke(&replay),
+            Err(Error::InvalidChannel(_))
+        ));
+
+        setup.grin.post(&old_close).unwrap();
+        let (sweep_output, sweep_blind) = setup.output(VALUE - 2 * FEE);
+        let responder = &setup.channels[1];
+        assert!(responder
+            .penalty(
+                &current_close,
+                &setup.funding_keys[1],
+                sweep_output,
+                &sweep_blind
+            )
+            .unwrap()
+            .is_none());
+        let sweep = responder
+            .penalty(
+                &old_close,
+                &setup.funding_keys[1],
+                sweep_output,
+                &sweep_blind,
+            )
+            .unwrap()
+            .unwrap();
+        setup.grin.post(&sweep).unwrap();
+        assert!(setup.grin.is_unspent(sweep_output.commitment()));
+
+        setup.grin.mine(u64::from(DELAY));
+        assert!(matches!(
+            setup.grin.post(&old_settle),
+            Err(Error::ChainRejected(_))
+        ));
+    }
+
+    #[test]
+    fn test_commitment_tied_to_balances() {
+        let setup = Setup::open();
+        let channel = &setup.channels[0];
+        let state = channel.propose([696, 300]).unwrap();
+        let [key, remote_key] = [0, 1].map(|_| SecretKey::new(&setup.secp, &mut thread_rng()));
+        let public = PublicKey::from_secret_key(&setup.secp, &key).unwrap();
+        let remote = PublicKey::from_secret_key(&setup.secp, &remote_key).unwrap();
+
+        // This party's settle output must pay its balance
+        let outputs = state.balances().map(|b| setup.output(b));
+        let (_, wrong_blind) = setup.output(696);
+        assert!(matches!(
+            channel.commitment(
+                state,
+                &channel.state_session(Role::Initiator, &public),
+                *outputs[0].0.proof(),
+                [outputs[0].0, outputs[1].0],
+                &wrong_blind,
+            ),
+            Err(Error::InvalidChannel(_))
+        ));
+
+        // A settle paying the other balance elsewhere is not the agreed version
+        let proof = setup.state_proof(Role::Initiator, &key);
+        let (builder, _, _) =
+            setup.sign_version_to(Role::Initiator, &key, state, &proof, outputs.clone());
+        let mut diverted = outputs;
+        diverted[1] = setup.output(300);
+        let (_, close, settle) =
+            setup.sign_version_to(Role::Initiator, &key, state, &proof, diverted);
+        assert_eq!(close.outputs(), builder.close().outputs());
+        assert!(matches!(
+            setup.channels[0]
+                .clone()
+                .commit(&builder, close, settle, key, remote),
+            Err(Error::InvalidChannel(_))
+        ));
+    }
+}
@@ -0,0 +1,782 @@
+//! ChillDKG-style distributed key generation over a single broadcast channel
+//!
+//! Instead of private channels, every participant encrypts its shares to the
+//! other participants' long-lived host keys and hands everything to an
+//! untrusted coordinator, who aggregates and broadcasts. Participants then
+//! sign the transcript with their host keys; the collected signatures form a
+//! certificate proving everyone saw the same transcript. The transcript plus
+//! certificate is the recovery data: together with its host secret key alone,
+//! a participant can rebuild its signing share.
+//!
+//! # Algorithm
+//! ```text
+//! Params:            host keys P_1..P_n, threshold t
+//! Participant i:     f_i random of degree t-1, C_i = commitments to f_i,
+//!                    proof of knowledge of f_i(0), r_i random, N_i = r_i*G
+//!                    ct_ij = f_i(j) + H(r_i*P_j || N_i || i || j)
+//! Coordinator:       C = sum(C_i),  ct_j = sum_i(ct_ij), broadcast with
+//!                    C_i0, proofs and N_i
+//! Participant j:     check C_j0 and N_j in the broadcast are its own
+//!                    s_j = ct_j - sum_i H(p_j*N_i || N_i || i || j)
+//!                    check s_j*G against C, sign the transcript with p_j
+//! Certificate:       every participant's transcript signature
+//! Recovery data:     params || coordinator broadcast || certificate
+//! ```
+
+use std::collections::BTreeMap;
+
+use blake2::{Blake2b512, Digest};
+use rand::thread_rng;
+use secp256k1zkp::{aggsig, Message, PublicKey, Secp256k1, SecretKey, Signature};
+use serde::{Deserialize, Serialize};
+
+use crate::dkg::ProofOfKnowledge;
+use crate::error::{Error, Result};
+use crate::frost::{KeyPackage, PublicKeyPackage};
+use crate::scalar;
+use crate::ser::{hex_pubkey, hex_pubkey_vec, hex_seckey_vec, hex_signature};
+use crate::types::ParticipantId;
+use crate::vss::{check_threshold, Polynomial, VssCommitment};
+
+/// Domain separator for share encryption pads
+const PAD_DOMAIN: &[u8] = b"grin-multisig/chilldkg/pad";
+
+/// Domain separator for the transcript signed by the certificate
+const TRANSCRIPT_DOMAIN: &[u8] = b"grin-multisig/chilldkg/transcript";
+
+/// Public session parameters every participant agrees on beforehand
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionParams {
+    /// Host public key of every participant; participant `i` is at index `i - 1`
+    #[serde(with = "hex_pubkey_vec")]
+    host_pubkeys: Vec<PublicKey>,
+
+    /// Threshold `t`
+    min_signers: usize,
+}
+
+impl SessionParams {
+    /// Create session parameters
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidThreshold` unless `1 <= t <= n`
+    /// Returns `Error::DuplicateParticipant` if a host key is listed twice
+    pub fn new(host_pubkeys: Vec<PublicKey>, min_signers: usize) -> Result<Self> {
+        let params = Self {
+            host_pubkeys,
+            min_signers,
+        };
+        check_threshold(min_signers, &params.participants())?;
+        for (i, host_pubkey) in params.host_pubkeys.iter().enumerate() {
+            if params.host_pubkeys[..i].contains(host_pubkey) {
+                return Err(Error::DuplicateParticipant {
+                    participant: participant_at(i),
+                });
+            }
+        }
+        Ok(params)
+    }
+
+    /// Get the host public keys
+    pub fn host_pubkeys(&self) -> &[PublicKey] {
+        &self.host_pubkeys
+    }
+
+    /// Get the threshold
+    pub const fn min_signers(&self) -> usize {
+        self.min_signers
+    }
+
+    /// Participant IDs, `1..=n` in host key order
+    pub fn participants(&self) -> Vec<ParticipantId> {
+        (0..self.host_pubkeys.len()).map(participant_at).collect()
+    }
+
+    /// Find the participant owning a host secret key
+    fn participant_of(
+        &self,
+        secp: &Secp256k1,
+        host_secret_key: &SecretKey,
+    ) -> Result<ParticipantId> {
+        let host_pubkey = PublicKey::from_secret_key(secp, host_secret_key)?;
+        self.host_pubkeys
+            .iter()
+            .position(|pk| *pk == host_pubkey)
+            .map(participant_at)
+            .ok_or(Error::UnknownHostKey)
+    }
+
+    fn host_pubkey(&self, participant: ParticipantId) -> &PublicKey {
+        &self.host_pubkeys[index_of(participant)]
+    }
+}
+
+/// A participant's contribution, sent to the coordinator
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParticipantMsg1 {
+    /// Commitments to the participant's polynomial
+    commitment: VssCommitment,
+
+    /// Proof of knowledge of the constant term
+    proof_of_knowledge: ProofOfKnowledge,
+
+    /// Encryption nonce `N_i = r_i * G`
+    #[serde(with = "hex_pubkey")]
+    enc_nonce: PublicKey,
+
+    /// Encrypted share for every participant, in participant order
+    #[serde(with = "hex_seckey_vec")]
+    ciphertexts: Vec<SecretKey>,
+}
+
+/// Coordinator's aggregated broadcast
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoordinatorMsg1 {
+    /// Each participant's commitment to its constant term
+    #[serde(with = "hex_pubkey_vec")]
+    constant_terms: Vec<PublicKey>,
+
+    /// Each participant's proof of knowledge
+    proofs: Vec<ProofOfKnowledge>,
+
+    /// Sum of all participants' commitments
+    commitment: VssCommitment,
+
+    /// Each participant's encryption nonce
+    #[serde(with = "hex_pubkey_vec")]
+    enc_nonces: Vec<PublicKey>,
+
+    /// Sum of the encrypted shares for every recipient
+    #[serde(with = "hex_seckey_vec")]
+    ciphertexts: Vec<SecretKey>,
+}
+
+/// A participant's signature over the transcript
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParticipantMsg2 {
+    /// Host key signature over the transcript hash
+    #[serde(with = "hex_signature")]
+    signature: Signature,
+}
+
+/// Coordinator's broadcast of the certificate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoordinatorMsg2 {
+    /// Every participant's transcript signature, in participant order
+    certificate: Vec<ParticipantMsg2>,
+}
+
+/// State kept by a participant after step 1
+#[derive(Debug, Clone)]
+pub struct ParticipantState1 {
+    /// Session parameters
+    params: SessionParams,
+
+    /// Commitment to this participant's constant term
+    constant_term: PublicKey,
+
+    /// This participant's encryption nonce
+    enc_nonce: PublicKey,
+}
+
+/// State kept by a participant after step 2
+#[derive(Debug, Clone)]
+pub struct ParticipantState2 {
+    /// Session parameters
+    params: SessionParams,
+
+    /// Coordinator broadcast the participant signed
+    message: CoordinatorMsg1,
+
+    /// Derived key material, released once the certificate verifies
+    key_package: KeyPackage,
+
+    /// Derived public keys
+    public_keys: PublicKeyPackage,
+}
+
+/// Everything needed to rebuild a participant's share with its host secret key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecoveryData {
+    /// Session parameters
+    params: SessionParams,
+
+    /// Coordinator broadcast
+    message: CoordinatorMsg1,
+
+    /// Certificate over the transcript
+    certificate: CoordinatorMsg2,
+}
+
+impl RecoveryData {
+    /// Serialize to bytes for backup
+    ///
+    /// # Errors
+    /// Returns `Error::Serialization` if encoding fails
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(self)?)
+    }
+
+    /// Deserialize from backup bytes
+    ///
+    /// # Errors
+    /// Returns `Error::Serialization` if the bytes are not valid recovery data
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+
+    /// Get the session parameters
+    pub const fn params(&self) -> &SessionParams {
+        &self.params
+    }
+}
+
+/// Deal shares and encrypt them to every participant's host key
+///
+/// # Arguments
+/// * `secp` - Secp256k1 context
+/// * `host_secret_key` - This participant's host secret key
+/// * `params` - Session parameters
+///
+/// # Returns
+/// State to keep and the message for the coordinator
+///
+/// # Errors
+/// Returns `Error::UnknownHostKey` if the host key is not in `params`
+pub fn participant_step1(
+    secp: &Secp256k1,
+    host_secret_key: &SecretKey,
+    params: &SessionParams,
+) -> Result<(ParticipantState1, ParticipantMsg1)> {
+    let participant = params.participant_of(secp, host_secret_key)?;
+    let mut rng = thread_rng();
+
+    let polynomial = Polynomial::random(secp, SecretKey::new(secp, &mut rng), params.min_signers);
+    let commitment = polynomial.commit(secp)?;
+    let proof_of_knowledge = ProofOfKnowledge::prove(secp, participant, polynomial.constant())?;
+
+    let enc_secret = SecretKey::new(secp, &mut rng);
+    let enc_nonce = PublicKey::from_secret_key(secp, &enc_secret)?;
+    let ciphertexts = params
+        .participants()
+        .into_iter()
+        .map(|recipient| {
+            let shared = scalar::mul_point(secp, params.host_pubkey(recipient), &enc_secret)?;
+            let pad = pad(secp, params, &shared, &enc_nonce, participant, recipient)?;
+            scalar::add(secp, &polynomial.evaluate(secp, recipient)?, &pad)
+        })
+        .collect::<Result<_>>()?;
+
+    let state = ParticipantState1 {
+        params: params.clone(),
+        constant_term: commitment.group_public_key()?,
+        enc_nonce,
+    };
+    let msg = ParticipantMsg1 {
+        commitment,
+        proof_of_knowledge,
+        enc_nonce,
+        ciphertexts,
+    };
+    Ok((state, msg))
+}
+
+/// Aggregate the participants' messages into one broadcast
+///
+/// # Arguments
+/// * `secp` - Secp256k1 context
+/// * `params` - Session parameters
+/// * `msgs` - Every participant's step 1 message, in participant order
+///
+/// # Errors
+/// Returns `Error::MalformedMessage` if a message has the wrong number of entries
+/// Returns `Error::InvalidCommitment` if a commitment has the wrong degree
+/// Returns `Error::InvalidProofOfKnowledge` if a proof does not verify
+pub fn coordinator_step1(
+    secp: &Secp256k1,
+    params: &SessionParams,
+    msgs: &[ParticipantMsg1],
+) -> Result<CoordinatorMsg1> {
+    let n = params.host_pubkeys.len();
+    if msgs.len() != n {
+        return Err(Error::MalformedMessage(format!(
+            "expected {n} participant messages, got {}",
+            msgs.len()
+        )));
+    }
+    for (i, msg) in msgs.iter().enumerate() {
+        let participant = participant_at(i);
+        if msg.commitment.threshold() != params.min_signers {
+            return Err(Error::InvalidCommitment { participant });
+        }
+        if msg.ciphertexts.len() != n {
+            return Err(Error::MalformedMessage(format!(
+                "participant {participant} sent {} ciphertexts for {n} participants",
+                msg.ciphertexts.len()
+            )));
+        }
+        msg.proof_of_knowledge
+            .verify(secp, participant, &msg.commitment.group_public_key()?)?;
+    }
+
+    let commitment = VssCommitment::sum(
+        secp,
+        &msgs.iter().map(|m| &m.commitment).collect::<Vec<_>>(),
+    )?;
+    let ciphertexts = (0..n)
+        .map(|j| {
+            msgs[1..]
+                .iter()
+                .try_fold(msgs[0].ciphertexts[j].clone(), |sum, m| {
+                    scalar::add(secp, &sum, &m.ciphertexts[j])
+                })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(CoordinatorMsg1 {
+        constant_terms: msgs
+            .iter()
+            .map(|m| m.commitment.group_public_key())
+            .collect::<Result<_>>()?,
+        proofs: msgs.iter().map(|m| m.proof_of_knowledge.clone()).collect(),
+        commitment,
+        enc_nonces: msgs.iter().map(|m| m.enc_nonce).collect(),
+        ciphertexts,
+    })
+}
+
+/// Decrypt and check this participant's share, then sign the transcript
+///
+/// # Arguments
+/// * `secp` - Secp256k1 context
+/// * `host_secret_key` - This participant's host secret key
+/// * `state` - State from [`participant_step1`]
+/// * `message` - Coordinator broadcast
+///
+/// # Returns
+/// State to keep and the transcript signature for the coordinator
+///
+/// # Errors
+/// Returns `Error::MalformedMessage` if the broadcast is inconsistent or does
+/// not carry this participant's own contribution
+/// Returns `Error::InvalidProofOfKnowledge` if a proof does not verify
+/// Returns `Error::InvalidSecretShare` if the decrypted share does not match
+/// the summed commitments
+pub fn participant_step2(
+    secp: &Secp256k1,
+    host_secret_key: &SecretKey,
+    state: ParticipantState1,
+    message: &CoordinatorMsg1,
+) -> Result<(ParticipantState2, ParticipantMsg2)> {
+    // Otherwise the coordinator could replace every contribution with its own
+    let index = index_of(state.params.participant_of(secp, host_secret_key)?);
+    if message.constant_terms.get(index) != Some(&state.constant_term)
+        || message.enc_nonces.get(index) != Some(&state.enc_nonce)
+    {
+        return Err(Error::MalformedMessage(
+            "coordinator broadcast replaced this participant's contribution".into(),
+        ));
+    }
+
+    let (key_package, public_keys) = derive_output(secp, host_secret_key, &state.params, message)?;
+
+    let msg = transcript(&state.params, message)?;
+    let host_pubkey = PublicKey::from_secret_key(secp, host_secret_key)?;
+    let signature = aggsig::sign_single(
+        secp,
+        &msg,
+        host_secret_key,
+        None,
+        None,
+        None,
+        Some(&host_pubkey),
+        None,
+    )?;
+
+    let next = ParticipantState2 {
+        params: state.params,
+        message: message.clone(),
+        key_package,
+        public_keys,
+    };
+    Ok((next, ParticipantMsg2 { signature }))
+}
+
+/// Collect the transcript signatures into a certificate
+///
+/// # Arguments
+/// * `secp` - Secp256k1 context
+/// * `params` - Session parameters
+/// * `message` - Coordinator broadcast from [`coordinator_step1`]
+/// * `msgs` - Every participant's transcript signature, in participant order
+///
+/// # Returns
+/// Certificate broadcast, the group's public keys and the recovery data
+///
+/// # Errors
+/// Returns `Error::InvalidCertificate` if a signature is missing or invalid
+pub fn coordinator_finalize(
+    secp: &Secp256k1,
+    params: &SessionParams,
+    message: &CoordinatorMsg1,
+    msgs: &[ParticipantMsg2],
+) -> Result<(CoordinatorMsg2, PublicKeyPackage, RecoveryData)> {
+    let certificate = CoordinatorMsg2 {
+        certificate: msgs.to_vec(),
+    };
+    verify_certificate(secp, params, message, &certificate)?;
+
+    let public_keys = public_key_package(secp, params, &message.commitment)?;
+    let recovery = RecoveryData {
+        params: params.clone(),
+        message: message.clone(),
+        certificate: certificate.clone(),
+    };
+    Ok((certificate, public_keys, recovery))
+}
+
+/// Release the key material once everyone has certified the transcript
+///
+/// # Returns
+/// This participant's key package, the group's public keys and the recovery data
+///
+/// # Errors
+/// Returns `Error::InvalidCertificate` if a signature is missing or invalid
+pub fn participant_finalize(
+    secp: &Secp256k1,
+    state: ParticipantState2,
+    certificate: &CoordinatorMsg2,
+) -> Result<(KeyPackage, PublicKeyPackage, RecoveryData)> {
+    verify_certificate(secp, &state.params, &state.message, certificate)?;
+    let recovery = RecoveryData {
+        params: state.params,
+        message: state.message,
+        certificate: certificate.clone(),
+    };
+    Ok((state.key_package, state.public_keys, recovery))
+}
+
+/// Rebuild a participant's key material from recovery data and its host key
+///
+/// # Errors
+/// Returns `Error::UnknownHostKey` if the host key is not in the session
+/// Returns `Error::InvalidCertificate` if the certificate does not verify
+/// Returns `Error::InvalidSecretShare` if the decrypted share does not match
+pub fn recover(
+    secp: &Secp256k1,
+    host_secret_key: &SecretKey,
+    recovery: &RecoveryData,
+) -> Result<(KeyPackage, PublicKeyPackage)> {
+    recovery.params.participant_of(secp, host_secret_key)?;
+    verify_certificate(
+        secp,
+        &recovery.params,
+        &recovery.message,
+        &recovery.certificate,
+    )?;
+    derive_output(secp, host_secret_key, &recovery.params, &recovery.message)
+}
+
+/// Decrypt and verify a participant's share from the coordinator broadcast
+fn derive_output(
+    secp: &Secp256k1,
+    host_secret_key: &SecretKey,
+    params: &SessionParams,
+    message: &CoordinatorMsg1,
+) -> Result<(KeyPackage, PublicKeyPackage)> {
+    let participant = params.participant_of(secp, host_secret_key)?;
+    let n = params.host_pubkeys.len();
+    if message.constant_terms.len() != n
+        || message.proofs.len() != n
+        || message.enc_nonces.len() != n
+        || message.ciphertexts.len() != n
+        || message.commitment.threshold() != params.min_signers
+    {
+        return Err(Error::MalformedMessage(
+            "coordinator broadcast does not match the session parameters".into(),
+        ));
+    }
+
+    for (i, (constant, proof)) in message
+        .constant_terms
+        .iter()
+        .zip(&message.proofs)
+        .enumerate()
+    {
+        proof.verify(secp, participant_at(i), constant)?;
+    }
+    let constant_sum = PublicKey::from_combination(secp, message.constant_terms.iter().collect())?;
+    if constant_sum != message.commitment.group_public_key()? {
+        return Err(Error::MalformedMessage(
+            "summed commitment does not match the constant terms".into(),
+        ));
+    }
+
+    let mut signing_share = message.ciphertexts[index_of(participant)].clone();
+    for (i, enc_nonce) in message.enc_nonces.iter().enumerate() {
+        let shared = scalar::mul_point(secp, enc_nonce, host_secret_key)?;
+        let pad = pad(
+            secp,
+            params,
+            &shared,
+            enc_nonce,
+            participant_at(i),
+            participant,
+        )?;
+        signing_share = scalar::sub(secp, &signing_share, &pad)?;
+    }
+    if PublicKey::from_secret_key(secp, &signing_share)?
+        != message.commitment.verifying_share(secp, participant)?
+    {
+        return Err(Error::InvalidSecretShare { participant });
+    }
+
+    let public_keys = public_key_package(secp, params, &message.commitment)?;
+    let key_package = KeyPackage::new(
+        secp,
+        participant,
+        signing_share,
+        *public_keys.group_public_key(),
+        params.min_signers,
+    )?;
+    Ok((key_package, public_keys))
+}
+
+/// Check every participant's signature over the transcript
+fn verify_certificate(
+    secp: &Secp256k1,
+    params: &SessionParams,
+    message: &CoordinatorMsg1,
+    certificate: &CoordinatorMsg2,
+) -> Result<()> {
+    let msg = transcript(params, message)?;
+    for (i, host_pubkey) in params.host_pubkeys.iter().enumerate() {
+        let valid = certificate.certificate.get(i).is_some_and(|m| {
+            aggsig::verify_single(
+                secp,
+                &m.signature,
+                &msg,
+                None,
+                host_pubkey,
+                Some(host_pubkey),
+                None,
+                false,
+            )
+        });
+        if !valid {
+            return Err(Error::InvalidCertificate {
+                participant: participant_at(i),
+            });
+        }
+    }
+    if certificate.certificate.len() == params.host_pubkeys.len() {
+        Ok(())
+    } else {
+        Err(Error::MalformedMessage(
+            "certificate has more signatures than participants".into(),
+        ))
+    }
+}
+
+/// Hash of the session parameters and coordinator broadcast
+fn transcript(params: &SessionParams, message: &CoordinatorMsg1) -> Result<Message> {
+    let mut hasher = Blake2b512::new();
+    hasher.update(TRANSCRIPT_DOMAIN);
+    hasher.update(serde_json::to_vec(&(params, message))?);
+    Ok(Message::from_slice(&hasher.finalize()[..32])?)
+}
+
+/// One-time pad for the share `sender` encrypts to `recipient`
+fn pad(
+    secp: &Secp256k1,
+    params: &SessionParams,
+    shared: &PublicKey,
+    enc_nonce: &PublicKey,
+    sender: ParticipantId,
+    recipient: ParticipantId,
+) -> Result<SecretKey> {
+    scalar::hash_to_scalar(
+        secp,
+        PAD_DOMAIN,
+        &[
+            &scalar::point_bytes(secp, shared),
+            &scalar::point_bytes(secp, enc_nonce),
+            &scalar::point_bytes(secp, params.host_pubkey(recipient)),
+            &sender.inner().to_be_bytes(),
+            &recipient.inner().to_be_bytes(),
+            &(params.min_signers as u64).to_be_bytes(),
+        ],
+    )
+}
+
+fn public_key_package(
+    secp: &Secp256k1,
+    params: &SessionParams,
+    commitment: &VssCommitment,
+) -> Result<PublicKeyPackage> {
+    let verifying_shares = params
+        .participants()
+        .into_iter()
+        .map(|p| Ok((p, commitment.verifying_share(secp, p)?)))
+        .collect::<Result<BTreeMap<_, _>>>()?;
+    Ok(PublicKeyPackage::new(
+        verifying_shares,
+        commitment.group_public_key()?,
+        params.min_signers,
+    ))
+}
+
+fn participant_at(index: usize) -> ParticipantId {
+    ParticipantId::new(u32::try_from(index + 1).unwrap_or(u32::MAX))
+}
+
+const fn index_of(participant: ParticipantId) -> usize {
+    participant.inner() as usize - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::try_sign;
+
+    fn hosts(secp: &Secp256k1, n: usize) -> (Vec<SecretKey>, SessionParams) {
+        let keys: Vec<_> = (0..n)
+            .map(|_| SecretKey::new(secp, &mut thread_rng()))
+            .collect();
+        let pubkeys = keys
+            .iter()
+            .map(|k| PublicKey::from_secret_key(secp, k).unwrap())
+            .collect();
+        (keys, SessionParams::new(pubkeys, 2).unwrap())
+    }
+
+    fn step1(
+        secp: &Secp256k1,
+        keys: &[SecretKey],
+        params: &SessionParams,
+    ) -> (Vec<ParticipantState1>, Vec<ParticipantMsg1>) {
+        keys.iter()
+            .map(|k| participant_step1(secp, k, params).unwrap())
+            .unzip()
+    }
+
+    #[test]
+    fn test_full_run_and_recovery() {
+        let secp = Secp256k1::new();
+        let (keys, params) = hosts(&secp, 3);
+        let (states, msgs) = step1(&secp, &keys, &params);
+        let broadcast = coordinator_step1(&secp, &params, &msgs).unwrap();
+
+        let (states, sigs): (Vec<_>, Vec<_>) = states
+            .into_iter()
+            .zip(&keys)
+            .map(|(s, k)| participant_step2(&secp, k, s, &broadcast).unwrap())
+            .unzip();
+        let (certificate, public_keys, recovery) =
+            coordinator_finalize(&secp, &params, &broadcast, &sigs).unwrap();
+
+        let outputs: Vec<_> = states
+            .into_iter()
+            .map(|s| participant_finalize(&secp, s, &certificate).unwrap())
+            .collect();
+        for ((key_package, public, own_recovery), host_key) in outputs.iter().zip(&keys) {
+            assert_eq!(*public, public_keys);
+            assert_eq!(*own_recovery, recovery);
+
+            // Lost state is rebuilt from the backup and the host key alone
+            let backup = RecoveryData::from_bytes(&recovery.to_bytes().unwrap()).unwrap();
+            let (recovered, recovered_public) = recover(&secp, host_key, &backup).unwrap();
+            assert_eq!(recovered, *key_package);
+            assert_eq!(recovered_public, public_keys);
+        }
+
+        // Any two participants can sign for the group key
+        assert!(try_sign(&secp, &[&outputs[0].0, &outputs[2].0], &public_keys).is_ok());
+    }
+
+    #[test]
+    fn test_tampering_detected() {
+        let secp = Secp256k1::new();
+        let (keys, params) = hosts(&secp, 3);
+        let (states, msgs) = step1(&secp, &keys, &params);
+        let broadcast = coordinator_step1(&secp, &params, &msgs).unwrap();
+
+        // A coordinator that alters participant 2's ciphertext is caught
+        let mut altered = broadcast.clone();
+        altered.ciphertexts[1] = SecretKey::new(&secp, &mut thread_rng());
+        assert_eq!(
+            participant_step2(&secp, &keys[1], states[1].clone(), &altered).unwrap_err(),
+            Error::InvalidSecretShare {
+                participant: ParticipantId::new(2)
+            }
+        );
+
+        // A certificate signature over another transcript is rejected
+        let sigs: Vec<_> = states
+            .iter()
+            .zip(&keys)
+            .map(|(s, k)| {
+                participant_step2(&secp, k, s.clone(), &broadcast)
+                    .unwrap()
+                    .1
+            })
+            .collect();
+        let (other_states, other_msgs) = step1(&secp, &keys, &params);
+        let other = coordinator_step1(&secp, &params, &other_msgs).unwrap();
+        let mut mixed = sigs.clone();
+        mixed[2] = participant_step2(&secp, &keys[2], other_states[2].clone(), &other)
+            .unwrap()
+            .1;
+        assert_eq!(
+            coordinator_finalize(&secp, &params, &broadcast, &mixed).unwrap_err(),
+            Error::InvalidCertificate {
+                participant: ParticipantId::new(3)
+            }
+        );
+        assert!(matches!(
+            coordinator_finalize(&secp, &params, &broadcast, &sigs[..2]),
+            Err(Error::InvalidCertificate { .. })
+        ));
+
+        // Recovery needs a host key from the session
+        let (_, _, recovery) = coordinator_finalize(&secp, &params, &broadcast, &sigs).unwrap();
+        let stranger = SecretKey::new(&secp, &mut thread_rng());
+        assert_eq!(
+            recover(&secp, &stranger, &recovery).unwrap_err(),
+            Error::UnknownHostKey
+        );
+    }
+
+    #[test]
+    fn test_substituted_contribution_rejected() {
+        let secp = Secp256k1::new();
+        let (keys, params) = hosts(&secp, 3);
+        let (states, mut msgs) = step1(&secp, &keys, &params);
+
+        // The coordinator swaps participant 1's contribution for one of its
+        // own, with a valid proof and ciphertexts
+        let (_, substitute) = participant_step1(&secp, &keys[0], &params).unwrap();
+        msgs[0] = substitute;
+        let broadcast = coordinator_step1(&secp, &params, &msgs).unwrap();
+
+        assert!(matches!(
+            participant_step2(&secp, &keys[0], states[0].clone(), &broadcast),
+            Err(Error::MalformedMessage(_))
+        ));
+    }
+
+    #[test]
+    fn test_forged_proof_of_knowledge_rejected() {
+        let secp = Secp256k1::new();
+        let (keys, params) = hosts(&secp, 3);
+        let (_, mut msgs) = step1(&secp, &keys, &params);
+
+        msgs[2].proof_of_knowledge = msgs[0].proof_of_knowledge.clone();
+        assert_eq!(
+            coordinator_step1(&secp, &params, &msgs).unwrap_err(),
+            Error::InvalidProofOfKnowledge {
+                participant: ParticipantId::new(3)
+            }
+        );
+    }
+}
@@ -0,0 +1,776 @@
+//! Automated co-signer with second-factor approval
+//!
+//! A service holding one participant key of a shared output co-signs on its
+//! own whenever the signer's [`Policy`] passes. Above an approval threshold it
+//! additionally wants a human's second factor: a TOTP code from the user's
+//! authenticator, or a one-time challenge code the service issued for that
+//! very spend and delivered out of band. With the service as one signer and
+//! the user as the other, this is "2FA multisig".
+//!
+//! The amount gated by the threshold is the one the [`SpendRequest`] derives
+//! from the spend, never a declared one, and a challenge code approves only
+//! the exact request it was issued for. Every request counts against a rate
+//! limit, so second-factor codes cannot be brute forced, and every decision
+//! lands in an append-only audit trail. All times come from the service's own
+//! [`Clock`], so a request cannot backdate itself past the limiter or a code's
+//! expiry. The service runs Round 1 itself and keeps its secret nonce until the
+//! partial signature that spends it, so no caller ever holds or replays it.
+//!
+//! # Algorithm
+//! ```text
+//! TOTP (RFC 6238, HMAC-SHA256, 30 s, 6 digits):
+//!     h    = HMAC(secret, floor(time / 30) as u64 BE)
+//!     o    = h[31] & 0x0f
+//!     code = (h[o..o+4] as u32 BE & 0x7fffffff) mod 10^6   (±1 step, no reuse)
+//! Round 1, for a session holding the service's key:
+//!     nonces[X_agg] = round1_generate_nonces(), reveal its public part
+//! Co-sign request, at now = service clock:
+//!     1. more than max_requests in the window  -> RateLimited
+//!     2. policy denies                         -> PolicyDenied
+//!     3. amount > threshold and no valid code  -> SecondFactorRequired | Rejected
+//!     4. take nonces[X_agg], round3_partial_sign_with_policy -> Signed
+//! ```
+
+use hmac::{Hmac, Mac};
+use rand::{thread_rng, Rng};
+use secp256k1zkp::{PublicKey, SecretKey};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::error::{Error, Result};
+use crate::policy::{Clock, Policy, SpendRequest};
+use crate::ser::hex_array;
+use crate::session::{Round1State, Session};
+use crate::types::{NonceCommitment, PartialSignature};
+
+/// Seconds per TOTP step
+pub const TOTP_STEP: u64 = 30;
+
+/// Seconds a challenge code stays valid
+pub const CHALLENGE_TTL: u64 = 300;
+
+/// Digits of TOTP and challenge codes
+const CODE_MODULUS: u32 = 1_000_000;
+
+/// Time-based one-time passwords (RFC 6238) over HMAC-SHA256
+#[derive(Debug, Clone)]
+pub struct Totp {
+    /// Secret shared with the user's authenticator
+    secret: Vec<u8>,
+}
+
+impl Totp {
+    /// Create a generator from the secret shared with the authenticator
+    pub const fn new(secret: Vec<u8>) -> Self {
+        Self { secret }
+    }
+
+    /// Compute the code at a Unix time
+    pub fn code(&self, time: u64) -> u32 {
+        self.code_at_step(time / TOTP_STEP)
+    }
+
+    fn code_at_step(&self, step: u64) -> u32 {
+        // HMAC accepts keys of any length
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret).expect("any key length");
+        mac.update(&step.to_be_bytes());
+        let hash = mac.finalize().into_bytes();
+        let offset = usize::from(hash[hash.len() - 1] & 0x0f);
+        let bytes = [
+            hash[offset],
+            hash[offset + 1],
+            hash[offset + 2],
+            hash[offset + 3],
+        ];
+        (u32::from_be_bytes(bytes) & 0x7fff_ffff) % CODE_MODULUS
+    }
+
+    /// Find the step a code belongs to, allowing one step of clock drift
+    fn matching_step(&self, code: u32, time: u64) -> Option<u64> {
+        let step = time / TOTP_STEP;
+        [step.saturating_sub(1), step, step + 1]
+            .into_iter()
+            .find(|s| self.code_at_step(*s) == code)
+    }
+}
+
+/// A human's approval of a spend above the threshold
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecondFactor {
+    /// Code from the user's authenticator
+    Totp(u32),
+    /// Code the service issued for this spend
+    Challenge(u32),
+}
+
+/// Most requests the service accepts per window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Requests allowed per window
+    max_requests: usize,
+
+    /// Window length in seconds
+    window: u64,
+}
+
+impl RateLimit {
+    /// Allow `max_requests` per `window` seconds
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidRateLimit` if `max_requests` or `window` is zero
+    pub const fn new(max_requests: usize, window: u64) -> Result<Self> {
+        if max_requests == 0 || window == 0 {
+            return Err(Error::InvalidRateLimit);
+        }
+        Ok(Self {
+            max_requests,
+            window,
+        })
+    }
+}
+
+/// What the service decided on a request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    /// Partial signature produced
+    Signed,
+    /// Challenge code issued for the spend
+    ChallengeIssued,
+    /// Refused for exceeding the rate limit
+    RateLimited,
+    /// Refused by the policy, with its reason
+    PolicyDenied(String),
+    /// Refused for lack of a second factor
+    SecondFactorRequired,
+    /// Refused for a wrong, expired or reused second factor
+    SecondFactorRejected,
+}
+
+/// One entry of the audit trail
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Service's Unix time when it decided
+    time: u64,
+
+    /// Kernel message of the spend
+    #[serde(with = "hex_array")]
+    message: [u8; 32],
+
+    /// Amount leaving the shared outputs
+    amount: u64,
+
+    /// Declared counterparty
+    counterparty: String,
+
+    /// Decision
+    outcome: AuditOutcome,
+}
+
+impl AuditEntry {
+    /// Get the service's Unix time when it decided
+    pub const fn time(&self) -> u64 {
+        self.time
+    }
+
+    /// Get the kernel message of the spend
+    pub const fn message(&self) -> &[u8; 32] {
+        &self.message
+    }
+
+    /// Get the amount leaving the shared outputs
+    pub const fn amount(&self) -> u64 {
+        self.amount
+    }
+
+    /// Get the declared counterparty
+    pub fn counterparty(&self) -> &str {
+        &self.counterparty
+    }
+
+    /// Get the decision
+    pub const fn outcome(&self) -> &AuditOutcome {
+        &self.outcome
+    }
+}
+
+/// Challenge code waiting for its spend
+#[derive(Debug, Clone)]
+struct Challenge {
+    /// Code delivered to the human
+    code: u32,
+
+    /// Request it approves
+    request: SpendRequest,
+
+    /// Unix time after which it is void
+    expires: u64,
+}
+
+impl Challenge {
+    /// The kernel message only covers the kernel features, so the inputs,
+    /// outputs, change and counterparty are matched as well
+    fn approves(&self, request: &SpendRequest) -> bool {
+        self.request == *request
+    }
+}
+
+/// Service co-signing with one participant key
+#[derive(Debug)]
+pub struct CoSigner<C> {
+    /// Service's own clock, for the rate limit, TOTP and challenge expiry
+    clock: C,
+
+    /// Service's participant key
+    secret_key: SecretKey,
+
+    /// Rules every spend must meet
+    policy: Policy,
+
+    /// User's authenticator
+    totp: Totp,
+
+    /// Amount above which a second factor is required
+    approval_threshold: u64,
+
+    /// Request rate limit
+    rate_limit: RateLimit,
+
+    /// Times of recent requests
+    requests: Vec<u64>,
+
+    /// Latest TOTP step accepted, so codes are not reused
+    last_totp_step: Option<u64>,
+
+    /// Outstanding challenge codes
+    challenges: Vec<Challenge>,
+
+    /// Unspent Round 1 nonces, by the aggregate key of their session
+    nonces: Vec<(PublicKey, Round1State)>,
+
+    /// Every decision, oldest first
+    audit: Vec<AuditEntry>,
+}
+
+impl<C: Clock> CoSigner<C> {
+    /// Create a co-signer
+    ///
+    /// # Arguments
+    /// * `clock` - Service's own clock, e.g. [`SystemClock`](crate::policy::SystemClock)
+    /// * `secret_key` - Service's participant key
+    /// * `policy` - Rules every spend must meet
+    /// * `totp` - User's authenticator
+    /// * `approval_threshold` - Amount above which a second factor is required
+    /// * `rate_limit` - Request rate limit
+    pub const fn new(
+        clock: C,
+        secret_key: SecretKey,
+        policy: Policy,
+        totp: Totp,
+        approval_threshold: u64,
+        rate_limit: RateLimit,
+    ) -> Self {
+        Self {
+            clock,
+            secret_key,
+            policy,
+            totp,
+            approval_threshold,
+            rate_limit,
+            requests: Vec::new(),
+            last_totp_step: None,
+            challenges: Vec::new(),
+            nonces: Vec::new(),
+            audit: Vec::new(),
+        }
+    }
+
+    /// Get the policy, with the spends signed so far
+    pub const fn policy(&self) -> &Policy {
+        &self.policy
+    }
+
+    /// Get the audit trail, oldest first
+    pub fn audit(&self) -> &[AuditEntry] {
+        &self.audit
+    }
+
+    /// Round 1: generate the service's nonces for a session
+    ///
+    /// The secret nonce stays with the service until [`Self::co_sign`] spends
+    /// it. Running Round 1 again for the same session discards the old one.
+    ///
+    /// # Arguments
+    /// * `session` - Kernel session the service participates in
+    ///
+    /// # Returns
+    /// Nonce commitment and public nonces to share with the other signers
+    ///
+    /// # Errors
+    /// Returns `Error::NotASigner` if the session does not hold the service's key
+    /// Returns `Error::Crypto` if nonce generation fails
+    pub fn round1(
+        &mut self,
+        session: &Session,
+    ) -> Result<(NonceCommitment, (PublicKey, PublicKey))> {
+        let agg_pubkey = self.session_key(session)?;
+        let round = session.round1_generate_nonces()?;
+        let public = (*round.commitment(), *round.public_nonces());
+        self.nonces.retain(|(key, _)| *key != agg_pubkey);
+        self.nonces.push((agg_pubkey, round));
+        Ok(public)
+    }
+
+    /// Issue a challenge code for a spend, to deliver to the human out of band
+    ///
+    /// # Errors
+    /// Returns `Error::RateLimited` if the rate limit is exceeded
+    pub fn issue_challenge(&mut self, request: &SpendRequest) -> Result<u32> {
+        self.admit(request)?;
+        let now = self.clock.now();
+        let code = thread_rng().gen_range(0, CODE_MODULUS);
+        self.challenges
+            .retain(|c| !c.approves(request) && c.expires >= now);
+        self.challenges.push(Challenge {
+            code,
+            request: request.clone(),
+            expires: now + CHALLENGE_TTL,
+        });
+        self.log(request, AuditOutcome::ChallengeIssued);
+        Ok(code)
+    }
+
+    /// Co-sign a spend if the policy and, above the threshold, the second factor pass
+    ///
+    /// # Arguments
+    /// * `session` - Kernel session the service participates in
+    /// * `request` - Spend to co-sign
+    /// * `second_factor` - Human's approval, needed above the threshold
+    /// * `agg_nonce` - Aggregated nonce from Round 2
+    ///
+    /// # Errors
+    /// Returns `Error::NotASigner` if the session does not hold the service's key
+    /// Returns `Error::MissingNonce` if the service has no unspent nonce for the
+    /// session
+    /// Returns `Error::RateLimited` if the rate limit is exceeded
+    /// Returns `Error::PolicyDenied` if a rule fails
+    /// Returns `Error::SecondFactorRequired` or `Error::SecondFactorRejected`
+    /// above the threshold without a valid code
+    /// Returns `Error::Crypto` if signing fails
+    pub fn co_sign(
+        &mut self,
+        session: &Session,
+        request: &SpendRequest,
+        second_factor: Option<SecondFactor>,
+        agg_nonce: &PublicKey,
+    ) -> Result<PartialSignature> {
+        let agg_pubkey = self.session_key(session)?;
+        if !self.nonces.iter().any(|(key, _)| *key == agg_pubkey) {
+            return Err(Error::MissingNonce);
+        }
+        self.admit(request)?;
+        if let Err(e) = self.policy.evaluate(request, self.clock.now()) {
+            self.log(request, AuditOutcome::PolicyDenied(e.to_string()));
+            return Err(e);
+        }
+        if request.amount() > self.approval_threshold {
+            self.check_second_factor(request, second_factor)?;
+        }
+
+        // The nonce is spent even if signing fails
+        let position = self
+            .nonces
+            .iter()
+            .position(|(key, _)| *key == agg_pubkey)
+            .ok_or(Error::MissingNonce)?;
+        let (_, round) = self.nonces.swap_remove(position);
+        let partial = session.round3_partial_sign_with_policy(
+            &mut self.policy,
+            &self.clock,
+            request,
+            &self.secret_key,
+            round.secret_nonce(),
+            agg_nonce,
+        )?;
+        self.log(request, AuditOutcome::Signed);
+        Ok(partial)
+    }
+
+    /// Aggregate key of a session holding the service's key
+    fn session_key(&self, session: &Session) -> Result<PublicKey> {
+        let public_key = PublicKey::from_secret_key(session.secp(), &self.secret_key)?;
+        if !session
+            .participants()
+            .iter()
+            .any(|p| *p.public_key() == public_key)
+        {
+            return Err(Error::NotASigner);
+        }
+        session.aggregate_pubkeys()
+    }
+
+    /// Count a request against the rate limit
+    fn admit(&mut self, request: &SpendRequest) -> Result<()> {
+        let now = self.clock.now();
+        let window = self.rate_limit.window;
+        self.requests.retain(|t| *t <= now && now - t < window);
+        if self.requests.len() >= self.rate_limit.max_requests {
+            let retry_after = self.requests[0] + window - now;
+            self.log(request, AuditOutcome::RateLimited);
+            return Err(Error::RateLimited { retry_after });
+        }
+        self.requests.push(now);
+        Ok(())
+    }
+
+    fn check_second_factor(
+        &mut self,
+        request: &SpendRequest,
+        second_factor: Option<SecondFactor>,
+    ) -> Result<()> {
+        let accepted = match second_factor {
+            None => {
+                self.log(request, AuditOutcome::SecondFactorRequired);
+                return Err(Error::SecondFactorRequired);
+            }
+            Some(SecondFactor::Totp(code)) => match self.totp.matching_step(code, self.clock.now())
+            {
+                Some(step) if self.last_totp_step.is_none_or(|last| step > last) => {
+                    self.last_totp_step = Some(step);
+                    true
+                }
+                _ => false,
+            },
+            Some(SecondFactor::Challenge(code)) => {
+                let now = self.clock.now();
+                let matched = self
+                    .challenges
+                    .iter()
+                    .any(|c| c.code == code && c.approves(request) && now <= c.expires);
+                // A challenge is good for one attempt
+                self.challenges.retain(|c| !c.approves(request));
+                matched
+            }
+        };
+        if !accepted {
+            self.log(request, AuditOutcome::SecondFactorRejected);
+            return Err(Error::SecondFactorRejected);
+        }
+        Ok(())
+    }
+
+    fn log(&mut self, request: &SpendRequest, outcome: AuditOutcome) {
+        self.audit.push(AuditEntry {
+            time: self.clock.now(),
+            message: *request.message(),
+            amount: request.amount(),
+            counterparty: request.counterparty().to_string(),
+            outcome,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::policy::Rule;
+    use crate::test_util::{keyed_session, spend_request};
+    use crate::transaction::KernelFeatures;
+
+    use secp256k1zkp::{ContextFlag, Secp256k1};
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    const TOTP_SECRET: &[u8] = b"12345678901234567890123456789012";
+
+    const PLAIN: KernelFeatures = KernelFeatures::Plain { fee: 2 };
+
+    /// Clock the test moves by hand
+    #[derive(Debug, Clone, Default)]
+    struct TestClock(Rc<Cell<u64>>);
+
+    impl TestClock {
+        fn set(&self, time: u64) {
+            self.0.set(time);
+        }
+    }
+
+    impl Clock for TestClock {
+        fn now(&self) -> u64 {
+            self.0.get()
+        }
+    }
+
+    fn service(
+        clock: &TestClock,
+        key: SecretKey,
+        policy: Policy,
+        rate_limit: RateLimit,
+    ) -> CoSigner<TestClock> {
+        CoSigner::new(
+            clock.clone(),
+            key,
+            policy,
+            Totp::new(TOTP_SECRET.to_vec()),
+            100,
+            rate_limit,
+        )
+    }
+
+    /// Run Rounds 1-2 and ask the service for its partial signature
+    fn co_sign(
+        service: &mut CoSigner<TestClock>,
+        session: &Session,
+        request: &SpendRequest,
+        second_factor: Option<SecondFactor>,
+    ) -> Result<PartialSignature> {
+        let user = session.round1_generate_nonces().unwrap();
+        let (commitment, revealed) = service.round1(session).unwrap();
+        let agg_nonce = session
+            .round2_aggregate_nonces(
+                &[*user.commitment(), commitment],
+                &[*user.public_nonces(), revealed],
+            )
+            .unwrap();
+        service.co_sign(session, request, second_factor, &agg_nonce)
+    }
+
+    #[test]
+    fn test_totp_matches_rfc_6238() {
+        // SHA-256 vectors of RFC 6238, truncated to 6 digits
+        let totp = Totp::new(TOTP_SECRET.to_vec());
+        assert_eq!(totp.code(59), 119_246);
+        assert_eq!(totp.code(1_111_111_109), 84_774);
+        assert_eq!(totp.code(2_000_000_000), 698_825);
+
+        assert_eq!(totp.matching_step(totp.code(59), 59 + TOTP_STEP), Some(1));
+        assert_eq!(totp.matching_step(totp.code(59), 59 + 2 * TOTP_STEP), None);
+    }
+
+    #[test]
+    fn test_second_factor_above_threshold() {
+        let secp = Secp256k1::with_caps(ContextFlag::Commit);
+        let (session, keys) = keyed_session(&secp, 2);
+        let clock = TestClock::default();
+        let mut service = service(
+            &clock,
+            keys[1].clone(),
+            Policy::new(),
+            RateLimit::new(10, 60).unwrap(),
+        );
+        let totp = Totp::new(TOTP_SECRET.to_vec());
+        let time = 1_700_000_000;
+        clock.set(time);
+
+        co_sign(
+            &mut service,
+            &session,
+            &spend_request(&secp, &session, PLAIN, 100, "alice"),
+            None,
+        )
+        .unwrap();
+
+        let large = spend_request(&secp, &session, PLAIN, 500, "alice");
+        assert!(matches!(
+            co_sign(&mut service, &session, &large, None),
+            Err(Error::SecondFactorRequired)
+        ));
+        let wrong = SecondFactor::Totp((totp.code(time) + 1) % CODE_MODULUS);
+        assert!(matches!(
+            co_sign(&mut service, &session, &large, Some(wrong)),
+            Err(Error::SecondFactorRejected)
+        ));
+        let code = Some(SecondFactor::Totp(totp.code(time)));
+        co_sign(&mut service, &session, &large, code).unwrap();
+        assert!(matches!(
+            co_sign(&mut service, &session, &large, code),
+            Err(Error::SecondFactorRejected)
+        ));
+
+        // A challenge code approves its own spend once
+        let code = service.issue_challenge(&large).unwrap();
+        let other = spend_request(&secp, &session, PLAIN, 400, "alice");
+        assert!(matches!(
+            co_sign(
+                &mut service,
+                &session,
+                &other,
+                Some(SecondFactor::Challenge(code))
+            ),
+            Err(Error::SecondFactorRejected)
+        ));
+        co_sign(
+            &mut service,
+            &session,
+            &large,
+            Some(SecondFactor::Challenge(code)),
+        )
+        .unwrap();
+        assert!(matches!(
+            co_sign(
+                &mut service,
+                &session,
+                &large,
+                Some(SecondFactor::Challenge(code))
+            ),
+            Err(Error::SecondFactorRejected)
+        ));
+
+        let outcomes: Vec<_> = service.audit().iter().map(AuditEntry::outcome).collect();
+        assert_eq!(
+            outcomes,
+            [
+                &AuditOutcome::Signed,
+                &AuditOutcome::SecondFactorRequired,
+                &AuditOutcome::SecondFactorRejected,
+                &AuditOutcome::Signed,
+                &AuditOutcome::SecondFactorRejected,
+                &AuditOutcome::ChallengeIssued,
+                &AuditOutcome::SecondFactorRejected,
+                &AuditOutcome::Signed,
+                &AuditOutcome::SecondFactorRejected,
+            ]
+        );
+        assert_eq!(service.policy().spent_in_day(time), 1_100);
+    }
+
+    #[test]
+    fn test_rate_limit_and_policy_denial() {
+        let secp = Secp256k1::with_caps(ContextFlag::Commit);
+        let (session, keys) = keyed_session(&secp, 2);
+        let policy = Policy::new().rule(Rule::AmountCap(50));
+        let clock = TestClock::default();
+        let mut service = service(
+            &clock,
+            keys[1].clone(),
+            policy,
+            RateLimit::new(2, 60).unwrap(),
+        );
+        let small = spend_request(&secp, &session, PLAIN, 50, "alice");
+
+        clock.set(1_000);
+        assert!(matches!(
+            co_sign(
+                &mut service,
+                &session,
+                &spend_request(&secp, &session, PLAIN, 60, "alice"),
+                None
+            ),
+            Err(Error::PolicyDenied(_))
+        ));
+        clock.set(1_010);
+        co_sign(&mut service, &session, &small, None).unwrap();
+        clock.set(1_020);
+        assert!(matches!(
+            co_sign(&mut service, &session, &small, None),
+            Err(Error::RateLimited { retry_after: 40 })
+        ));
+        clock.set(1_060);
+        co_sign(&mut service, &session, &small, None).unwrap();
+
+        let trail = serde_json::to_string(service.audit()).unwrap();
+        let restored: Vec<AuditEntry> = serde_json::from_str(&trail).unwrap();
+        assert_eq!(restored, service.audit());
+        assert!(matches!(
+            restored[0].outcome(),
+            AuditOutcome::PolicyDenied(reason) if reason.contains("cap of 50")
+        ));
+        assert_eq!(restored[2].outcome(), &AuditOutcome::RateLimited);
+        assert_eq!(restored[2].time(), 1_020);
+
+        assert_eq!(RateLimit::new(0, 60), Err(Error::InvalidRateLimit));
+        assert_eq!(RateLimit::new(2, 0), Err(Error::InvalidRateLimit));
+    }
+
+    #[test]
+    fn test_challenge_bound_to_request() {
+        let secp = Secp256k1::with_caps(ContextFlag::Commit);
+        let (session, keys) = keyed_session(&secp, 2);
+        let clock = TestClock::default();
+        let mut service = service(
+            &clock,
+            keys[1].clone(),
+            Policy::new(),
+            RateLimit::new(10, 60).unwrap(),
+        );
+        let large = spend_request(&secp, &session, PLAIN, 500, "alice");
+        // Same amount and counterparty, different outputs
+        let twin = spend_request(&secp, &session, PLAIN, 500, "alice");
+        assert_eq!(twin.amount(), large.amount());
+
+        clock.set(1_000);
+        let code = SecondFactor::Challenge(service.issue_challenge(&large).unwrap());
+        assert_eq!(
+            co_sign(&mut service, &session, &twin, Some(code)),
+            Err(Error::SecondFactorRejected)
+        );
+
+        // Expiry follows the service's clock
+        let code = SecondFactor::Challenge(service.issue_challenge(&large).unwrap());
+        clock.set(1_000 + CHALLENGE_TTL + 1);
+        assert_eq!(
+            co_sign(&mut service, &session, &large, Some(code)),
+            Err(Error::SecondFactorRejected)
+        );
+
+        let code = SecondFactor::Challenge(service.issue_challenge(&large).unwrap());
+        co_sign(&mut service, &session, &large, Some(code)).unwrap();
+    }
+
+    #[test]
+    fn test_service_owns_its_nonce() {
+        let secp = Secp256k1::with_caps(ContextFlag::Commit);
+        let (session, keys) = keyed_session(&secp, 2);
+        let clock = TestClock::default();
+        let mut service = service(
+            &clock,
+            keys[1].clone(),
+            Policy::new(),
+            RateLimit::new(10, 60).unwrap(),
+        );
+        let request = spend_request(&secp, &session, PLAIN, 100, "alice");
+
+        // A session without the service's key gets no nonce
+        let (stranger, _) = keyed_session(&secp, 2);
+        assert_eq!(service.round1(&stranger), Err(Error::NotASigner));
+
+        let user = session.round1_generate_nonces().unwrap();
+        assert_eq!(
+            service.co_sign(&session, &request, None, &user.public_nonces().0),
+            Err(Error::MissingNonce)
+        );
+
+        let (commitment, revealed) = service.round1(&session).unwrap();
+        let agg_nonce = session
+            .round2_aggregate_nonces(
+                &[*user.commitment(), commitment],
+                &[*user.public_nonces(), revealed],
+            )
+            .unwrap();
+        // A denial leaves the nonce unspent
+        let large = spend_request(&secp, &session, PLAIN, 500, "alice");
+        assert_eq!(
+            service.co_sign(&session, &large, None, &agg_nonce),
+            Err(Error::SecondFactorRequired)
+        );
+        let partial = service
+            .co_sign(&session, &request, None, &agg_nonce)
+            .unwrap();
+        let service_key = session.participants()[1].public_key();
+        assert!(session.verify_partial_signature(
+            request.message(),
+            &partial,
+            service_key,
+            &revealed,
+            &agg_nonce,
+            &session.aggregate_pubkeys().unwrap(),
+        ));
+
+        // Signing spent it
+        assert_eq!(
+            service.co_sign(&session, &request, None, &agg_nonce),
+            Err(Error::MissingNonce)
+        );
+    }
+}
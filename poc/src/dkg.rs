@@ -0,0 +1,759 @@
+//! Pedersen distributed key generation with complaints
+//!
+//! Every member deals a random polynomial, so the group secret is the sum of
+//! all constant terms and no single machine ever holds it. Shares are checked
+//! against Feldman commitments; a recipient of a bad share complains, and the
+//! dealer must answer by publishing the share. Dealers that cannot answer
+//! are disqualified and their polynomials are dropped by everyone.
+//!
+//! # Algorithm
+//! ```text
+//! Round 1 (broadcast): f_i random of degree t-1, C_i = commitments to f_i,
+//!                      proof of knowledge (R_i, mu_i) of a_i0:
+//!                      c = H(i || C_i0 || R_i),  mu_i = k + c * a_i0
+//! Round 2 (private):   verify every proof, send f_i(j) to member j
+//! Complaints:          j complains about i if f_i(j)*G != sum(C_ik * j^k)
+//! Responses:           i publishes f_i(j); a valid answer clears i
+//! QUAL:                dealers without an unanswered complaint from a member
+//! Result:              s_j = sum_{i in QUAL} f_i(j),  Y = sum_{i in QUAL} C_i0
+//! ```
+//!
+//! Complaints and responses are broadcast, so every member computes the same
+//! qualified set.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use rand::thread_rng;
+use secp256k1zkp::{PublicKey, Secp256k1, SecretKey};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::frost::{KeyPackage, PublicKeyPackage};
+use crate::scalar;
+use crate::ser::{hex_pubkey, hex_seckey};
+use crate::types::ParticipantId;
+use crate::vss::{check_threshold, Polynomial, VssCommitment};
+
+/// Domain separator for proof of knowledge challenges
+const POK_DOMAIN: &[u8] = b"grin-multisig/dkg/pok";
+
+/// Schnorr proof of knowledge of a dealer's constant term
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofOfKnowledge {
+    /// Nonce commitment `R = k * G`
+    #[serde(with = "hex_pubkey")]
+    commitment: PublicKey,
+
+    /// Response `mu = k + c * a_0`
+    #[serde(with = "hex_seckey")]
+    response: SecretKey,
+}
+
+impl ProofOfKnowledge {
+    /// Prove knowledge of `secret` on behalf of `participant`
+    pub(crate) fn prove(
+        secp: &Secp256k1,
+        participant: ParticipantId,
+        secret: &SecretKey,
+    ) -> Result<Self> {
+        let nonce = SecretKey::new(secp, &mut thread_rng());
+        let commitment = PublicKey::from_secret_key(secp, &nonce)?;
+        let public = PublicKey::from_secret_key(secp, secret)?;
+        let challenge = Self::challenge(secp, participant, &public, &commitment)?;
+        let response = scalar::add(secp, &nonce, &scalar::mul(secp, &challenge, secret)?)?;
+        Ok(Self {
+            commitment,
+            response,
+        })
+    }
+
+    /// Check `mu * G == R + c * C_0`
+    pub(crate) fn verify(
+        &self,
+        secp: &Secp256k1,
+        participant: ParticipantId,
+        public: &PublicKey,
+    ) -> Result<()> {
+        let challenge = Self::challenge(secp, participant, public, &self.commitment)?;
+        let expected = PublicKey::from_combination(
+            secp,
+            vec![
+                &self.commitment,
+                &scalar::mul_point(secp, public, &challenge)?,
+            ],
+        )?;
+        if PublicKey::from_secret_key(secp, &self.response)? == expected {
+            Ok(())
+        } else {
+            Err(Error::InvalidProofOfKnowledge { participant })
+        }
+    }
+
+    fn challenge(
+        secp: &Secp256k1,
+        participant: ParticipantId,
+        public: &PublicKey,
+        commitment: &PublicKey,
+    ) -> Result<SecretKey> {
+        scalar::hash_to_scalar(
+            secp,
+            POK_DOMAIN,
+            &[
+                &participant.inner().to_be_bytes(),
+                &scalar::point_bytes(secp, public),
+                &scalar::point_bytes(secp, commitment),
+            ],
+        )
+    }
+}
+
+/// Round 1 broadcast: a dealer's commitments and proof of knowledge
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Round1Package {
+    /// Dealer
+    participant: ParticipantId,
+
+    /// Commitments to the dealer's polynomial
+    commitment: VssCommitment,
+
+    /// Proof of knowledge of the constant term
+    proof_of_knowledge: ProofOfKnowledge,
+}
+
+impl Round1Package {
+    /// Get the dealer
+    pub const fn participant(&self) -> ParticipantId {
+        self.participant
+    }
+
+    /// Get the dealer's commitments
+    pub const fn commitment(&self) -> &VssCommitment {
+        &self.commitment
+    }
+
+    /// Get the proof of knowledge
+    pub const fn proof_of_knowledge(&self) -> &ProofOfKnowledge {
+        &self.proof_of_knowledge
+    }
+}
+
+/// Round 1 state kept by the dealer
+#[derive(Debug, Clone)]
+pub struct Round1SecretPackage {
+    /// This member
+    participant: ParticipantId,
+
+    /// Every group member, including this one
+    participants: Vec<ParticipantId>,
+
+    /// Threshold `t`
+    min_signers: usize,
+
+    /// Secret polynomial
+    polynomial: Polynomial,
+
+    /// Commitments to the polynomial
+    commitment: VssCommitment,
+}
+
+/// Round 2 private message: a dealer's share for one recipient
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Round2Package {
+    /// Dealer
+    dealer: ParticipantId,
+
+    /// Recipient
+    recipient: ParticipantId,
+
+    /// Share `f_dealer(recipient)`
+    #[serde(with = "hex_seckey")]
+    share: SecretKey,
+}
+
+impl Round2Package {
+    /// Create a share message from already known values
+    pub const fn new(dealer: ParticipantId, recipient: ParticipantId, share: SecretKey) -> Self {
+        Self {
+            dealer,
+            recipient,
+            share,
+        }
+    }
+
+    /// Get the dealer
+    pub const fn dealer(&self) -> ParticipantId {
+        self.dealer
+    }
+
+    /// Get the recipient
+    pub const fn recipient(&self) -> ParticipantId {
+        self.recipient
+    }
+
+    /// Get the share
+    pub const fn share(&self) -> &SecretKey {
+        &self.share
+    }
+}
+
+/// Round 2 state kept by each member
+#[derive(Debug, Clone)]
+pub struct Round2SecretPackage {
+    /// This member
+    participant: ParticipantId,
+
+    /// Threshold `t`
+    min_signers: usize,
+
+    /// Secret polynomial
+    polynomial: Polynomial,
+
+    /// Verified commitments of every dealer, including this one
+    commitments: BTreeMap<ParticipantId, VssCommitment>,
+}
+
+impl Round2SecretPackage {
+    /// Get this member's ID
+    pub const fn participant(&self) -> ParticipantId {
+        self.participant
+    }
+
+    /// Get every dealer's verified commitments
+    pub const fn commitments(&self) -> &BTreeMap<ParticipantId, VssCommitment> {
+        &self.commitments
+    }
+}
+
+/// Broadcast accusation that a dealer's share is missing or invalid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Complaint {
+    /// Recipient of the bad share
+    recipient: ParticipantId,
+
+    /// Dealer of the bad share
+    dealer: ParticipantId,
+}
+
+impl Complaint {
+    /// Create a complaint
+    pub const fn new(recipient: ParticipantId, dealer: ParticipantId) -> Self {
+        Self { recipient, dealer }
+    }
+
+    /// Get the recipient of the bad share
+    pub const fn recipient(&self) -> ParticipantId {
+        self.recipient
+    }
+
+    /// Get the dealer of the bad share
+    pub const fn dealer(&self) -> ParticipantId {
+        self.dealer
+    }
+}
+
+/// Broadcast answer to a complaint: the disputed share in the clear
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComplaintResponse {
+    /// Complaint being answered
+    complaint: Complaint,
+
+    /// Share `f_dealer(recipient)`
+    #[serde(with = "hex_seckey")]
+    share: SecretKey,
+}
+
+impl ComplaintResponse {
+    /// Get the complaint being answered
+    pub const fn complaint(&self) -> &Complaint {
+        &self.complaint
+    }
+
+    /// Get the revealed share
+    pub const fn share(&self) -> &SecretKey {
+        &self.share
+    }
+
+    /// Check the revealed share against the dealer's commitments
+    fn is_valid(&self, secp: &Secp256k1, commitment: &VssCommitment) -> bool {
+        share_matches(secp, commitment, self.complaint.recipient, &self.share)
+    }
+}
+
+/// Start the DKG: deal a random polynomial and prove knowledge of its constant term
+///
+/// # Arguments
+/// * `secp` - Secp256k1 context
+/// * `participant` - This member's ID, also its share index
+/// * `min_signers` - Threshold `t`
+/// * `participants` - Every group member, including this one
+///
+/// # Returns
+/// Secret state to keep and the package to broadcast
+///
+/// # Errors
+/// Returns `Error::InvalidThreshold` unless `1 <= t <= n`
+/// Returns `Error::UnknownParticipant` if `participant` is not in `participants`
+pub fn part1(
+    secp: &Secp256k1,
+    participant: ParticipantId,
+    min_signers: usize,
+    participants: &[ParticipantId],
+) -> Result<(Round1SecretPackage, Round1Package)> {
+    check_threshold(min_signers, participants)?;
+    if !participants.contains(&participant) {
+        return Err(Error::UnknownParticipant { participant });
+    }
+
+    let polynomial = Polynomial::random(secp, SecretKey::new(secp, &mut thread_rng()), min_signers);
+    let commitment = polynomial.commit(secp)?;
+    let proof_of_knowledge = ProofOfKnowledge::prove(secp, participant, polynomial.constant())?;
+
+    let package = Round1Package {
+        participant,
+        commitment: commitment.clone(),
+        proof_of_knowledge,
+    };
+    let secret = Round1SecretPackage {
+        participant,
+        participants: participants.to_vec(),
+        min_signers,
+        polynomial,
+        commitment,
+    };
+    Ok((secret, package))
+}
+
+/// Verify every dealer's round 1 package and compute the shares to send
+///
+/// # Arguments
+/// * `secp` - Secp256k1 context
+/// * `secret` - State from [`part1`]
+/// * `round1_packages` - Round 1 packages of every other member, keyed by dealer
+///
+/// # Returns
+/// Secret state to keep and one private package per recipient
+///
+/// # Errors
+/// Returns `Error::MissingMessage` if a member's package is missing
+/// Returns `Error::UnknownParticipant` if a package comes from a non-member
+/// Returns `Error::InvalidCommitment` if a commitment has the wrong degree
+/// Returns `Error::InvalidProofOfKnowledge` if a proof does not verify
+pub fn part2(
+    secp: &Secp256k1,
+    secret: Round1SecretPackage,
+    round1_packages: &BTreeMap<ParticipantId, Round1Package>,
+) -> Result<(Round2SecretPackage, BTreeMap<ParticipantId, Round2Package>)> {
+    for (&dealer, package) in round1_packages {
+        if dealer != package.participant
+            || dealer == secret.participant
+            || !secret.participants.contains(&dealer)
+        {
+            return Err(Error::UnknownParticipant {
+                participant: package.participant,
+            });
+        }
+    }
+
+    let mut commitments = BTreeMap::new();
+    let mut shares = BTreeMap::new();
+    for &member in &secret.participants {
+        if member == secret.participant {
+            continue;
+        }
+        let package = round1_packages.get(&member).ok_or(Error::MissingMessage {
+            participant: member,
+        })?;
+        if package.commitment.threshold() != secret.min_signers {
+            return Err(Error::InvalidCommitment {
+                participant: member,
+            });
+        }
+        package
+            .proof_of_knowledge
+            .verify(secp, member, &package.commitment.group_public_key()?)?;
+
+        commitments.insert(member, package.commitment.clone());
+        shares.insert(
+            member,
+            Round2Package::new(
+                secret.participant,
+                member,
+                secret.polynomial.evaluate(secp, member)?,
+            ),
+        );
+    }
+    commitments.insert(secret.participant, secret.commitment);
+
+    let state = Round2SecretPackage {
+        participant: secret.participant,
+        min_signers: secret.min_signers,
+        polynomial: secret.polynomial,
+        commitments,
+    };
+    Ok((state, shares))
+}
+
+/// Check the shares received in round 2 and list the dealers to complain about
+///
+/// # Arguments
+/// * `secp` - Secp256k1 context
+/// * `secret` - State from [`part2`]
+/// * `round2_packages` - Shares received, keyed by dealer
+///
+/// # Returns
+/// One complaint for every dealer whose share is missing or invalid
+pub fn verify_shares(
+    secp: &Secp256k1,
+    secret: &Round2SecretPackage,
+    round2_packages: &BTreeMap<ParticipantId, Round2Package>,
+) -> Vec<Complaint> {
+    secret
+        .commitments
+        .iter()
+        .filter(|(&dealer, _)| dealer != secret.participant)
+        .filter(|(dealer, commitment)| {
+            !round2_packages.get(dealer).is_some_and(|package| {
+                package.recipient == secret.participant
+                    && share_matches(secp, commitment, secret.participant, &package.share)
+            })
+        })
+        .map(|(&dealer, _)| Complaint::new(secret.participant, dealer))
+        .collect()
+}
+
+/// Answer a complaint against this member by revealing the disputed share
+///
+/// # Errors
+/// Returns `Error::UnknownParticipant` if the complaint is against another
+/// member or comes from a non-member
+pub fn respond(
+    secp: &Secp256k1,
+    secret: &Round2SecretPackage,
+    complaint: &Complaint,
+) -> Result<ComplaintResponse> {
+    if complaint.dealer != secret.participant {
+        return Err(Error::UnknownParticipant {
+            participant: complaint.dealer,
+        });
+    }
+    if !secret.commitments.contains_key(&complaint.recipient) {
+        return Err(Error::UnknownParticipant {
+            participant: complaint.recipient,
+        });
+    }
+    Ok(ComplaintResponse {
+        complaint: *complaint,
+        share: secret.polynomial.evaluate(secp, complaint.recipient)?,
+    })
+}
+
+/// Compute the qualified dealer set from the broadcast complaints and responses
+///
+/// A dealer is disqualified if any complaint against it lacks a response
+/// whose revealed share matches the dealer's commitments. Complaints naming a
+/// recipient outside the group are ignored, since the dealer cannot answer
+/// them.
+///
+/// # Arguments
+/// * `secp` - Secp256k1 context
+/// * `commitments` - Every dealer's commitments
+/// * `complaints` - All broadcast complaints
+/// * `responses` - All broadcast responses
+///
+/// # Returns
+/// IDs of the dealers whose polynomials count towards the group key
+pub fn qualified_dealers(
+    secp: &Secp256k1,
+    commitments: &BTreeMap<ParticipantId, VssCommitment>,
+    complaints: &[Complaint],
+    responses: &[ComplaintResponse],
+) -> BTreeSet<ParticipantId> {
+    commitments
+        .iter()
+        .filter(|(&dealer, commitment)| {
+            complaints
+                .iter()
+                .filter(|c| c.dealer == dealer && commitments.contains_key(&c.recipient))
+                .all(|complaint| {
+                    responses
+                        .iter()
+                        .any(|r| r.complaint == *complaint && r.is_valid(secp, commitment))
+                })
+        })
+        .map(|(&dealer, _)| dealer)
+        .collect()
+}
+
+/// Finish the DKG once complaints are resolved
+///
+/// Shares revealed in valid complaint responses replace the ones received in
+/// round 2.
+///
+/// # Arguments
+/// * `secp` - Secp256k1 context
+/// * `secret` - State from [`part2`]
+/// * `round2_packages` - Shares received, keyed by dealer
+/// * `complaints` - All broadcast complaints
+/// * `responses` - All broadcast responses
+///
+/// # Returns
+/// This member's key package and the group's public key package
+///
+/// # Errors
+/// Returns `Error::NoParticipants` if every dealer was disqualified
+/// Returns `Error::InvalidDealerShare` if a qualified dealer's share is still
+/// missing or invalid
+pub fn part3(
+    secp: &Secp256k1,
+    secret: &Round2SecretPackage,
+    round2_packages: &BTreeMap<ParticipantId, Round2Package>,
+    complaints: &[Complaint],
+    responses: &[ComplaintResponse],
+) -> Result<(KeyPackage, PublicKeyPackage)> {
+    let qualified = qualified_dealers(secp, &secret.commitments, complaints, responses);
+
+    let mut shares = Vec::with_capacity(qualified.len());
+    for &dealer in &qualified {
+        if dealer == secret.participant {
+            shares.push(secret.polynomial.evaluate(secp, dealer)?);
+            continue;
+        }
+
+        let commitment = &secret.commitments[&dealer];
+        let revealed = responses.iter().find(|r| {
+            r.complaint == Complaint::new(secret.participant, dealer)
+                && r.is_valid(secp, commitment)
+        });
+        let share = match revealed {
+            Some(response) => response.share.clone(),
+            None => round2_packages
+                .get(&dealer)
+                .filter(|p| share_matches(secp, commitment, secret.participant, &p.share))
+                .map(|p| p.share.clone())
+                .ok_or(Error::InvalidDealerShare {
+                    dealer,
+                    participant: secret.participant,
+                })?,
+        };
+        shares.push(share);
+    }
+
+    let mut signing_share = shares.pop().ok_or(Error::NoParticipants)?;
+    for share in &shares {
+        signing_share.add_assign(secp, share)?;
+    }
+
+    let group_commitment = VssCommitment::sum(
+        secp,
+        &qualified
+            .iter()
+            .map(|dealer| &secret.commitments[dealer])
+            .collect::<Vec<_>>(),
+    )?;
+    let group_public_key = group_commitment.group_public_key()?;
+    let verifying_shares = secret
+        .commitments
+        .keys()
+        .map(|&member| Ok((member, group_commitment.verifying_share(secp, member)?)))
+        .collect::<Result<BTreeMap<_, _>>>()?;
+
+    let key_package = KeyPackage::new(
+        secp,
+        secret.participant,
+        signing_share,
+        group_public_key,
+        secret.min_signers,
+    )?;
+    let public_keys = PublicKeyPackage::new(verifying_shares, group_public_key, secret.min_signers);
+    Ok((key_package, public_keys))
+}
+
+/// Whether `share * G` matches the value the commitments give for `participant`
+fn share_matches(
+    secp: &Secp256k1,
+    commitment: &VssCommitment,
+    participant: ParticipantId,
+    share: &SecretKey,
+) -> bool {
+    match (
+        commitment.verifying_share(secp, participant),
+        PublicKey::from_secret_key(secp, share),
+    ) {
+        (Ok(expected), Ok(actual)) => expected == actual,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::try_sign;
+
+    fn ids(n: u32) -> Vec<ParticipantId> {
+        (1..=n).map(ParticipantId::new).collect()
+    }
+
+    /// Run rounds 1 and 2 for every member
+    fn deal(
+        secp: &Secp256k1,
+        t: usize,
+        n: u32,
+    ) -> (
+        Vec<Round2SecretPackage>,
+        BTreeMap<ParticipantId, BTreeMap<ParticipantId, Round2Package>>,
+    ) {
+        let members = ids(n);
+        let round1: Vec<_> = members
+            .iter()
+            .map(|&id| part1(secp, id, t, &members).unwrap())
+            .collect();
+
+        let mut secrets = Vec::new();
+        let mut inbox: BTreeMap<_, BTreeMap<_, _>> = BTreeMap::new();
+        for (secret, own) in round1.iter().cloned() {
+            let others = round1
+                .iter()
+                .filter(|(_, p)| p.participant() != own.participant())
+                .map(|(_, p)| (p.participant(), p.clone()))
+                .collect();
+            let (state, outgoing) = part2(secp, secret, &others).unwrap();
+            for (recipient, package) in outgoing {
+                inbox
+                    .entry(recipient)
+                    .or_default()
+                    .insert(package.dealer(), package);
+            }
+            secrets.push(state);
+        }
+        (secrets, inbox)
+    }
+
+    #[test]
+    fn test_honest_dkg_agrees_on_group_key() {
+        let secp = Secp256k1::new();
+        let (secrets, inbox) = deal(&secp, 2, 3);
+
+        let results: Vec<_> = secrets
+            .iter()
+            .map(|s| {
+                let received = &inbox[&s.participant()];
+                assert!(verify_shares(&secp, s, received).is_empty());
+                part3(&secp, s, received, &[], &[]).unwrap()
+            })
+            .collect();
+
+        let public_keys = &results[0].1;
+        for (key, public) in &results {
+            assert_eq!(public, public_keys);
+            assert_eq!(
+                key.verifying_share(),
+                public_keys.verifying_share(key.participant()).unwrap()
+            );
+        }
+        assert!(try_sign(&secp, &[&results[0].0, &results[2].0], public_keys).is_ok());
+    }
+
+    #[test]
+    fn test_complaints_resolved_or_disqualify() {
+        let secp = Secp256k1::new();
+        let (secrets, mut inbox) = deal(&secp, 2, 3);
+        let (p1, p2, p3) = (
+            ParticipantId::new(1),
+            ParticipantId::new(2),
+            ParticipantId::new(3),
+        );
+
+        // Dealer 1 sends member 2 a bad share and later answers honestly;
+        // dealer 3 sends member 2 a bad share and never answers
+        let junk = SecretKey::new(&secp, &mut thread_rng());
+        for dealer in [p1, p3] {
+            inbox
+                .get_mut(&p2)
+                .unwrap()
+                .insert(dealer, Round2Package::new(dealer, p2, junk.clone()));
+        }
+
+        let complaints = verify_shares(&secp, &secrets[1], &inbox[&p2]);
+        assert_eq!(
+            complaints,
+            vec![Complaint::new(p2, p1), Complaint::new(p2, p3)]
+        );
+
+        let responses = vec![respond(&secp, &secrets[0], &complaints[0]).unwrap()];
+        assert_eq!(
+            qualified_dealers(&secp, secrets[0].commitments(), &complaints, &responses),
+            BTreeSet::from([p1, p2])
+        );
+
+        let results: Vec<_> = secrets
+            .iter()
+            .map(|s| part3(&secp, s, &inbox[&s.participant()], &complaints, &responses).unwrap())
+            .collect();
+        let public_keys = &results[0].1;
+        assert!(results.iter().all(|(_, p)| p == public_keys));
+        assert_eq!(
+            *public_keys.group_public_key(),
+            PublicKey::from_combination(
+                &secp,
+                vec![
+                    &secrets[0].commitments()[&p1].group_public_key().unwrap(),
+                    &secrets[0].commitments()[&p2].group_public_key().unwrap(),
+                ],
+            )
+            .unwrap()
+        );
+        assert!(try_sign(&secp, &[&results[1].0, &results[2].0], public_keys).is_ok());
+
+        // Skipping the complaint phase leaves member 2 with an unusable share
+        assert_eq!(
+            part3(&secp, &secrets[1], &inbox[&p2], &[], &[]).unwrap_err(),
+            Error::InvalidDealerShare {
+                dealer: p1,
+                participant: p2
+            }
+        );
+    }
+
+    #[test]
+    fn test_complaint_from_non_member_ignored() {
+        let secp = Secp256k1::new();
+        let (secrets, _) = deal(&secp, 2, 3);
+        let outsider = ParticipantId::new(9);
+        let framed = Complaint::new(outsider, ParticipantId::new(1));
+
+        // The dealer cannot answer, so the complaint must not count
+        assert_eq!(
+            respond(&secp, &secrets[0], &framed).unwrap_err(),
+            Error::UnknownParticipant {
+                participant: outsider
+            }
+        );
+        assert_eq!(
+            qualified_dealers(&secp, secrets[0].commitments(), &[framed], &[]),
+            BTreeSet::from_iter(ids(3))
+        );
+    }
+
+    #[test]
+    fn test_forged_proof_of_knowledge_rejected() {
+        let secp = Secp256k1::new();
+        let members = ids(3);
+        let (secret, _) = part1(&secp, members[0], 2, &members).unwrap();
+        let (_, honest) = part1(&secp, members[1], 2, &members).unwrap();
+        let (_, other) = part1(&secp, members[2], 2, &members).unwrap();
+
+        // Member 3 replays member 2's proof for its own commitments
+        let forged = Round1Package {
+            participant: members[2],
+            commitment: other.commitment().clone(),
+            proof_of_knowledge: honest.proof_of_knowledge().clone(),
+        };
+        let packages = BTreeMap::from([(members[1], honest), (members[2], forged)]);
+        assert_eq!(
+            part2(&secp, secret, &packages).unwrap_err(),
+            Error::InvalidProofOfKnowledge {
+                participant: members[2]
+            }
+        );
+    }
+}
@@ -0,0 +1,793 @@
+//! Discreet log contracts
+//!
+//! Two parties bet on an event an oracle will attest to. Both fund a
+//! [`SharedOutput`](crate::spend::SharedOutput) behind a co-signed refund
+//! ([`FundingBuilder`]). Before funding, they co-sign one contract execution
+//! transaction (CET) per outcome, each as an adaptor pre-signature locked to
+//! that outcome's attestation point. The oracle announces a nonce ahead of
+//! the event, so every attestation point can be computed in advance; its
+//! attestation of the actual outcome is exactly the secret completing that
+//! outcome's CET. The oracle never learns about the contract, and no other
+//! CET can be completed.
+//!
+//! The funding kernel message is only handed out once the refund and every
+//! CET are signed, so no funding transaction can exist before every outcome
+//! is settleable. If the oracle never attests, the refund returns both
+//! contributions once its lock height is reached.
+//!
+//! # Algorithm
+//! ```text
+//! Announce:  oracle key P = x*G, nonce R = k*G, outcomes m_1..m_n
+//! Point:     S_i = R + e_i*P                  e_i = H(R, P, m_i)
+//! Negotiate: co-sign refund     C -> both parties, HeightLocked { lock_height }
+//!            pre-sign CET_i     C -> payouts of m_i, adaptor point S_i
+//!            co-sign funding    both parties' inputs -> C + change
+//! Attest:    oracle reveals s_i = k + e_i*x   (s_i*G = S_i)
+//! Settle:    complete CET_i with s_i and broadcast
+//! Timeout:   no attestation by lock_height -> broadcast refund
+//! ```
+//!
+//! An oracle must attest to a single outcome per nonce: two attestations
+//! under the same nonce reveal its key.
+
+use rand::thread_rng;
+use secp256k1zkp::pedersen::RangeProof;
+use secp256k1zkp::{ContextFlag, PublicKey, Secp256k1, SecretKey};
+
+use crate::adaptor::PreSignature;
+use crate::error::{Error, Result};
+use crate::funding::FundingBuilder;
+use crate::offset::OffsetShare;
+use crate::participant::Participant;
+use crate::scalar;
+use crate::session::Session;
+use crate::spend::{SharedOutput, SpendBuilder};
+use crate::transaction::{KernelFeatures, Output, Transaction};
+use crate::types::PartialSignature;
+use crate::validation::validate;
+
+/// Domain separator of the attestation challenge
+const ATTESTATION_DOMAIN: &[u8] = b"grin-multisig/dlc-attestation";
+
+/// Oracle's commitment to attest to one event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Announcement {
+    /// Oracle's public key `P`
+    public_key: PublicKey,
+
+    /// Nonce `R` the attestation will use
+    nonce: PublicKey,
+
+    /// Possible outcomes
+    outcomes: Vec<String>,
+}
+
+impl Announcement {
+    /// Create an announcement
+    pub const fn new(public_key: PublicKey, nonce: PublicKey, outcomes: Vec<String>) -> Self {
+        Self {
+            public_key,
+            nonce,
+            outcomes,
+        }
+    }
+
+    /// Get the oracle's public key
+    pub const fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    /// Get the attestation nonce
+    pub const fn nonce(&self) -> &PublicKey {
+        &self.nonce
+    }
+
+    /// Get the possible outcomes
+    pub fn outcomes(&self) -> &[String] {
+        &self.outcomes
+    }
+
+    /// Compute the point an attestation of `outcome` is the secret of
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidContract` if the outcome is not announced
+    /// Returns `Error::Crypto` if the point cannot be computed
+    pub fn attestation_point(&self, secp: &Secp256k1, outcome: &str) -> Result<PublicKey> {
+        if !self.outcomes.iter().any(|o| o == outcome) {
+            return Err(Error::InvalidContract(format!(
+                "outcome {outcome:?} is not announced"
+            )));
+        }
+        let e = self.challenge(secp, outcome)?;
+        let tweaked = scalar::mul_point(secp, &self.public_key, &e)?;
+        Ok(PublicKey::from_combination(
+            secp,
+            vec![&self.nonce, &tweaked],
+        )?)
+    }
+
+    /// `e = H(R, P, m)`
+    fn challenge(&self, secp: &Secp256k1, outcome: &str) -> Result<SecretKey> {
+        scalar::hash_to_scalar(
+            secp,
+            ATTESTATION_DOMAIN,
+            &[
+                &scalar::point_bytes(secp, &self.nonce),
+                &scalar::point_bytes(secp, &self.public_key),
+                outcome.as_bytes(),
+            ],
+        )
+    }
+}
+
+/// Oracle's signature on the outcome that occurred
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attestation {
+    /// Attested outcome
+    outcome: String,
+
+    /// Secret `s = k + e*x` of the outcome's attestation point
+    secret: SecretKey,
+}
+
+impl Attestation {
+    /// Create an attestation received from an oracle
+    pub const fn new(outcome: String, secret: SecretKey) -> Self {
+        Self { outcome, secret }
+    }
+
+    /// Get the attested outcome
+    pub fn outcome(&self) -> &str {
+        &self.outcome
+    }
+
+    /// Get the attestation secret
+    pub const fn secret(&self) -> &SecretKey {
+        &self.secret
+    }
+}
+
+/// In-process oracle for a single event
+#[derive(Debug)]
+pub struct LocalOracle {
+    /// Context for signing
+    secp: Secp256k1,
+
+    /// Oracle key `x`
+    key: SecretKey,
+
+    /// Event nonce `k`
+    nonce: SecretKey,
+
+    /// Announcement of the event
+    announcement: Announcement,
+}
+
+impl LocalOracle {
+    /// Announce an event with the given outcomes under a fresh key and nonce
+    ///
+    /// # Errors
+    /// Returns `Error::Crypto` if the public key or nonce cannot be derived
+    pub fn new(outcomes: Vec<String>) -> Result<Self> {
+        let secp = Secp256k1::with_caps(ContextFlag::Commit);
+        let key = SecretKey::new(&secp, &mut thread_rng());
+        let nonce = SecretKey::new(&secp, &mut thread_rng());
+        let announcement = Announcement::new(
+            PublicKey::from_secret_key(&secp, &key)?,
+            PublicKey::from_secret_key(&secp, &nonce)?,
+            outcomes,
+        );
+        Ok(Self {
+            secp,
+            key,
+            nonce,
+            announcement,
+        })
+    }
+
+    /// Get the announcement of the event
+    pub const fn announcement(&self) -> &Announcement {
+        &self.announcement
+    }
+
+    /// Attest to the outcome that occurred
+    ///
+    /// Consumes the oracle, as the nonce must not sign a second outcome.
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidContract` if the outcome is not announced
+    /// Returns `Error::Crypto` if signing fails
+    pub fn attest(self, outcome: &str) -> Result<Attestation> {
+        // Validates the outcome
+        self.announcement.attestation_point(&self.secp, outcome)?;
+        let e = self.announcement.challenge(&self.secp, outcome)?;
+        let secret = scalar::add(
+            &self.secp,
+            &self.nonce,
+            &scalar::mul(&self.secp, &e, &self.key)?,
+        )?;
+        Ok(Attestation::new(outcome.to_string(), secret))
+    }
+}
+
+/// Pre-signed CET, ready to be completed with the attestation
+#[derive(Debug, Clone)]
+struct SignedExecution {
+    /// Kernel session participants
+    participants: Vec<Participant>,
+
+    /// Offset shares of the CET
+    offset_shares: Vec<OffsetShare>,
+
+    /// Pre-signature locked to the outcome's attestation point
+    pre_signature: PreSignature,
+}
+
+/// CET paying out one outcome
+#[derive(Debug, Clone)]
+struct Execution {
+    /// Outcome it pays out
+    outcome: String,
+
+    /// Spend of the shared output to the outcome's payouts
+    cet: SpendBuilder,
+
+    /// Co-signed data, once stored
+    signed: Option<SignedExecution>,
+}
+
+/// One party's view of a discreet log contract
+#[derive(Debug, Clone)]
+pub struct Dlc {
+    /// Funding of the shared output, with its refund to both parties
+    funding: FundingBuilder,
+
+    /// Co-signed funding transaction
+    funding_tx: Option<Transaction>,
+
+    /// Oracle announcement the contract is settled by
+    announcement: Announcement,
+
+    /// One CET per announced outcome
+    executions: Vec<Execution>,
+}
+
+impl Dlc {
+    /// Start negotiating a contract
+    ///
+    /// # Arguments
+    /// * `funding` - Both parties' funding of the shared output, refunding
+    ///   each contribution at its lock height
+    /// * `announcement` - Oracle announcement of the event
+    /// * `cet_fee` - Fee of every CET
+    /// * `payouts` - Outputs each outcome pays, one entry per announced outcome
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidContract` if the payouts do not cover every
+    /// announced outcome exactly once
+    pub fn new(
+        funding: FundingBuilder,
+        announcement: Announcement,
+        cet_fee: u64,
+        payouts: Vec<(String, Vec<Output>)>,
+    ) -> Result<Self> {
+        for outcome in announcement.outcomes() {
+            let count = payouts.iter().filter(|(o, _)| o == outcome).count();
+            if count != 1 {
+                return Err(Error::InvalidContract(format!(
+                    "outcome {outcome:?} has {count} payouts instead of one"
+                )));
+            }
+        }
+        if payouts.len() != announcement.outcomes().len() {
+            return Err(Error::InvalidContract(
+                "payouts for outcomes that are not announced".into(),
+            ));
+        }
+
+        let shared = *funding.shared_output();
+        let executions = payouts
+            .into_iter()
+            .map(|(outcome, outputs)| Execution {
+                outcome,
+                cet: outputs.into_iter().fold(
+                    SpendBuilder::new(shared, KernelFeatures::Plain { fee: cet_fee }),
+                    SpendBuilder::output,
+                ),
+                signed: None,
+            })
+            .collect();
+        Ok(Self {
+            funding,
+            funding_tx: None,
+            announcement,
+            executions,
+        })
+    }
+
+    /// Get the shared output both parties fund
+    pub const fn shared_output(&self) -> &SharedOutput {
+        self.funding.shared_output()
+    }
+
+    /// Get the refund to co-sign first, with
+    /// [`SharedOutput::spend_blinding`](crate::spend::SharedOutput::spend_blinding)
+    pub const fn refund(&self) -> &SpendBuilder {
+        self.funding.refund()
+    }
+
+    /// Message both parties sign in the funding kernel session
+    ///
+    /// # Errors
+    /// Returns `Error::MissingRefund` until the refund is stored
+    /// Returns `Error::InvalidContract` until every CET is pre-signed
+    pub fn funding_message(&self) -> Result<[u8; 32]> {
+        if self.funding.signed_refund().is_none() {
+            return Err(Error::MissingRefund);
+        }
+        if let Some(unsigned) = self.executions.iter().find(|e| e.signed.is_none()) {
+            return Err(Error::InvalidContract(format!(
+                "CET of {:?} is not pre-signed",
+                unsigned.outcome
+            )));
+        }
+        self.funding.kernel_message()
+    }
+
+    /// Combine offset shares and signatures into the funding transaction
+    ///
+    /// # Arguments
+    /// * `session` - Kernel session over both parties' excess keys
+    /// * `shared_proof` - Range proof for the shared output
+    /// * `offset_shares` - One offset share per party
+    /// * `partials` - Partial signatures over [`Self::funding_message`]
+    /// * `revealed_nonces` - Public nonces revealed in Round 2
+    /// * `agg_nonce` - Aggregated nonce from Round 2
+    ///
+    /// # Errors
+    /// Returns the errors of [`Self::funding_message`] and
+    /// [`FundingBuilder::finalize`]
+    pub fn finalize_funding(
+        &self,
+        session: &Session,
+        shared_proof: RangeProof,
+        offset_shares: &[OffsetShare],
+        partials: &[PartialSignature],
+        revealed_nonces: &[(PublicKey, PublicKey)],
+        agg_nonce: &PublicKey,
+    ) -> Result<Transaction> {
+        self.funding_message()?;
+        self.funding.finalize(
+            session,
+            shared_proof,
+            offset_shares,
+            partials,
+            revealed_nonces,
+            agg_nonce,
+        )
+    }
+
+    /// Get the oracle announcement
+    pub const fn announcement(&self) -> &Announcement {
+        &self.announcement
+    }
+
+    /// Get the CET of an outcome to pre-sign, with
+    /// [`SharedOutput::spend_blinding`](crate::spend::SharedOutput::spend_blinding)
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidContract` if the outcome is not announced
+    pub fn execution(&self, outcome: &str) -> Result<&SpendBuilder> {
+        Ok(&self.find(outcome)?.cet)
+    }
+
+    /// Check and store the co-signed refund
+    ///
+    /// # Errors
+    /// Returns the errors of [`FundingBuilder::store_refund`]
+    pub fn store_refund(&mut self, secp: &Secp256k1, refund: Transaction) -> Result<()> {
+        self.funding.store_refund(secp, refund)
+    }
+
+    /// Check and store the pre-signed CET of an outcome
+    ///
+    /// # Arguments
+    /// * `outcome` - Outcome the CET pays out
+    /// * `kernel_session` - Kernel session of the CET
+    /// * `offset_shares` - One offset share per participant
+    /// * `pre_signature` - Pre-signature over the CET's kernel message
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidContract` if the outcome is not announced, or the
+    /// pre-signature is locked to another point or does not verify
+    /// Returns the errors of [`SpendBuilder::balanced_offset`]
+    pub fn store_execution(
+        &mut self,
+        outcome: &str,
+        kernel_session: &Session,
+        offset_shares: &[OffsetShare],
+        pre_signature: PreSignature,
+    ) -> Result<()> {
+        let point = self
+            .announcement
+            .attestation_point(kernel_session.secp(), outcome)?;
+        if *pre_signature.adaptor_point() != point {
+            return Err(Error::InvalidContract(format!(
+                "CET of {outcome:?} is locked to another point"
+            )));
+        }
+        let execution = self.find(outcome)?;
+        execution
+            .cet
+            .balanced_offset(kernel_session, offset_shares)?;
+        let agg_pubkey = kernel_session.aggregate_pubkeys()?;
+        if !kernel_session.verify_pre_signature(
+            &execution.cet.kernel_message(),
+            &pre_signature,
+            &agg_pubkey,
+        ) {
+            return Err(Error::InvalidContract(format!(
+                "CET pre-signature of {outcome:?} does not verify"
+            )));
+        }
+
+        let signed = SignedExecution {
+            participants: kernel_session.participants().to_vec(),
+            offset_shares: offset_shares.to_vec(),
+            pre_signature,
+        };
+        if let Some(execution) = self.executions.iter_mut().find(|e| e.outcome == outcome) {
+            execution.signed = Some(signed);
+        }
+        Ok(())
+    }
+
+    /// Check and store the co-signed funding
+    ///
+    /// # Errors
+    /// Returns the errors of [`Self::funding_message`]
+    /// Returns `Error::InvalidContract` if the transaction does not create the
+    /// shared output
+    /// Returns `Error::ValidationFailed` if the transaction does not validate
+    pub fn store_funding(&mut self, secp: &Secp256k1, funding: Transaction) -> Result<()> {
+        self.funding_message()?;
+        let shared = self.funding.shared_output().commitment();
+        if !funding.outputs().iter().any(|o| o.commitment() == shared) {
+            return Err(Error::InvalidContract(
+                "funding does not create the shared output".into(),
+            ));
+        }
+        validate(secp, &funding).into_result()?;
+
+        self.funding_tx = Some(funding);
+        Ok(())
+    }
+
+    /// Get the stored funding transaction, to broadcast
+    pub const fn funding_transaction(&self) -> Option<&Transaction> {
+        self.funding_tx.as_ref()
+    }
+
+    /// Complete the CET of the attested outcome
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidContract` if the funding is not stored yet or
+    /// the outcome is not announced
+    /// Returns `Error::InvalidAdaptorSecret` if the attestation is not the
+    /// oracle's for that outcome
+    pub fn settle(&self, attestation: &Attestation) -> Result<Transaction> {
+        if self.funding_tx.is_none() {
+            return Err(Error::InvalidContract("contract is not funded".into()));
+        }
+        let execution = self.find(attestation.outcome())?;
+        let signed = execution
+            .signed
+            .as_ref()
+            .ok_or_else(|| Error::InvalidContract("CET is not pre-signed".into()))?;
+        let session = Session::new(signed.participants.clone());
+        let signature =
+            session.complete_pre_signature(&signed.pre_signature, attestation.secret())?;
+        execution
+            .cet
+            .finalize_with_signature(&session, &signed.offset_shares, signature)
+    }
+
+    /// Get the refund if it can be mined at `height`
+    pub fn refund_at(&self, height: u64) -> Option<&Transaction> {
+        self.funding.refund_at(height)
+    }
+
+    fn find(&self, outcome: &str) -> Result<&Execution> {
+        self.executions
+            .iter()
+            .find(|e| e.outcome == outcome)
+            .ok_or_else(|| Error::InvalidContract(format!("outcome {outcome:?} is not announced")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockGrinChain;
+    use crate::swap::GrinChain;
+    use crate::test_util::{co_sign, keyed_session};
+    use crate::transaction::{Input, OutputFeatures};
+
+    const LOCK_HEIGHT: u64 = 50;
+
+    /// Alice and Bob each put 300 on a coin flip; heads pays Alice everything
+    struct Contract {
+        secp: Secp256k1,
+        grin: MockGrinChain,
+        oracle: LocalOracle,
+        dlc: Dlc,
+        funding: Session,
+        funding_keys: Vec<SecretKey>,
+        input_blinds: [SecretKey; 2],
+        refund_blinds: [SecretKey; 2],
+        /// Blinding factors of each party's outputs, per outcome
+        payout_blinds: Vec<(String, [Vec<SecretKey>; 2])>,
+    }
+
+    impl Contract {
+        fn new() -> Self {
+            let secp = Secp256k1::with_caps(ContextFlag::Commit);
+            let blind = || SecretKey::new(&secp, &mut thread_rng());
+            let (funding, funding_keys) = keyed_session(&secp, 2);
+            let oracle = LocalOracle::new(vec!["heads".into(), "tails".into()]).unwrap();
+
+            let mut grin = MockGrinChain::new();
+            let input_blinds = [blind(), blind()];
+            let inputs = input_blinds.clone().map(|b| secp.commit(301, b).unwrap());
+            for input in inputs {
+                grin.add_output(input);
+            }
+            let refund_blinds = [blind(), blind()];
+            let [alice_refund, bob_refund] = refund_blinds
+                .clone()
+                .map(|b| Output::create(&secp, 299, &b).unwrap());
+            let builder = FundingBuilder::new(
+                &funding,
+                600,
+                KernelFeatures::Plain { fee: 2 },
+                2,
+                LOCK_HEIGHT,
+                alice_refund,
+            )
+            .unwrap()
+            .refund_output(bob_refund)
+            .input(Input::new(OutputFeatures::Plain, inputs[0]))
+            .input(Input::new(OutputFeatures::Plain, inputs[1]));
+
+            let payout_values = [
+                ("heads", [vec![598], vec![]]),
+                ("tails", [vec![100], vec![498]]),
+            ];
+            let mut payouts = Vec::new();
+            let mut payout_blinds = Vec::new();
+            for (outcome, values) in payout_values {
+                let blinds = values
+                    .clone()
+                    .map(|v| v.iter().map(|_| blind()).collect::<Vec<_>>());
+                let outputs = values
+                    .iter()
+                    .flatten()
+                    .zip(blinds.iter().flatten())
+                    .map(|(v, b)| Output::create(&secp, *v, b).unwrap())
+                    .collect();
+                payouts.push((outcome.to_string(), outputs));
+                payout_blinds.push((outcome.to_string(), blinds));
+            }
+            let dlc = Dlc::new(builder, oracle.announcement().clone(), 2, payouts).unwrap();
+
+            Self {
+                secp,
+                grin,
+                oracle,
+                dlc,
+                funding,
+                funding_keys,
+                input_blinds,
+                refund_blinds,
+                payout_blinds,
+            }
+        }
+
+        /// Co-sign the refund and store it
+        fn sign_refund(&mut self) {
+            let output = *self.dlc.shared_output();
+            let (kernel, excess_keys) = keyed_session(&self.secp, 2);
+            let blindings: Vec<_> = self
+                .funding_keys
+                .iter()
+                .zip(&self.refund_blinds)
+                .map(|(k, b)| {
+                    output
+                        .spend_blinding(&self.funding, k, std::slice::from_ref(b))
+                        .unwrap()
+                })
+                .collect();
+            let refund = self.dlc.refund();
+            let (shares, partials, revealed, agg_nonce) = co_sign(
+                &kernel,
+                &excess_keys,
+                &blindings,
+                &refund.kernel_message(),
+                None,
+            );
+            let refund = refund
+                .finalize(&kernel, &shares, &partials, &revealed, &agg_nonce)
+                .unwrap();
+            self.dlc.store_refund(&self.secp, refund).unwrap();
+        }
+
+        /// Pre-sign the CET of `outcome`, locked to `point`
+        fn pre_sign(
+            &self,
+            outcome: &str,
+            point: &PublicKey,
+        ) -> (Session, Vec<OffsetShare>, PreSignature) {
+            let output = self.dlc.shared_output();
+            let (_, blinds) = self
+                .payout_blinds
+                .iter()
+                .find(|(o, _)| o == outcome)
+                .unwrap();
+            let (kernel, excess_keys) = keyed_session(&self.secp, 2);
+            let blindings: Vec<_> = self
+                .funding_keys
+                .iter()
+                .zip(blinds)
+                .map(|(k, b)| output.spend_blinding(&self.funding, k, b).unwrap())
+                .collect();
+            let message = self.dlc.execution(outcome).unwrap().kernel_message();
+            let (shares, partials, revealed, agg_nonce) =
+                co_sign(&kernel, &excess_keys, &blindings, &message, Some(point));
+            let pre_signature = kernel
+                .aggregate_pre_signatures(&message, &partials, &revealed, &agg_nonce, point)
+                .unwrap();
+            (kernel, shares, pre_signature)
+        }
+
+        /// Pre-sign and store every CET
+        fn sign_executions(&mut self) {
+            for outcome in ["heads", "tails"] {
+                let point = self
+                    .oracle
+                    .announcement()
+                    .attestation_point(&self.secp, outcome)
+                    .unwrap();
+                let (kernel, shares, pre_signature) = self.pre_sign(outcome, &point);
+                self.dlc
+                    .store_execution(outcome, &kernel, &shares, pre_signature)
+                    .unwrap();
+            }
+        }
+
+        /// Co-sign the funding, with a stand-in for a joint range proof
+        fn sign_funding(&self) -> Transaction {
+            let (kernel, excess_keys) = keyed_session(&self.secp, 2);
+            let blindings: Vec<_> = self
+                .funding_keys
+                .iter()
+                .zip(&self.input_blinds)
+                .map(|(k, b)| {
+                    FundingBuilder::funding_blinding(&self.funding, k, &[], std::slice::from_ref(b))
+                        .unwrap()
+                })
+                .collect();
+            let message = self.dlc.funding_message().unwrap();
+            let (shares, partials, revealed, agg_nonce) =
+                co_sign(&kernel, &excess_keys, &blindings, &message, None);
+            let shared_blind = self
+                .secp
+                .blind_sum(
+                    self.funding_keys
+                        .iter()
+                        .map(|k| self.funding.weighted_secret_key(k).unwrap())
+                        .collect(),
+                    vec![],
+                )
+                .unwrap();
+            let proof = *Output::create(&self.secp, 600, &shared_blind)
+                .unwrap()
+                .proof();
+            self.dlc
+                .finalize_funding(&kernel, proof, &shares, &partials, &revealed, &agg_nonce)
+                .unwrap()
+        }
+
+        /// Negotiate everything and put the funding on chain
+        fn fund() -> Self {
+            let mut contract = Self::new();
+            contract.sign_refund();
+            contract.sign_executions();
+            let funding = contract.sign_funding();
+            contract.dlc.store_funding(&contract.secp, funding).unwrap();
+            let funding = contract.dlc.funding_transaction().unwrap().clone();
+            contract.grin.post(&funding).unwrap();
+            contract
+        }
+    }
+
+    #[test]
+    fn test_attested_outcome_settles() {
+        let mut contract = Contract::fund();
+        let oracle = LocalOracle::new(Vec::new()).unwrap();
+        let oracle = std::mem::replace(&mut contract.oracle, oracle);
+        let attestation = oracle.attest("tails").unwrap();
+
+        // The tails attestation does not unlock the heads CET
+        let forged = Attestation::new("heads".into(), attestation.secret().clone());
+        assert!(matches!(
+            contract.dlc.settle(&forged),
+            Err(Error::InvalidAdaptorSecret)
+        ));
+
+        let cet = contract.dlc.settle(&attestation).unwrap();
+        contract.grin.post(&cet).unwrap();
+        assert_eq!(cet.outputs().len(), 2);
+        assert!(cet
+            .outputs()
+            .iter()
+            .all(|o| contract.grin.is_unspent(o.commitment())));
+    }
+
+    #[test]
+    fn test_refund_without_attestation() {
+        let mut contract = Contract::fund();
+        assert!(contract.dlc.refund_at(LOCK_HEIGHT - 1).is_none());
+
+        contract.grin.mine(LOCK_HEIGHT);
+        let refund = contract
+            .dlc
+            .refund_at(contract.grin.height())
+            .unwrap()
+            .clone();
+        contract.grin.post(&refund).unwrap();
+        assert_eq!(refund.outputs().len(), 2);
+        assert!(!contract
+            .grin
+            .is_unspent(contract.dlc.shared_output().commitment()));
+    }
+
+    #[test]
+    fn test_funding_requires_refund_and_every_cet() {
+        let mut contract = Contract::new();
+        assert_eq!(contract.dlc.funding_message(), Err(Error::MissingRefund));
+
+        // The funding cannot be signed while a CET is missing
+        contract.sign_refund();
+        assert!(matches!(
+            contract.dlc.funding_message(),
+            Err(Error::InvalidContract(_))
+        ));
+
+        // A CET pre-signed on the wrong outcome's point is refused
+        let tails = contract
+            .oracle
+            .announcement()
+            .attestation_point(&contract.secp, "tails")
+            .unwrap();
+        let (kernel, shares, pre_signature) = contract.pre_sign("heads", &tails);
+        assert!(matches!(
+            contract
+                .dlc
+                .store_execution("heads", &kernel, &shares, pre_signature),
+            Err(Error::InvalidContract(_))
+        ));
+
+        assert!(matches!(
+            contract.dlc.funding_message(),
+            Err(Error::InvalidContract(_))
+        ));
+        contract.sign_executions();
+        assert!(contract.dlc.funding_message().is_ok());
+
+        let announcement = contract.oracle.announcement().clone();
+        assert!(matches!(
+            Dlc::new(contract.dlc.funding.clone(), announcement, 2, Vec::new()),
+            Err(Error::InvalidContract(_))
+        ));
+    }
+}
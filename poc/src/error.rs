@@ -2,6 +2,8 @@
 
 use std::fmt;
 
+use crate::types::ParticipantId;
+
 /// Result type alias for `MuSig2` operations
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -43,14 +45,349 @@ pub enum Error {
     /// were provided.
     NoNonces,
 
+    /// Wrong number of partial signatures for aggregation
+    ///
+    /// This error occurs when the number of partial signatures or revealed
+    /// nonces does not match the number of participants.
+    PartialSignatureCount {
+        /// Number of participants in the session
+        expected: usize,
+        /// Number of contributions provided
+        actual: usize,
+    },
+
+    /// Partial signature verification failed
+    ///
+    /// This error occurs when a participant's partial signature does not
+    /// verify against its public key and revealed nonces.
+    InvalidPartialSignature {
+        /// Index of the participant with the invalid partial signature
+        participant_index: usize,
+    },
+
     /// Cryptographic operation failed
     ///
     /// This error wraps underlying cryptographic errors from the secp256k1-zkp
     /// library.
     Crypto(String),
+
+    /// Participant is not part of the session
+    ///
+    /// This error occurs when a contribution names a participant ID that is
+    /// not in the session's participant list.
+    UnknownParticipant {
+        /// ID of the unknown participant
+        participant: ParticipantId,
+    },
+
+    /// Secret key does not match the participant's registered public key
+    KeyMismatch {
+        /// ID of the participant whose key did not match
+        participant: ParticipantId,
+    },
+
+    /// A participant's kernel offset share is missing
+    ///
+    /// This error occurs when combining offsets before every participant has
+    /// contributed exactly one share.
+    MissingOffsetShare {
+        /// ID of the participant whose share is missing
+        participant: ParticipantId,
+    },
+
+    /// Transaction does not balance
+    ///
+    /// This error occurs when `sum(outputs) + fee*H - sum(inputs)` does not
+    /// equal `excess + offset*G`.
+    UnbalancedTransaction,
+
+    /// Slatepack armor is malformed
+    ///
+    /// This error occurs when armored text is missing its
+    /// `BEGINSLATEPACK.`/`. ENDSLATEPACK.` framing or the payload is not
+    /// valid Base58.
+    InvalidArmor(String),
+
+    /// Slatepack checksum verification failed
+    ///
+    /// This error occurs when the 4-byte double-SHA256 checksum embedded in
+    /// an armored payload does not match the decoded data, usually because
+    /// the text was truncated or altered in transit.
+    ChecksumMismatch,
+
+    /// Message payload could not be serialized or deserialized
+    ///
+    /// This error occurs when a decoded Slatepack payload is not a valid
+    /// multisig round message.
+    Serialization(String),
+
+    /// Transaction failed local validation
+    ///
+    /// This error occurs when a finished transaction fails one of the checks
+    /// in its validation report; the message names the first failing check.
+    ValidationFailed(String),
+
+    /// Participant ID cannot be used as a share index
+    ///
+    /// This error occurs when a threshold protocol is given participant ID 0,
+    /// the point at which the shared secret itself is evaluated.
+    InvalidShareIndex {
+        /// The invalid participant ID
+        participant: ParticipantId,
+    },
+
+    /// Participant appears more than once
+    ///
+    /// This error occurs when a signer set, signing package or share list
+    /// contains the same participant ID twice.
+    DuplicateParticipant {
+        /// ID of the repeated participant
+        participant: ParticipantId,
+    },
+
+    /// Fewer signers than the group threshold
+    NotEnoughSigners {
+        /// Minimum number of signers
+        threshold: usize,
+        /// Number of signers provided
+        actual: usize,
+    },
+
+    /// Threshold signature share verification failed
+    ///
+    /// This error occurs when a signer's share does not verify against its
+    /// verification share and signing commitments.
+    InvalidSignatureShare {
+        /// ID of the signer with the invalid share
+        participant: ParticipantId,
+    },
+
+    /// A signer in the signing package has not provided a signature share
+    MissingSignatureShare {
+        /// ID of the signer whose share is missing
+        participant: ParticipantId,
+    },
+
+    /// Threshold is out of range for the number of participants
+    ///
+    /// This error occurs when a threshold `t` is 0 or larger than the
+    /// number of participants `n`.
+    InvalidThreshold {
+        /// Requested threshold
+        threshold: usize,
+        /// Number of participants
+        participants: usize,
+    },
+
+    /// Secret share does not match the dealer's commitments
+    ///
+    /// This error occurs when `f(i) * G` differs from the value the dealer's
+    /// Feldman commitments give for the recipient: the dealer sent a wrong
+    /// share, a share meant for someone else, or inconsistent commitments.
+    InvalidSecretShare {
+        /// Recipient of the inconsistent share
+        participant: ParticipantId,
+    },
+
+    /// Proof of knowledge of a DKG dealer's constant term does not verify
+    ///
+    /// This error occurs when a round 1 DKG package carries a proof that does
+    /// not match its first coefficient commitment, so the dealer may be
+    /// attempting a rogue key attack.
+    InvalidProofOfKnowledge {
+        /// ID of the dealer whose proof failed
+        participant: ParticipantId,
+    },
+
+    /// DKG commitment has the wrong number of coefficients
+    ///
+    /// This error occurs when a dealer commits to a polynomial whose degree
+    /// does not match the agreed threshold.
+    InvalidCommitment {
+        /// ID of the dealer with the malformed commitment
+        participant: ParticipantId,
+    },
+
+    /// Expected protocol message was never received
+    ///
+    /// This error occurs when a group member's contribution to a round is
+    /// missing.
+    MissingMessage {
+        /// ID of the member whose message is missing
+        participant: ParticipantId,
+    },
+
+    /// A qualified DKG dealer's share is missing or wrong
+    ///
+    /// This error occurs when a share still fails verification after the
+    /// complaint phase, which means the recipient did not complain in time.
+    InvalidDealerShare {
+        /// ID of the dealer who sent the share
+        dealer: ParticipantId,
+        /// ID of the recipient
+        participant: ParticipantId,
+    },
+
+    /// Protocol message is structurally invalid
+    ///
+    /// This error occurs when a message has the wrong number of entries or
+    /// internally inconsistent fields.
+    MalformedMessage(String),
+
+    /// Certificate signature does not verify
+    ///
+    /// This error occurs when a participant's signature over the DKG
+    /// transcript is missing or invalid, so agreement cannot be confirmed.
+    InvalidCertificate {
+        /// ID of the participant whose signature failed
+        participant: ParticipantId,
+    },
+
+    /// Host key is not part of the session
+    ///
+    /// This error occurs when a host secret key does not match any of the
+    /// session's host public keys.
+    UnknownHostKey,
+
+    /// Blame proof does not show the accused participant at fault
+    ///
+    /// This error occurs when a third party checks a blame proof and the
+    /// evidence against a participant is not signed with its identity key or
+    /// turns out to be valid behaviour.
+    InvalidBlameProof {
+        /// ID of the wrongly accused participant
+        participant: ParticipantId,
+    },
+
+    /// Approval is not valid for the escrow
+    ///
+    /// This error occurs when an approval's signature does not verify, the
+    /// approval is for another decision, or the party may not make it in the
+    /// escrow's current state.
+    InvalidApproval {
+        /// ID of the approving party
+        participant: ParticipantId,
+    },
+
+    /// Escrow or swap cannot move to the requested state
+    ///
+    /// This error occurs when a decision is applied to an escrow that has
+    /// already been resolved or is not in a state that allows it, or when a
+    /// swap is driven before it is fully negotiated.
+    InvalidTransition(String),
+
+    /// Funding transaction signed before its refund
+    ///
+    /// This error occurs when the funding kernel is requested before a
+    /// co-signed refund of the shared output has been stored.
+    MissingRefund,
+
+    /// Refund transaction does not match the agreed refund
+    ///
+    /// This error occurs when a stored refund spends something other than the
+    /// shared output, pays other outputs than agreed or lacks the agreed
+    /// height-locked kernel.
+    InvalidRefund(String),
+
+    /// Adaptor secret does not match the pre-signature
+    ///
+    /// This error occurs when a secret is not the discrete logarithm of the
+    /// adaptor point, or a signature is not a completion of the pre-signature.
+    InvalidAdaptorSecret,
+
+    /// Swap data does not match the agreed terms
+    ///
+    /// This error occurs when a co-signed transaction or pre-signature does
+    /// not fit the swap, or the buyer lacks its adaptor secret.
+    InvalidSwap(String),
+
+    /// Chain refused a transaction
+    ///
+    /// This error occurs when a transaction spends missing coins, is not yet
+    /// past its lock height, or does not satisfy the lock it spends.
+    ChainRejected(String),
+
+    /// Channel update or close does not fit the channel
+    ///
+    /// This error occurs when a state does not follow the latest one, a
+    /// commitment is not the expected close and settle, or a revocation does
+    /// not match the state awaiting it.
+    InvalidChannel(String),
+
+    /// Contract data does not match the agreed terms
+    ///
+    /// This error occurs when a DLC's payouts do not cover the announced
+    /// outcomes, a CET pre-signature does not fit its outcome, or the contract
+    /// is settled before it is funded.
+    InvalidContract(String),
+
+    /// Signer's policy refused the spend
+    ///
+    /// This error occurs when a spend breaks one of the signer's rules, before
+    /// any partial signature is produced.
+    PolicyDenied(Denial),
+
+    /// Spent shared output does not belong to the signer's funding session
+    ///
+    /// This error occurs when a spend request's input does not commit to its
+    /// stated value under the funding session's aggregate key.
+    UnknownSharedOutput,
+
+    /// Claimed change output is not part of the spend
+    ///
+    /// This error occurs when a signer opens a change output that the spend
+    /// does not create, or one it has already opened.
+    UnknownChangeOutput,
+
+    /// Signer's key is not part of the session
+    ///
+    /// This error occurs when a service holding one participant key is asked
+    /// to take part in a session whose participants do not include that key.
+    NotASigner,
+
+    /// No unspent nonce for the session
+    ///
+    /// This error occurs when a signer is asked for a partial signature in a
+    /// session it has not run Round 1 for, or whose nonce it already spent.
+    MissingNonce,
+
+    /// Spend needs a human's second factor
+    ///
+    /// This error occurs when a co-signer is asked to sign above its approval
+    /// threshold without a TOTP or challenge code.
+    SecondFactorRequired,
+
+    /// Second factor did not check out
+    ///
+    /// This error occurs when a TOTP code is wrong or reused, or a challenge
+    /// code is wrong, expired or issued for another spend.
+    SecondFactorRejected,
+
+    /// Rate limit admits no requests
+    ///
+    /// This error occurs when a co-signer's rate limit allows zero requests
+    /// or has an empty window.
+    InvalidRateLimit,
+
+    /// Too many requests
+    ///
+    /// This error occurs when a co-signer has already handled its maximum
+    /// number of requests within the rate limit window.
+    RateLimited {
+        /// Seconds until the next request is accepted
+        retry_after: u64,
+    },
+
+    /// Rotation does not fit the group
+    ///
+    /// This error occurs when an output is not held by the group, the group
+    /// holds too little to sweep, or a sweep does not move every output to
+    /// the new participant set.
+    InvalidRotation(String),
 }
 
 impl fmt::Display for Error {
+    #[allow(clippy::too_many_lines)] // One arm per variant
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::NoParticipants => write!(f, "No participants in session"),
@@ -58,15 +395,204 @@ impl fmt::Display for Error {
                 write!(f, "Commitment mismatch for participant {participant_index}")
             }
             Self::NoNonces => write!(f, "No nonces provided for aggregation"),
+            Self::PartialSignatureCount { expected, actual } => {
+                write!(f, "Expected {expected} partial signatures, got {actual}")
+            }
+            Self::InvalidPartialSignature { participant_index } => {
+                write!(
+                    f,
+                    "Invalid partial signature from participant {participant_index}"
+                )
+            }
             Self::Crypto(msg) => write!(f, "Cryptographic error: {msg}"),
+            Self::UnknownParticipant { participant } => {
+                write!(f, "Unknown participant {participant}")
+            }
+            Self::KeyMismatch { participant } => {
+                write!(f, "Secret key does not match participant {participant}")
+            }
+            Self::MissingOffsetShare { participant } => {
+                write!(f, "Missing offset share from participant {participant}")
+            }
+            Self::UnbalancedTransaction => {
+                write!(f, "Transaction excess and offset do not balance")
+            }
+            Self::InvalidArmor(msg) => write!(f, "Invalid Slatepack armor: {msg}"),
+            Self::ChecksumMismatch => write!(f, "Slatepack checksum mismatch"),
+            Self::Serialization(msg) => write!(f, "Serialization error: {msg}"),
+            Self::ValidationFailed(msg) => write!(f, "Transaction validation failed: {msg}"),
+            Self::InvalidShareIndex { participant } => {
+                write!(
+                    f,
+                    "Participant {participant} cannot be used as a share index"
+                )
+            }
+            Self::DuplicateParticipant { participant } => {
+                write!(f, "Participant {participant} appears more than once")
+            }
+            Self::NotEnoughSigners { threshold, actual } => {
+                write!(f, "Expected at least {threshold} signers, got {actual}")
+            }
+            Self::InvalidSignatureShare { participant } => {
+                write!(f, "Invalid signature share from participant {participant}")
+            }
+            Self::MissingSignatureShare { participant } => {
+                write!(f, "Missing signature share from participant {participant}")
+            }
+            Self::InvalidThreshold {
+                threshold,
+                participants,
+            } => {
+                write!(
+                    f,
+                    "Invalid threshold {threshold} for {participants} participants"
+                )
+            }
+            Self::InvalidSecretShare { participant } => {
+                write!(
+                    f,
+                    "Secret share for participant {participant} does not match the dealer's commitments"
+                )
+            }
+            Self::InvalidProofOfKnowledge { participant } => {
+                write!(
+                    f,
+                    "Invalid proof of knowledge from participant {participant}"
+                )
+            }
+            Self::InvalidCommitment { participant } => {
+                write!(
+                    f,
+                    "Commitment from participant {participant} has the wrong degree"
+                )
+            }
+            Self::MissingMessage { participant } => {
+                write!(f, "Missing message from participant {participant}")
+            }
+            Self::InvalidDealerShare {
+                dealer,
+                participant,
+            } => {
+                write!(
+                    f,
+                    "Share from dealer {dealer} to participant {participant} is missing or invalid"
+                )
+            }
+            Self::MalformedMessage(msg) => write!(f, "Malformed message: {msg}"),
+            Self::InvalidCertificate { participant } => {
+                write!(
+                    f,
+                    "Invalid certificate signature from participant {participant}"
+                )
+            }
+            Self::UnknownHostKey => write!(f, "Host key is not part of the session"),
+            Self::InvalidBlameProof { participant } => {
+                write!(
+                    f,
+                    "Blame proof does not show participant {participant} at fault"
+                )
+            }
+            Self::InvalidApproval { participant } => {
+                write!(f, "Invalid approval from participant {participant}")
+            }
+            Self::InvalidTransition(msg) => write!(f, "Invalid state transition: {msg}"),
+            Self::MissingRefund => {
+                write!(f, "Funding cannot be signed before the refund is stored")
+            }
+            Self::InvalidRefund(msg) => write!(f, "Invalid refund transaction: {msg}"),
+            Self::InvalidAdaptorSecret => {
+                write!(f, "Adaptor secret does not match the pre-signature")
+            }
+            Self::InvalidSwap(msg) => write!(f, "Invalid swap: {msg}"),
+            Self::ChainRejected(msg) => write!(f, "Chain rejected transaction: {msg}"),
+            Self::InvalidChannel(msg) => write!(f, "Invalid channel: {msg}"),
+            Self::InvalidContract(msg) => write!(f, "Invalid contract: {msg}"),
+            Self::PolicyDenied(denial) => write!(f, "Policy denied the spend: {denial}"),
+            Self::UnknownSharedOutput => {
+                write!(f, "Spent output is not a shared output of the session")
+            }
+            Self::UnknownChangeOutput => write!(f, "Change output is not part of the spend"),
+            Self::NotASigner => write!(f, "Signer's key is not part of the session"),
+            Self::MissingNonce => write!(f, "No unspent nonce for the session"),
+            Self::SecondFactorRequired => write!(f, "Spend requires a second factor"),
+            Self::SecondFactorRejected => write!(f, "Second factor rejected"),
+            Self::InvalidRateLimit => write!(f, "Rate limit must allow at least one request"),
+            Self::RateLimited { retry_after } => {
+                write!(f, "Rate limit exceeded, retry after {retry_after} seconds")
+            }
+            Self::InvalidRotation(msg) => write!(f, "Invalid rotation: {msg}"),
         }
     }
 }
 
 impl std::error::Error for Error {}
 
+/// Why a policy refused to sign
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Denial {
+    /// The spend is above the per-spend cap
+    AmountCapExceeded {
+        /// Amount leaving the shared outputs
+        amount: u64,
+        /// Configured cap
+        cap: u64,
+    },
+    /// The spend would take the last 24 hours over the limit
+    DailyLimitExceeded {
+        /// Amount already signed in the window
+        spent: u64,
+        /// Amount leaving the shared outputs
+        amount: u64,
+        /// Configured limit
+        limit: u64,
+    },
+    /// The counterparty is not on the allow list
+    CounterpartyNotAllowed {
+        /// Declared counterparty
+        counterparty: String,
+    },
+    /// The kernel unlocks too early
+    LockHeightTooLow {
+        /// Kernel lock height, 0 if not height locked
+        lock_height: u64,
+        /// Configured minimum
+        minimum: u64,
+    },
+}
+
+impl fmt::Display for Denial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AmountCapExceeded { amount, cap } => {
+                write!(f, "amount {amount} exceeds the cap of {cap}")
+            }
+            Self::DailyLimitExceeded {
+                spent,
+                amount,
+                limit,
+            } => write!(
+                f,
+                "amount {amount} on top of {spent} spent today exceeds the limit of {limit}"
+            ),
+            Self::CounterpartyNotAllowed { counterparty } => {
+                write!(f, "counterparty {counterparty:?} is not allowed")
+            }
+            Self::LockHeightTooLow {
+                lock_height,
+                minimum,
+            } => write!(f, "lock height {lock_height} is below {minimum}"),
+        }
+    }
+}
+
 impl From<secp256k1zkp::Error> for Error {
     fn from(e: secp256k1zkp::Error) -> Self {
         Self::Crypto(e.to_string())
     }
 }
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Serialization(e.to_string())
+    }
+}
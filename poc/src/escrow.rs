@@ -0,0 +1,652 @@
+//! Buyer/seller/arbiter escrow
+//!
+//! The escrow deposit is locked to a 2-of-3 threshold key held by the buyer,
+//! the seller and an arbiter, so any two of them can spend it. Buyer and
+//! seller settle on their own; the arbiter only counts once either of them
+//! has raised a dispute.
+//!
+//! Every decision is a signed [`Approval`] under the party's verification
+//! share, bound to the deposit commitment, and the escrow keeps every approval
+//! it accepted as a record of who decided what. Resolving the escrow returns
+//! the [`EscrowSpend`] for the approving parties: the kernel [`Session`] among
+//! them and their shares of the spend's blinding factor.
+//!
+//! # Algorithm
+//! ```text
+//! Funded   --dispute (buyer | seller)--------------------------> Disputed
+//! Funded   --release | refund (buyer + seller)-----------------> Released | Refunded
+//! Disputed --release | refund (any two of buyer, seller, arbiter)> Released | Refunded
+//!
+//! Deposit:   C = v*H + Y                          (Y = 2-of-3 group key)
+//! Spend by signers S:  b_i = sum(own output blinds) - lambda_i * s_i
+//! ```
+
+use blake2::{Blake2b512, Digest};
+use secp256k1zkp::{aggsig, Message, Secp256k1, SecretKey, Signature};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::frost::{KeyPackage, PublicKeyPackage};
+use crate::participant::Participant;
+use crate::scalar::{self, lagrange_coefficient};
+use crate::ser::hex_signature;
+use crate::session::Session;
+use crate::spend::SharedOutput;
+use crate::types::ParticipantId;
+
+/// Domain separator for approval messages
+const APPROVAL_DOMAIN: &[u8] = b"grin-multisig/escrow/approval";
+
+/// A party's role in the escrow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// Pays into the escrow
+    Buyer,
+    /// Gets paid on release
+    Seller,
+    /// Decides disputes
+    Arbiter,
+}
+
+/// Where the escrow stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EscrowState {
+    /// Deposit confirmed, no decision yet
+    Funded,
+    /// Buyer or seller asked the arbiter to decide
+    Disputed,
+    /// Deposit paid out to the seller
+    Released,
+    /// Deposit paid back to the buyer
+    Refunded,
+}
+
+/// What a party approves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    /// Ask the arbiter to decide
+    Dispute,
+    /// Pay the deposit to the seller
+    Release,
+    /// Pay the deposit back to the buyer
+    Refund,
+}
+
+impl Decision {
+    const fn tag(self) -> u8 {
+        match self {
+            Self::Dispute => 0,
+            Self::Release => 1,
+            Self::Refund => 2,
+        }
+    }
+}
+
+/// A party's signed decision
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Approval {
+    /// Approving party
+    participant: ParticipantId,
+
+    /// What the party approves
+    decision: Decision,
+
+    /// Signature under the party's verification share
+    #[serde(with = "hex_signature")]
+    signature: Signature,
+}
+
+impl Approval {
+    /// Get the approving party
+    pub const fn participant(&self) -> ParticipantId {
+        self.participant
+    }
+
+    /// Get what the party approves
+    pub const fn decision(&self) -> Decision {
+        self.decision
+    }
+}
+
+/// The spend a resolution calls for
+pub struct EscrowSpend {
+    /// Resolution the spend carries out
+    decision: Decision,
+
+    /// Parties who approved and now sign, ordered by ID
+    signers: Vec<ParticipantId>,
+
+    /// Kernel session among the signers' excess keys
+    session: Session,
+}
+
+impl EscrowSpend {
+    /// Get the resolution the spend carries out
+    pub const fn decision(&self) -> Decision {
+        self.decision
+    }
+
+    /// Get the parties who sign the spend
+    pub fn signers(&self) -> &[ParticipantId] {
+        &self.signers
+    }
+
+    /// Get the kernel session among the signers
+    pub const fn session(&self) -> &Session {
+        &self.session
+    }
+
+    /// Compute a signer's spend blinding total `b_i`
+    ///
+    /// # Arguments
+    /// * `key_package` - Signer's share of the escrow key
+    /// * `output_blinds` - Blinding factors of the outputs this signer creates
+    ///   in the spend, possibly none
+    ///
+    /// # Errors
+    /// Returns `Error::UnknownParticipant` if the signer did not approve the resolution
+    /// Returns `Error::Crypto` if the blinding factors sum to zero
+    pub fn spend_blinding(
+        &self,
+        key_package: &KeyPackage,
+        output_blinds: &[SecretKey],
+    ) -> Result<SecretKey> {
+        let secp = self.session.secp();
+        let lambda = lagrange_coefficient(secp, key_package.participant(), &self.signers)?;
+        let share = scalar::mul(secp, key_package.signing_share(), &lambda)?;
+        Ok(secp.blind_sum(output_blinds.to_vec(), vec![share])?)
+    }
+}
+
+/// An escrow between a buyer and a seller, with an arbiter for disputes
+#[derive(Debug, Clone)]
+pub struct Escrow {
+    /// Buyer's ID in the threshold key
+    buyer: ParticipantId,
+
+    /// Seller's ID in the threshold key
+    seller: ParticipantId,
+
+    /// Arbiter's ID in the threshold key
+    arbiter: ParticipantId,
+
+    /// Public keys of the 2-of-3 escrow key
+    public_keys: PublicKeyPackage,
+
+    /// Output holding the deposit
+    deposit: SharedOutput,
+
+    /// Current state
+    state: EscrowState,
+
+    /// Every approval accepted so far, in order
+    approvals: Vec<Approval>,
+}
+
+impl Escrow {
+    /// Open an escrow over a funded deposit
+    ///
+    /// # Arguments
+    /// * `secp` - Secp256k1 context capable of Pedersen commitments
+    /// * `public_keys` - Public keys of the 2-of-3 key from DKG among the parties
+    /// * `roles` - IDs of the buyer, seller and arbiter, in that order
+    /// * `value` - Deposit value
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidThreshold` if the key is not 2-of-3
+    /// Returns `Error::UnknownParticipant` if a role holds no share of the key
+    /// Returns `Error::DuplicateParticipant` if one party holds two roles
+    pub fn new(
+        secp: &Secp256k1,
+        public_keys: PublicKeyPackage,
+        roles: [ParticipantId; 3],
+        value: u64,
+    ) -> Result<Self> {
+        let holders = public_keys.verifying_shares().len();
+        if public_keys.min_signers() != 2 || holders != 3 {
+            return Err(Error::InvalidThreshold {
+                threshold: public_keys.min_signers(),
+                participants: holders,
+            });
+        }
+        for (i, &participant) in roles.iter().enumerate() {
+            public_keys.verifying_share(participant)?;
+            if roles[..i].contains(&participant) {
+                return Err(Error::DuplicateParticipant { participant });
+            }
+        }
+
+        let [buyer, seller, arbiter] = roles;
+        let deposit = SharedOutput::from_public_key(secp, public_keys.group_public_key(), value)?;
+        Ok(Self {
+            buyer,
+            seller,
+            arbiter,
+            public_keys,
+            deposit,
+            state: EscrowState::Funded,
+            approvals: Vec::new(),
+        })
+    }
+
+    /// Get the output holding the deposit
+    pub const fn deposit(&self) -> &SharedOutput {
+        &self.deposit
+    }
+
+    /// Get the current state
+    pub const fn state(&self) -> EscrowState {
+        self.state
+    }
+
+    /// Get every approval accepted so far, in order
+    pub fn approvals(&self) -> &[Approval] {
+        &self.approvals
+    }
+
+    /// Get a party's role
+    pub fn role(&self, participant: ParticipantId) -> Option<Role> {
+        [
+            (self.buyer, Role::Buyer),
+            (self.seller, Role::Seller),
+            (self.arbiter, Role::Arbiter),
+        ]
+        .into_iter()
+        .find_map(|(id, role)| (id == participant).then_some(role))
+    }
+
+    /// Sign a decision about this escrow
+    ///
+    /// # Errors
+    /// Returns `Error::UnknownParticipant` if the signer is not a party
+    /// Returns `Error::Crypto` if signing fails
+    pub fn approve(
+        &self,
+        secp: &Secp256k1,
+        key_package: &KeyPackage,
+        decision: Decision,
+    ) -> Result<Approval> {
+        let participant = key_package.participant();
+        self.public_keys.verifying_share(participant)?;
+        let signature = aggsig::sign_single(
+            secp,
+            &self.approval_message(decision)?,
+            key_package.signing_share(),
+            None,
+            None,
+            None,
+            Some(key_package.verifying_share()),
+            None,
+        )?;
+        Ok(Approval {
+            participant,
+            decision,
+            signature,
+        })
+    }
+
+    /// Raise a dispute, handing the decision to the arbiter
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidTransition` if the escrow is not funded
+    /// Returns `Error::InvalidApproval` if the approval is not a valid dispute
+    /// from the buyer or the seller
+    pub fn dispute(&mut self, secp: &Secp256k1, approval: Approval) -> Result<()> {
+        if self.state != EscrowState::Funded {
+            return Err(self.transition_error(Decision::Dispute));
+        }
+        let participant = approval.participant;
+        if approval.decision != Decision::Dispute
+            || self.role(participant) == Some(Role::Arbiter)
+            || !self.verify(secp, &approval)
+        {
+            return Err(Error::InvalidApproval { participant });
+        }
+
+        self.approvals.push(approval);
+        self.state = EscrowState::Disputed;
+        Ok(())
+    }
+
+    /// Release or refund the deposit
+    ///
+    /// # Arguments
+    /// * `secp` - Secp256k1 context
+    /// * `approvals` - Signed approvals of the same decision from at least two parties
+    /// * `kernel_keys` - Each approving party's excess key for the spend kernel
+    ///
+    /// # Returns
+    /// The spend the approving parties now sign
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidTransition` if the escrow is already resolved or
+    /// the decision is not a release or refund
+    /// Returns `Error::NotEnoughSigners` if fewer than two parties approve
+    /// Returns `Error::DuplicateParticipant` if a party approves twice
+    /// Returns `Error::InvalidApproval` if an approval does not verify, is for
+    /// another decision, or comes from the arbiter before any dispute
+    /// Returns `Error::MissingMessage` if an approving party has no kernel key
+    pub fn resolve(
+        &mut self,
+        secp: &Secp256k1,
+        approvals: &[Approval],
+        kernel_keys: &[Participant],
+    ) -> Result<EscrowSpend> {
+        let decision = approvals
+            .first()
+            .map_or(Decision::Release, |approval| approval.decision);
+        let resolved = match decision {
+            Decision::Release => EscrowState::Released,
+            Decision::Refund => EscrowState::Refunded,
+            Decision::Dispute => return Err(self.transition_error(decision)),
+        };
+        if matches!(self.state, EscrowState::Released | EscrowState::Refunded) {
+            return Err(self.transition_error(decision));
+        }
+        if approvals.len() < self.public_keys.min_signers() {
+            return Err(Error::NotEnoughSigners {
+                threshold: self.public_keys.min_signers(),
+                actual: approvals.len(),
+            });
+        }
+
+        let mut signers = Vec::with_capacity(approvals.len());
+        for approval in approvals {
+            let participant = approval.participant;
+            if signers.contains(&participant) {
+                return Err(Error::DuplicateParticipant { participant });
+            }
+            let arbiter_too_early = self.role(participant) == Some(Role::Arbiter)
+                && self.state != EscrowState::Disputed;
+            if approval.decision != decision || arbiter_too_early || !self.verify(secp, approval) {
+                return Err(Error::InvalidApproval { participant });
+            }
+            signers.push(participant);
+        }
+        signers.sort_unstable();
+
+        let kernel_participants = signers
+            .iter()
+            .map(|&participant| {
+                kernel_keys
+                    .iter()
+                    .find(|key| key.id() == participant)
+                    .cloned()
+                    .ok_or(Error::MissingMessage { participant })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.approvals.extend_from_slice(approvals);
+        self.state = resolved;
+        Ok(EscrowSpend {
+            decision,
+            signers,
+            session: Session::new(kernel_participants),
+        })
+    }
+
+    /// Check an approval against the party's verification share
+    fn verify(&self, secp: &Secp256k1, approval: &Approval) -> bool {
+        let (Ok(verifying_share), Ok(msg)) = (
+            self.public_keys.verifying_share(approval.participant),
+            self.approval_message(approval.decision),
+        ) else {
+            return false;
+        };
+        aggsig::verify_single(
+            secp,
+            &approval.signature,
+            &msg,
+            None,
+            verifying_share,
+            Some(verifying_share),
+            None,
+            false,
+        )
+    }
+
+    /// Message binding a decision to this escrow's deposit
+    fn approval_message(&self, decision: Decision) -> Result<Message> {
+        let mut hasher = Blake2b512::new();
+        hasher.update(APPROVAL_DOMAIN);
+        hasher.update(self.deposit.commitment().0);
+        hasher.update([decision.tag()]);
+        Ok(Message::from_slice(&hasher.finalize()[..32])?)
+    }
+
+    fn transition_error(&self, decision: Decision) -> Error {
+        Error::InvalidTransition(format!("cannot {decision:?} from {:?}", self.state))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::offset::OffsetShare;
+    use crate::spend::SpendBuilder;
+    use crate::transaction::{KernelFeatures, Output, Transaction};
+    use crate::vss::trusted_dealer_keygen;
+    use rand::thread_rng;
+    use secp256k1zkp::{ContextFlag, PublicKey};
+
+    const BUYER: ParticipantId = ParticipantId(1);
+    const SELLER: ParticipantId = ParticipantId(2);
+    const ARBITER: ParticipantId = ParticipantId(3);
+
+    fn open(secp: &Secp256k1) -> (Escrow, Vec<KeyPackage>) {
+        let secret = SecretKey::new(secp, &mut thread_rng());
+        let (shares, public_keys) =
+            trusted_dealer_keygen(secp, &secret, 2, &[BUYER, SELLER, ARBITER]).unwrap();
+        let keys = shares
+            .into_iter()
+            .map(|s| s.into_key_package(secp).unwrap())
+            .collect();
+        let escrow = Escrow::new(secp, public_keys, [BUYER, SELLER, ARBITER], 1_000).unwrap();
+        (escrow, keys)
+    }
+
+    /// Sign the spend and pay everything but the fee to `recipient`, a signer
+    ///
+    /// Excess keys are ordered like the spend's signers.
+    fn pay_out(
+        escrow: &Escrow,
+        spend: &EscrowSpend,
+        keys: &[KeyPackage],
+        excess_keys: &[SecretKey],
+        recipient: ParticipantId,
+    ) -> Transaction {
+        let session = spend.session();
+        let secp = session.secp();
+        let recipient_blind = SecretKey::new(secp, &mut thread_rng());
+        let builder = SpendBuilder::new(*escrow.deposit(), KernelFeatures::Plain { fee: 10 })
+            .output(Output::create(secp, 990, &recipient_blind).unwrap());
+
+        let signers: Vec<_> = spend
+            .signers()
+            .iter()
+            .map(|&id| keys.iter().find(|k| k.participant() == id).unwrap())
+            .collect();
+        let shares: Vec<_> = signers
+            .iter()
+            .zip(excess_keys)
+            .map(|(key, x)| {
+                let own = if key.participant() == recipient {
+                    vec![recipient_blind.clone()]
+                } else {
+                    Vec::new()
+                };
+                let blinding = spend.spend_blinding(key, &own).unwrap();
+                OffsetShare::generate(session, key.participant(), x, &blinding).unwrap()
+            })
+            .collect();
+
+        let rounds: Vec<_> = excess_keys
+            .iter()
+            .map(|_| session.round1_generate_nonces().unwrap())
+            .collect();
+        let revealed: Vec<_> = rounds.iter().map(|r| *r.public_nonces()).collect();
+        let agg_nonce = session
+            .round2_aggregate_nonces(
+                &rounds.iter().map(|r| *r.commitment()).collect::<Vec<_>>(),
+                &revealed,
+            )
+            .unwrap();
+        let agg_pubkey = session.aggregate_pubkeys().unwrap();
+        let message = builder.kernel_message();
+        let partials: Vec<_> = excess_keys
+            .iter()
+            .zip(&rounds)
+            .map(|(x, r)| {
+                session
+                    .round3_partial_sign(&message, x, r.secret_nonce(), &agg_nonce, &agg_pubkey)
+                    .unwrap()
+            })
+            .collect();
+
+        builder
+            .finalize(session, &shares, &partials, &revealed, &agg_nonce)
+            .unwrap()
+    }
+
+    fn kernel_keys(secp: &Secp256k1, ids: &[ParticipantId]) -> (Vec<Participant>, Vec<SecretKey>) {
+        ids.iter()
+            .map(|&id| {
+                let x = SecretKey::new(secp, &mut thread_rng());
+                let pk = PublicKey::from_secret_key(secp, &x).unwrap();
+                (Participant::new(id, pk), x)
+            })
+            .unzip()
+    }
+
+    #[test]
+    fn test_cooperative_release() {
+        let secp = Secp256k1::with_caps(ContextFlag::Commit);
+        let (mut escrow, keys) = open(&secp);
+        let approvals = [
+            escrow.approve(&secp, &keys[1], Decision::Release).unwrap(),
+            escrow.approve(&secp, &keys[0], Decision::Release).unwrap(),
+        ];
+        let (participants, excess_keys) = kernel_keys(&secp, &[SELLER, BUYER]);
+
+        let spend = escrow.resolve(&secp, &approvals, &participants).unwrap();
+        assert_eq!(escrow.state(), EscrowState::Released);
+        assert_eq!(spend.signers(), &[BUYER, SELLER]);
+        assert_eq!(escrow.approvals(), &approvals);
+
+        // The session orders signers by ID
+        let excess_keys = [excess_keys[1].clone(), excess_keys[0].clone()];
+        let tx = pay_out(&escrow, &spend, &keys, &excess_keys, SELLER);
+        assert_eq!(tx.inputs()[0].commitment(), escrow.deposit().commitment());
+
+        // A resolved escrow cannot be resolved again
+        assert!(matches!(
+            escrow.resolve(&secp, &approvals, &participants),
+            Err(Error::InvalidTransition(_))
+        ));
+    }
+
+    #[test]
+    fn test_dispute_refunded_by_arbiter() {
+        let secp = Secp256k1::with_caps(ContextFlag::Commit);
+        let (mut escrow, keys) = open(&secp);
+        let refund = [
+            escrow.approve(&secp, &keys[2], Decision::Refund).unwrap(),
+            escrow.approve(&secp, &keys[0], Decision::Refund).unwrap(),
+        ];
+        let (participants, excess_keys) = kernel_keys(&secp, &[BUYER, ARBITER]);
+
+        // The arbiter has no say before a dispute
+        assert_eq!(
+            escrow.resolve(&secp, &refund, &participants).err(),
+            Some(Error::InvalidApproval {
+                participant: ARBITER
+            })
+        );
+
+        escrow
+            .dispute(
+                &secp,
+                escrow.approve(&secp, &keys[1], Decision::Dispute).unwrap(),
+            )
+            .unwrap();
+        assert_eq!(escrow.state(), EscrowState::Disputed);
+
+        let spend = escrow.resolve(&secp, &refund, &participants).unwrap();
+        assert_eq!(escrow.state(), EscrowState::Refunded);
+        assert_eq!(spend.decision(), Decision::Refund);
+        assert_eq!(spend.signers(), &[BUYER, ARBITER]);
+        let record: Vec<_> = escrow
+            .approvals()
+            .iter()
+            .map(|a| (a.participant(), a.decision()))
+            .collect();
+        assert_eq!(
+            record,
+            vec![
+                (SELLER, Decision::Dispute),
+                (ARBITER, Decision::Refund),
+                (BUYER, Decision::Refund)
+            ]
+        );
+
+        let tx = pay_out(&escrow, &spend, &keys, &excess_keys, BUYER);
+        assert_eq!(tx.fee(), 10);
+    }
+
+    #[test]
+    fn test_invalid_approvals_rejected() {
+        let secp = Secp256k1::with_caps(ContextFlag::Commit);
+        let (mut escrow, keys) = open(&secp);
+        let (participants, _) = kernel_keys(&secp, &[BUYER, SELLER]);
+
+        // An approval for one decision cannot be replayed as another
+        let mut forged = escrow.approve(&secp, &keys[0], Decision::Release).unwrap();
+        forged.decision = Decision::Refund;
+        let seller = escrow.approve(&secp, &keys[1], Decision::Refund).unwrap();
+        assert_eq!(
+            escrow
+                .resolve(&secp, &[seller, forged], &participants)
+                .err(),
+            Some(Error::InvalidApproval { participant: BUYER })
+        );
+
+        // Nor be bound to another escrow's deposit
+        let (other, other_keys) = open(&secp);
+        let foreign = other
+            .approve(&secp, &other_keys[0], Decision::Refund)
+            .unwrap();
+        assert_eq!(
+            escrow
+                .resolve(&secp, &[seller, foreign], &participants)
+                .err(),
+            Some(Error::InvalidApproval { participant: BUYER })
+        );
+
+        assert_eq!(
+            escrow
+                .resolve(&secp, &[seller, seller], &participants)
+                .err(),
+            Some(Error::DuplicateParticipant {
+                participant: SELLER
+            })
+        );
+        assert!(matches!(
+            escrow.resolve(&secp, &[seller], &participants),
+            Err(Error::NotEnoughSigners { .. })
+        ));
+        assert_eq!(
+            escrow
+                .dispute(
+                    &secp,
+                    escrow.approve(&secp, &keys[2], Decision::Dispute).unwrap()
+                )
+                .unwrap_err(),
+            Error::InvalidApproval {
+                participant: ARBITER
+            }
+        );
+        assert_eq!(escrow.state(), EscrowState::Funded);
+        assert!(escrow.approvals().is_empty());
+    }
+}
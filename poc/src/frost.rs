@@ -0,0 +1,731 @@
+//! FROST t-of-n threshold signing
+//!
+//! Any `t` of the `n` holders of a Shamir-shared key produce a single Schnorr
+//! signature for the group public key. The signature has the same form as the
+//! `MuSig2` path's and verifies under Grin's kernel signature rules.
+//!
+//! # Algorithm
+//! ```text
+//! Round 1 (commit):  d_i, e_i random;  publish (D_i, E_i) = (d_i*G, e_i*G)
+//! Package:           B = sorted list of (i, D_i, E_i) and the message m
+//! Binding factors:   rho_i = H("rho" || Y || i || m || B)
+//! Group commitment:  R = sum(D_i + rho_i * E_i)
+//! Round 2 (sign):    z_i = (d_i + rho_i * e_i) + c * lambda_i * s_i
+//! Aggregate:         z = sum(z_i), signature (R.x, z)
+//! ```
+//!
+//! `lambda_i` is the Lagrange coefficient of signer `i` over the signer set,
+//! with the signer's `ParticipantId` as its share index. The challenge `c`
+//! and nonce sign handling are Grin's, as in [`crate::Session`].
+
+use std::collections::BTreeMap;
+
+use rand::thread_rng;
+use secp256k1zkp::{aggsig, Message, PublicKey, Secp256k1, SecretKey, Signature};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::scalar::{self, lagrange_coefficient};
+use crate::ser::{hex_pubkey, hex_seckey};
+use crate::types::{PartialSignature, ParticipantId};
+
+/// Domain separator for binding factors
+const BINDING_DOMAIN: &[u8] = b"grin-multisig/frost/rho";
+
+/// A signer's long-lived threshold key material
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyPackage {
+    /// Signer's ID, also its share index
+    participant: ParticipantId,
+
+    /// Secret signing share `s_i = f(i)`
+    #[serde(with = "hex_seckey")]
+    signing_share: SecretKey,
+
+    /// Public verification share `Y_i = s_i * G`
+    #[serde(with = "hex_pubkey")]
+    verifying_share: PublicKey,
+
+    /// Group public key `Y = f(0) * G`
+    #[serde(with = "hex_pubkey")]
+    group_public_key: PublicKey,
+
+    /// Threshold `t`
+    min_signers: usize,
+}
+
+impl KeyPackage {
+    /// Create a key package from a signing share
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidShareIndex` if `participant` is 0
+    /// Returns `Error::Crypto` if the verification share cannot be derived
+    pub fn new(
+        secp: &Secp256k1,
+        participant: ParticipantId,
+        signing_share: SecretKey,
+        group_public_key: PublicKey,
+        min_signers: usize,
+    ) -> Result<Self> {
+        scalar::share_index(secp, participant)?;
+        let verifying_share = PublicKey::from_secret_key(secp, &signing_share)?;
+        Ok(Self {
+            participant,
+            signing_share,
+            verifying_share,
+            group_public_key,
+            min_signers,
+        })
+    }
+
+    /// Get the signer's ID
+    pub const fn participant(&self) -> ParticipantId {
+        self.participant
+    }
+
+    /// Get the secret signing share
+    pub const fn signing_share(&self) -> &SecretKey {
+        &self.signing_share
+    }
+
+    /// Give up the key material, keeping only the signing share
+    pub(crate) fn into_signing_share(self) -> SecretKey {
+        self.signing_share
+    }
+
+    /// Get the public verification share
+    pub const fn verifying_share(&self) -> &PublicKey {
+        &self.verifying_share
+    }
+
+    /// Get the group public key
+    pub const fn group_public_key(&self) -> &PublicKey {
+        &self.group_public_key
+    }
+
+    /// Get the threshold
+    pub const fn min_signers(&self) -> usize {
+        self.min_signers
+    }
+}
+
+/// Public data every signer and the aggregator share
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicKeyPackage {
+    /// Verification share of every group member
+    #[serde(with = "verifying_shares_serde")]
+    verifying_shares: BTreeMap<ParticipantId, PublicKey>,
+
+    /// Group public key
+    #[serde(with = "hex_pubkey")]
+    group_public_key: PublicKey,
+
+    /// Threshold `t`
+    min_signers: usize,
+}
+
+impl PublicKeyPackage {
+    /// Create a public key package
+    pub const fn new(
+        verifying_shares: BTreeMap<ParticipantId, PublicKey>,
+        group_public_key: PublicKey,
+        min_signers: usize,
+    ) -> Self {
+        Self {
+            verifying_shares,
+            group_public_key,
+            min_signers,
+        }
+    }
+
+    /// Get every member's verification share
+    pub const fn verifying_shares(&self) -> &BTreeMap<ParticipantId, PublicKey> {
+        &self.verifying_shares
+    }
+
+    /// Get a member's verification share
+    ///
+    /// # Errors
+    /// Returns `Error::UnknownParticipant` if `participant` is not a group member
+    pub fn verifying_share(&self, participant: ParticipantId) -> Result<&PublicKey> {
+        self.verifying_shares
+            .get(&participant)
+            .ok_or(Error::UnknownParticipant { participant })
+    }
+
+    /// Get the group public key
+    pub const fn group_public_key(&self) -> &PublicKey {
+        &self.group_public_key
+    }
+
+    /// Get the threshold
+    pub const fn min_signers(&self) -> usize {
+        self.min_signers
+    }
+}
+
+/// A signer's public nonce commitments for one signing attempt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SigningCommitments {
+    /// Committing signer
+    participant: ParticipantId,
+
+    /// Hiding nonce commitment `D_i`
+    #[serde(with = "hex_pubkey")]
+    hiding: PublicKey,
+
+    /// Binding nonce commitment `E_i`
+    #[serde(with = "hex_pubkey")]
+    binding: PublicKey,
+}
+
+impl SigningCommitments {
+    /// Create signing commitments from already known points
+    pub const fn new(participant: ParticipantId, hiding: PublicKey, binding: PublicKey) -> Self {
+        Self {
+            participant,
+            hiding,
+            binding,
+        }
+    }
+
+    /// Get the committing signer
+    pub const fn participant(&self) -> ParticipantId {
+        self.participant
+    }
+
+    /// Get the hiding nonce commitment `D_i`
+    pub const fn hiding(&self) -> &PublicKey {
+        &self.hiding
+    }
+
+    /// Get the binding nonce commitment `E_i`
+    pub const fn binding(&self) -> &PublicKey {
+        &self.binding
+    }
+}
+
+/// A signer's secret nonces for one signing attempt
+///
+/// [`sign`] consumes the nonces, so one pair can never sign two packages.
+#[derive(Debug)]
+pub struct SigningNonces {
+    /// Hiding nonce `d_i`
+    hiding: SecretKey,
+
+    /// Binding nonce `e_i`
+    binding: SecretKey,
+
+    /// Matching public commitments
+    commitments: SigningCommitments,
+}
+
+impl SigningNonces {
+    /// Get the public commitments to publish
+    pub const fn commitments(&self) -> &SigningCommitments {
+        &self.commitments
+    }
+}
+
+/// Message and signer commitments for one signing attempt
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SigningPackage {
+    /// Message to sign (e.g. a kernel signature message)
+    message: [u8; 32],
+
+    /// Commitments of every signer, ordered by participant ID
+    commitments: Vec<SigningCommitments>,
+}
+
+impl SigningPackage {
+    /// Create a signing package
+    ///
+    /// # Errors
+    /// Returns `Error::NoParticipants` if there are no commitments
+    /// Returns `Error::DuplicateParticipant` if a signer committed twice
+    pub fn new(message: [u8; 32], mut commitments: Vec<SigningCommitments>) -> Result<Self> {
+        if commitments.is_empty() {
+            return Err(Error::NoParticipants);
+        }
+        commitments.sort_by_key(SigningCommitments::participant);
+        if let Some(pair) = commitments
+            .windows(2)
+            .find(|pair| pair[0].participant == pair[1].participant)
+        {
+            return Err(Error::DuplicateParticipant {
+                participant: pair[0].participant,
+            });
+        }
+        Ok(Self {
+            message,
+            commitments,
+        })
+    }
+
+    /// Get the message
+    pub const fn message(&self) -> &[u8; 32] {
+        &self.message
+    }
+
+    /// Get the signer commitments, ordered by participant ID
+    pub fn commitments(&self) -> &[SigningCommitments] {
+        &self.commitments
+    }
+
+    /// Get the IDs of the signers
+    pub fn signers(&self) -> Vec<ParticipantId> {
+        self.commitments.iter().map(|c| c.participant).collect()
+    }
+
+    /// Get a signer's commitments
+    ///
+    /// # Errors
+    /// Returns `Error::UnknownParticipant` if `participant` is not a signer
+    pub fn signer_commitments(&self, participant: ParticipantId) -> Result<&SigningCommitments> {
+        self.commitments
+            .iter()
+            .find(|c| c.participant == participant)
+            .ok_or(Error::UnknownParticipant { participant })
+    }
+}
+
+/// A signer's share of the threshold signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignatureShare {
+    /// Signer
+    participant: ParticipantId,
+
+    /// Partial signature `R_i.x || z_i`
+    share: PartialSignature,
+}
+
+impl SignatureShare {
+    /// Create a signature share from already known values
+    pub const fn new(participant: ParticipantId, share: PartialSignature) -> Self {
+        Self { participant, share }
+    }
+
+    /// Get the signer
+    pub const fn participant(&self) -> ParticipantId {
+        self.participant
+    }
+
+    /// Get the partial signature
+    pub const fn share(&self) -> &PartialSignature {
+        &self.share
+    }
+}
+
+/// Round 1: generate fresh nonces and their public commitments
+///
+/// # Errors
+/// Returns `Error::Crypto` if nonce generation fails
+pub fn commit(secp: &Secp256k1, key_package: &KeyPackage) -> Result<SigningNonces> {
+    let mut rng = thread_rng();
+    let hiding = SecretKey::new(secp, &mut rng);
+    let binding = SecretKey::new(secp, &mut rng);
+    let commitments = SigningCommitments::new(
+        key_package.participant,
+        PublicKey::from_secret_key(secp, &hiding)?,
+        PublicKey::from_secret_key(secp, &binding)?,
+    );
+    Ok(SigningNonces {
+        hiding,
+        binding,
+        commitments,
+    })
+}
+
+/// Round 2: compute this signer's signature share
+///
+/// # Arguments
+/// * `secp` - Secp256k1 context
+/// * `package` - Signing package chosen by the coordinator
+/// * `nonces` - Signer's nonces from Round 1, consumed even on error
+/// * `key_package` - Signer's key material
+///
+/// # Errors
+/// Returns `Error::NotEnoughSigners` if the package has fewer than `t` signers
+/// Returns `Error::UnknownParticipant` if the signer is not in the package
+/// Returns `Error::CommitmentMismatch` if the package carries different
+/// commitments than the signer's nonces
+pub fn sign(
+    secp: &Secp256k1,
+    package: &SigningPackage,
+    nonces: SigningNonces,
+    key_package: &KeyPackage,
+) -> Result<SignatureShare> {
+    let signers = package.signers();
+    if signers.len() < key_package.min_signers {
+        return Err(Error::NotEnoughSigners {
+            threshold: key_package.min_signers,
+            actual: signers.len(),
+        });
+    }
+
+    let SigningNonces {
+        hiding,
+        binding,
+        commitments,
+    } = nonces;
+    let participant = key_package.participant;
+    if *package.signer_commitments(participant)? != commitments {
+        let participant_index = signers
+            .iter()
+            .position(|id| *id == participant)
+            .unwrap_or_default();
+        return Err(Error::CommitmentMismatch { participant_index });
+    }
+
+    let group_key = &key_package.group_public_key;
+    let rho = binding_factor(secp, package, group_key, participant)?;
+    let group_commitment = group_commitment(secp, package, group_key)?;
+
+    // Effective nonce d_i + rho_i * e_i and weighted key lambda_i * s_i
+    let nonce = scalar::add(secp, &hiding, &scalar::mul(secp, &binding, &rho)?)?;
+    let lambda = lagrange_coefficient(secp, participant, &signers)?;
+    let weighted_share = scalar::mul(secp, &key_package.signing_share, &lambda)?;
+
+    let signature = aggsig::sign_single(
+        secp,
+        &Message::from_slice(&package.message)?,
+        &weighted_share,
+        Some(&nonce),
+        None,
+        Some(&group_commitment),
+        Some(group_key),
+        Some(&group_commitment),
+    )?;
+
+    Ok(SignatureShare::new(
+        participant,
+        PartialSignature::new(signature.to_raw_data()),
+    ))
+}
+
+/// Verify a signature share against the signer's verification share
+///
+/// # Errors
+/// Returns `Error::UnknownParticipant` if the signer is not in the package or group
+/// Returns `Error::InvalidSignatureShare` if the share does not verify
+pub fn verify_signature_share(
+    secp: &Secp256k1,
+    package: &SigningPackage,
+    share: &SignatureShare,
+    public_keys: &PublicKeyPackage,
+) -> Result<()> {
+    let participant = share.participant;
+    let commitments = package.signer_commitments(participant)?;
+    let verifying_share = public_keys.verifying_share(participant)?;
+
+    let group_key = &public_keys.group_public_key;
+    let rho = binding_factor(secp, package, group_key, participant)?;
+    let signer_commitment = PublicKey::from_combination(
+        secp,
+        vec![
+            &commitments.hiding,
+            &scalar::mul_point(secp, &commitments.binding, &rho)?,
+        ],
+    )?;
+    let group_commitment = group_commitment(secp, package, group_key)?;
+    let lambda = lagrange_coefficient(secp, participant, &package.signers())?;
+    let weighted_share = scalar::mul_point(secp, verifying_share, &lambda)?;
+    let msg = Message::from_slice(&package.message)?;
+
+    // The share must carry this signer's commitment, not another one
+    let serialized = scalar::point_bytes(secp, &signer_commitment);
+    let valid = share.share.as_bytes()[..32] == serialized[1..]
+        && Signature::from_raw_data(share.share.as_bytes()).is_ok_and(|signature| {
+            aggsig::verify_single(
+                secp,
+                &signature,
+                &msg,
+                Some(&group_commitment),
+                &weighted_share,
+                Some(group_key),
+                None,
+                true,
+            )
+        });
+
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidSignatureShare { participant })
+    }
+}
+
+/// Verify every share and combine them into the group signature
+///
+/// # Errors
+/// Returns `Error::NotEnoughSigners` if the package has fewer than `t` signers
+/// Returns `Error::MissingSignatureShare` if a signer in the package has no share
+/// Returns `Error::InvalidSignatureShare` naming the first invalid share
+pub fn aggregate(
+    secp: &Secp256k1,
+    package: &SigningPackage,
+    shares: &[SignatureShare],
+    public_keys: &PublicKeyPackage,
+) -> Result<Signature> {
+    let signers = package.signers();
+    if signers.len() < public_keys.min_signers {
+        return Err(Error::NotEnoughSigners {
+            threshold: public_keys.min_signers,
+            actual: signers.len(),
+        });
+    }
+
+    let mut ordered = Vec::with_capacity(signers.len());
+    for participant in signers {
+        let share = shares
+            .iter()
+            .find(|s| s.participant == participant)
+            .ok_or(Error::MissingSignatureShare { participant })?;
+        verify_signature_share(secp, package, share, public_keys)?;
+        ordered.push(Signature::from_raw_data(share.share.as_bytes())?);
+    }
+
+    let group_key = &public_keys.group_public_key;
+    let group_commitment = group_commitment(secp, package, group_key)?;
+    let signature =
+        aggsig::add_signatures_single(secp, ordered.iter().collect(), &group_commitment)?;
+
+    let msg = Message::from_slice(&package.message)?;
+    if !aggsig::verify_single(
+        secp,
+        &signature,
+        &msg,
+        None,
+        group_key,
+        Some(group_key),
+        None,
+        false,
+    ) {
+        return Err(Error::Crypto("aggregated signature does not verify".into()));
+    }
+
+    Ok(signature)
+}
+
+/// Binding factor `rho_i = H("rho" || Y || i || m || B)`
+///
+/// Binding the group public key `Y` follows RFC 9591, so a commitment list
+/// cannot be replayed against a different group.
+fn binding_factor(
+    secp: &Secp256k1,
+    package: &SigningPackage,
+    group_public_key: &PublicKey,
+    participant: ParticipantId,
+) -> Result<SecretKey> {
+    let mut encoded = Vec::new();
+    for c in &package.commitments {
+        encoded.extend_from_slice(&c.participant.inner().to_be_bytes());
+        encoded.extend_from_slice(&scalar::point_bytes(secp, &c.hiding));
+        encoded.extend_from_slice(&scalar::point_bytes(secp, &c.binding));
+    }
+    scalar::hash_to_scalar(
+        secp,
+        BINDING_DOMAIN,
+        &[
+            &scalar::point_bytes(secp, group_public_key),
+            &participant.inner().to_be_bytes(),
+            &package.message,
+            &encoded,
+        ],
+    )
+}
+
+/// Group commitment `R = sum(D_i + rho_i * E_i)`
+fn group_commitment(
+    secp: &Secp256k1,
+    package: &SigningPackage,
+    group_public_key: &PublicKey,
+) -> Result<PublicKey> {
+    let mut points = Vec::with_capacity(2 * package.commitments.len());
+    for c in &package.commitments {
+        let rho = binding_factor(secp, package, group_public_key, c.participant)?;
+        points.push(c.hiding);
+        points.push(scalar::mul_point(secp, &c.binding, &rho)?);
+    }
+    Ok(PublicKey::from_combination(secp, points.iter().collect())?)
+}
+
+// Serde helper for the verification share map (ID -> hex point)
+mod verifying_shares_serde {
+    use std::collections::BTreeMap;
+
+    use secp256k1zkp::PublicKey;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::ser::hex_pubkey;
+    use crate::types::ParticipantId;
+
+    #[derive(Serialize, Deserialize)]
+    struct Entry {
+        participant: ParticipantId,
+        #[serde(with = "hex_pubkey")]
+        verifying_share: PublicKey,
+    }
+
+    pub fn serialize<S>(
+        shares: &BTreeMap<ParticipantId, PublicKey>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        shares
+            .iter()
+            .map(|(participant, verifying_share)| Entry {
+                participant: *participant,
+                verifying_share: *verifying_share,
+            })
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(
+        deserializer: D,
+    ) -> Result<BTreeMap<ParticipantId, PublicKey>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let entries = Vec::<Entry>::deserialize(deserializer)?;
+        Ok(entries
+            .into_iter()
+            .map(|e| (e.participant, e.verifying_share))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{sign_with, split};
+
+    #[test]
+    fn test_any_t_of_n_signs_for_group_key() {
+        let secp = Secp256k1::new();
+        let (packages, public_keys) = split(&secp, 2, 3);
+        let message = [0x5au8; 32];
+
+        for pair in [[0, 1], [0, 2], [1, 2]] {
+            let signers = [&packages[pair[0]], &packages[pair[1]]];
+            let (package, shares) = sign_with(&secp, &signers, message).unwrap();
+            let signature = aggregate(&secp, &package, &shares, &public_keys).unwrap();
+
+            let group_key = public_keys.group_public_key();
+            let msg = Message::from_slice(&message).unwrap();
+            assert!(aggsig::verify_single(
+                &secp,
+                &signature,
+                &msg,
+                None,
+                group_key,
+                Some(group_key),
+                None,
+                false
+            ));
+        }
+
+        // All three signing also works
+        let signers: Vec<_> = packages.iter().collect();
+        let (package, shares) = sign_with(&secp, &signers, message).unwrap();
+        assert!(aggregate(&secp, &package, &shares, &public_keys).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_share_identified() {
+        let secp = Secp256k1::new();
+        let (packages, public_keys) = split(&secp, 2, 3);
+        let (package, mut shares) =
+            sign_with(&secp, &[&packages[0], &packages[2]], [1u8; 32]).unwrap();
+
+        let mut bytes = *shares[1].share().as_bytes();
+        bytes[63] ^= 1;
+        shares[1] = SignatureShare::new(shares[1].participant(), PartialSignature::new(bytes));
+
+        assert_eq!(
+            aggregate(&secp, &package, &shares, &public_keys),
+            Err(Error::InvalidSignatureShare {
+                participant: ParticipantId::new(3)
+            })
+        );
+        assert!(verify_signature_share(&secp, &package, &shares[0], &public_keys).is_ok());
+    }
+
+    #[test]
+    fn test_binding_factor_commits_to_group_key() {
+        let secp = Secp256k1::new();
+        let (packages, public_keys) = split(&secp, 2, 3);
+        let (_, other_keys) = split(&secp, 2, 3);
+        let (package, _) = sign_with(&secp, &[&packages[0], &packages[1]], [4u8; 32]).unwrap();
+        let participant = ParticipantId::new(1);
+
+        assert_ne!(
+            binding_factor(&secp, &package, public_keys.group_public_key(), participant).unwrap(),
+            binding_factor(&secp, &package, other_keys.group_public_key(), participant).unwrap()
+        );
+
+        assert_ne!(
+            group_commitment(&secp, &package, public_keys.group_public_key()).unwrap(),
+            group_commitment(&secp, &package, other_keys.group_public_key()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_below_threshold_and_missing_shares_rejected() {
+        let secp = Secp256k1::new();
+        let (packages, public_keys) = split(&secp, 2, 3);
+
+        let nonces = commit(&secp, &packages[0]).unwrap();
+        let package = SigningPackage::new([2u8; 32], vec![*nonces.commitments()]).unwrap();
+        assert_eq!(
+            sign(&secp, &package, nonces, &packages[0]).unwrap_err(),
+            Error::NotEnoughSigners {
+                threshold: 2,
+                actual: 1
+            }
+        );
+
+        let (package, shares) = sign_with(&secp, &[&packages[0], &packages[1]], [3u8; 32]).unwrap();
+        assert_eq!(
+            aggregate(&secp, &package, &shares[..1], &public_keys),
+            Err(Error::MissingSignatureShare {
+                participant: ParticipantId::new(2)
+            })
+        );
+    }
+
+    #[test]
+    fn test_lagrange_coefficients_interpolate_secret() {
+        let secp = Secp256k1::new();
+        let (packages, public_keys) = split(&secp, 3, 5);
+        let signers = [
+            ParticipantId::new(1),
+            ParticipantId::new(3),
+            ParticipantId::new(5),
+        ];
+
+        let weighted: Vec<_> = signers
+            .iter()
+            .map(|id| {
+                let lambda = lagrange_coefficient(&secp, *id, &signers).unwrap();
+                let share = packages[id.inner() as usize - 1].signing_share();
+                PublicKey::from_secret_key(&secp, &scalar::mul(&secp, share, &lambda).unwrap())
+                    .unwrap()
+            })
+            .collect();
+        let interpolated = PublicKey::from_combination(&secp, weighted.iter().collect()).unwrap();
+        assert_eq!(interpolated, *public_keys.group_public_key());
+
+        assert!(matches!(
+            lagrange_coefficient(
+                &secp,
+                ParticipantId::new(1),
+                &[ParticipantId::new(1), ParticipantId::new(1)]
+            ),
+            Err(Error::DuplicateParticipant { .. })
+        ));
+    }
+}
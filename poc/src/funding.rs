@@ -0,0 +1,489 @@
+//! Funding a shared output behind a pre-signed refund
+//!
+//! Once coins sit in a jointly held output, a co-signer who disappears locks
+//! them forever. The funding flow therefore first builds and fully co-signs a
+//! `HeightLocked` refund spending the shared output back to the funder. Only
+//! once that refund is stored will the builder produce the funding kernel
+//! message or finalize the funding transaction. The stored refund can be
+//! broadcast by the funder alone once the chain passes its lock height.
+//!
+//! # Algorithm
+//! ```text
+//! 1. C = v*H + X_agg                          (SharedOutput over the funding keys)
+//! 2. refund  = C -> funder's output, HeightLocked { lock_height }, co-signed
+//! 3. check refund: validates, spends exactly C, pays exactly the agreed
+//!    refund outputs, carries the agreed kernel
+//! 4. funding = funder's inputs -> C + change, co-signed
+//!    holder i:  b_i = sum(own outputs) + a_i * k_i - sum(own inputs)
+//! 5. at height >= lock_height the funder may broadcast the refund
+//! ```
+//!
+//! The shared output needs a range proof, which has to come from a joint
+//! range proof protocol among the holders; this crate does not provide one.
+
+use secp256k1zkp::pedersen::{Commitment, RangeProof};
+use secp256k1zkp::{PublicKey, Secp256k1, SecretKey};
+
+use crate::error::{Error, Result};
+use crate::offset::{combine_offsets, verify_balance, OffsetShare};
+use crate::session::Session;
+use crate::spend::{SharedOutput, SpendBuilder};
+use crate::transaction::{Input, KernelFeatures, Output, OutputFeatures, Transaction, TxKernel};
+use crate::types::PartialSignature;
+use crate::validation::validate;
+
+/// Builds a funding transaction that can only be signed after its refund
+#[derive(Debug, Clone)]
+pub struct FundingBuilder {
+    /// Output the funding transaction creates
+    shared: SharedOutput,
+
+    /// Funding kernel features, including the fee
+    features: KernelFeatures,
+
+    /// Funder's inputs
+    inputs: Vec<Input>,
+
+    /// Change outputs
+    change: Vec<Output>,
+
+    /// Height from which the refund can be mined
+    lock_height: u64,
+
+    /// Kernel features the refund must carry
+    refund_features: KernelFeatures,
+
+    /// Refund spending the shared output back to the funder
+    refund: SpendBuilder,
+
+    /// Fully signed refund, once stored
+    signed_refund: Option<Transaction>,
+}
+
+impl FundingBuilder {
+    /// Start a funding flow for a shared output
+    ///
+    /// # Arguments
+    /// * `funding_session` - Session over every holder's funding key `K_i`
+    /// * `value` - Value of the shared output
+    /// * `features` - Funding kernel features, including the fee
+    /// * `refund_fee` - Fee of the refund transaction
+    /// * `lock_height` - Height from which the refund can be mined
+    /// * `refund_output` - Funder's output receiving `value - refund_fee`
+    ///
+    /// # Errors
+    /// Returns `Error::NoParticipants` if the funding session is empty
+    /// Returns `Error::Crypto` if the shared commitment cannot be created
+    pub fn new(
+        funding_session: &Session,
+        value: u64,
+        features: KernelFeatures,
+        refund_fee: u64,
+        lock_height: u64,
+        refund_output: Output,
+    ) -> Result<Self> {
+        let shared = SharedOutput::from_session(funding_session, value)?;
+        let refund_features = KernelFeatures::HeightLocked {
+            fee: refund_fee,
+            lock_height,
+        };
+        Ok(Self {
+            shared,
+            features,
+            inputs: Vec::new(),
+            change: Vec::new(),
+            lock_height,
+            refund_features,
+            refund: SpendBuilder::new(shared, refund_features).output(refund_output),
+            signed_refund: None,
+        })
+    }
+
+    /// Add one of the funder's inputs
+    #[must_use]
+    pub fn input(mut self, input: Input) -> Self {
+        self.inputs.push(input);
+        self
+    }
+
+    /// Add a change output
+    #[must_use]
+    pub fn change(mut self, output: Output) -> Self {
+        self.change.push(output);
+        self
+    }
+
+    /// Add another refund output, e.g. for a second funder's contribution
+    #[must_use]
+    pub fn refund_output(mut self, output: Output) -> Self {
+        self.refund = self.refund.output(output);
+        self
+    }
+
+    /// Get the shared output being funded
+    pub const fn shared_output(&self) -> &SharedOutput {
+        &self.shared
+    }
+
+    /// Get the refund to co-sign first, with [`SharedOutput::spend_blinding`]
+    pub const fn refund(&self) -> &SpendBuilder {
+        &self.refund
+    }
+
+    /// Get the height from which the refund can be mined
+    pub const fn lock_height(&self) -> u64 {
+        self.lock_height
+    }
+
+    /// Check and store the co-signed refund
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidRefund` if the transaction does not spend exactly
+    /// the shared output into the agreed refund outputs under the agreed
+    /// refund kernel
+    /// Returns `Error::ValidationFailed` if the transaction does not validate
+    pub fn store_refund(&mut self, secp: &Secp256k1, refund: Transaction) -> Result<()> {
+        if refund.inputs() != [self.shared.input()] {
+            return Err(Error::InvalidRefund(
+                "must spend exactly the shared output".into(),
+            ));
+        }
+        let mut outputs = self.refund.outputs().to_vec();
+        outputs.sort_by_cached_key(Output::hash);
+        if refund.outputs() != outputs {
+            return Err(Error::InvalidRefund(
+                "must pay exactly the agreed refund outputs".into(),
+            ));
+        }
+        if refund.kernels().len() != 1 || *refund.kernels()[0].features() != self.refund_features {
+            return Err(Error::InvalidRefund(format!(
+                "must carry a single {:?} kernel",
+                self.refund_features
+            )));
+        }
+        validate(secp, &refund).into_result()?;
+
+        self.signed_refund = Some(refund);
+        Ok(())
+    }
+
+    /// Get the stored refund
+    pub const fn signed_refund(&self) -> Option<&Transaction> {
+        self.signed_refund.as_ref()
+    }
+
+    /// Get the stored refund if it can be mined at `height`
+    pub fn refund_at(&self, height: u64) -> Option<&Transaction> {
+        self.signed_refund
+            .as_ref()
+            .filter(|_| height >= self.lock_height())
+    }
+
+    /// Compute a holder's funding blinding total `b_i`
+    ///
+    /// The holder's share `a_i * k_i` of the shared output counts as one of
+    /// its own outputs.
+    ///
+    /// # Arguments
+    /// * `funding_session` - Funding session the shared output was created with
+    /// * `funding_key` - Holder's secret funding key `k_i`
+    /// * `output_blinds` - Blinding factors of the holder's change outputs
+    /// * `input_blinds` - Blinding factors of the holder's inputs
+    ///
+    /// # Errors
+    /// Returns `Error::Crypto` if the blinding factors sum to zero
+    pub fn funding_blinding(
+        funding_session: &Session,
+        funding_key: &SecretKey,
+        output_blinds: &[SecretKey],
+        input_blinds: &[SecretKey],
+    ) -> Result<SecretKey> {
+        let mut positive = output_blinds.to_vec();
+        positive.push(funding_session.weighted_secret_key(funding_key)?);
+        Ok(funding_session
+            .secp()
+            .blind_sum(positive, input_blinds.to_vec())?)
+    }
+
+    /// Message every holder signs in the funding kernel session
+    ///
+    /// # Errors
+    /// Returns `Error::MissingRefund` until the refund is stored
+    pub fn kernel_message(&self) -> Result<[u8; 32]> {
+        self.signed_refund
+            .as_ref()
+            .map(|_| self.features.kernel_sig_msg())
+            .ok_or(Error::MissingRefund)
+    }
+
+    /// Combine offset shares and signatures into the funding transaction
+    ///
+    /// # Arguments
+    /// * `session` - Kernel session over every holder's excess key
+    /// * `shared_proof` - Range proof for the shared output
+    /// * `offset_shares` - One offset share per holder
+    /// * `partials` - Partial signatures over [`Self::kernel_message`]
+    /// * `revealed_nonces` - Public nonces revealed in Round 2
+    /// * `agg_nonce` - Aggregated nonce from Round 2
+    ///
+    /// # Errors
+    /// Returns `Error::MissingRefund` until the refund is stored
+    /// Returns `Error::MissingOffsetShare` or `Error::UnknownParticipant` if the
+    /// offset shares do not match the session
+    /// Returns `Error::UnbalancedTransaction` if inputs, outputs, fee and kernel
+    /// do not balance
+    /// Returns `Error::InvalidPartialSignature` if any partial signature is invalid
+    pub fn finalize(
+        &self,
+        session: &Session,
+        shared_proof: RangeProof,
+        offset_shares: &[OffsetShare],
+        partials: &[PartialSignature],
+        revealed_nonces: &[(PublicKey, PublicKey)],
+        agg_nonce: &PublicKey,
+    ) -> Result<Transaction> {
+        let message = self.kernel_message()?;
+
+        let mut outputs = self.change.clone();
+        outputs.push(Output::new(
+            OutputFeatures::Plain,
+            *self.shared.commitment(),
+            shared_proof,
+        ));
+        let offset = combine_offsets(session, offset_shares)?;
+        let output_commits: Vec<_> = outputs.iter().map(|o| *o.commitment()).collect();
+        let input_commits: Vec<_> = self.inputs.iter().map(|i| *i.commitment()).collect();
+        verify_balance(
+            session,
+            &output_commits,
+            &input_commits,
+            self.features.fee(),
+            &offset,
+        )?;
+
+        let excess_sig =
+            session.aggregate_signatures(&message, partials, revealed_nonces, agg_nonce)?;
+        let excess = Commitment::from_pubkey(session.secp(), &session.aggregate_pubkeys()?)?;
+
+        Ok(Transaction::new(
+            offset,
+            self.inputs.clone(),
+            outputs,
+            vec![TxKernel::new(self.features, excess, excess_sig)],
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_util::{co_sign, keyed_session};
+
+    use rand::thread_rng;
+    use secp256k1zkp::ContextFlag;
+
+    /// Holder 1 funds a 2-of-2 output of 100 from a 150 input, refund at 1000
+    struct Setup {
+        secp: Secp256k1,
+        funding: Session,
+        funding_keys: Vec<SecretKey>,
+        builder: FundingBuilder,
+        refund_blind: SecretKey,
+        input_blind: SecretKey,
+        change_blind: SecretKey,
+    }
+
+    fn setup() -> Setup {
+        let secp = Secp256k1::with_caps(ContextFlag::Commit);
+        let mut rng = thread_rng();
+        let (funding, funding_keys) = keyed_session(&secp, 2);
+
+        let refund_blind = SecretKey::new(&secp, &mut rng);
+        let input_blind = SecretKey::new(&secp, &mut rng);
+        let change_blind = SecretKey::new(&secp, &mut rng);
+        let input = Input::new(
+            OutputFeatures::Plain,
+            secp.commit(150, input_blind.clone()).unwrap(),
+        );
+        let builder = FundingBuilder::new(
+            &funding,
+            100,
+            KernelFeatures::Plain { fee: 2 },
+            1,
+            1_000,
+            Output::create(&secp, 99, &refund_blind).unwrap(),
+        )
+        .unwrap()
+        .input(input)
+        .change(Output::create(&secp, 48, &change_blind).unwrap());
+
+        Setup {
+            secp,
+            funding,
+            funding_keys,
+            builder,
+            refund_blind,
+            input_blind,
+            change_blind,
+        }
+    }
+
+    fn sign_refund(s: &Setup) -> Transaction {
+        sign_spend(s, s.builder.refund(), &s.refund_blind)
+    }
+
+    /// Co-sign a spend of the shared output into one output of `blind`
+    fn sign_spend(s: &Setup, refund: &SpendBuilder, blind: &SecretKey) -> Transaction {
+        let (kernel, excess_keys) = keyed_session(&s.secp, 2);
+        let output = s.builder.shared_output();
+        let blindings = [
+            output
+                .spend_blinding(&s.funding, &s.funding_keys[0], std::slice::from_ref(blind))
+                .unwrap(),
+            output
+                .spend_blinding(&s.funding, &s.funding_keys[1], &[])
+                .unwrap(),
+        ];
+        let (shares, partials, revealed, agg_nonce) = co_sign(
+            &kernel,
+            &excess_keys,
+            &blindings,
+            &refund.kernel_message(),
+            None,
+        );
+        refund
+            .finalize(&kernel, &shares, &partials, &revealed, &agg_nonce)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_refund_signed_before_funding() {
+        let mut s = setup();
+        assert_eq!(
+            s.builder.kernel_message().unwrap_err(),
+            Error::MissingRefund
+        );
+
+        let refund = sign_refund(&s);
+        s.builder.store_refund(&s.secp, refund).unwrap();
+        assert!(s.builder.refund_at(999).is_none());
+        assert!(s.builder.refund_at(1_000).is_some());
+
+        // Now the funding transaction can be co-signed
+        let (kernel, excess_keys) = keyed_session(&s.secp, 2);
+        let blindings = [
+            FundingBuilder::funding_blinding(
+                &s.funding,
+                &s.funding_keys[0],
+                &[s.change_blind.clone()],
+                &[s.input_blind.clone()],
+            )
+            .unwrap(),
+            FundingBuilder::funding_blinding(&s.funding, &s.funding_keys[1], &[], &[]).unwrap(),
+        ];
+        let message = s.builder.kernel_message().unwrap();
+        let (shares, partials, revealed, agg_nonce) =
+            co_sign(&kernel, &excess_keys, &blindings, &message, None);
+
+        // Stand-in for a joint range proof: the test knows every funding key
+        let shared_blind = s
+            .secp
+            .blind_sum(
+                s.funding_keys
+                    .iter()
+                    .map(|k| s.funding.weighted_secret_key(k).unwrap())
+                    .collect(),
+                vec![],
+            )
+            .unwrap();
+        let proof = *Output::create(&s.secp, 100, &shared_blind).unwrap().proof();
+
+        let funding = s
+            .builder
+            .finalize(&kernel, proof, &shares, &partials, &revealed, &agg_nonce)
+            .unwrap();
+        assert!(validate(&s.secp, &funding).into_result().is_ok());
+        assert!(funding
+            .outputs()
+            .iter()
+            .any(|o| o.commitment() == s.builder.shared_output().commitment()));
+        assert_eq!(
+            s.builder.refund_at(1_000).unwrap().inputs()[0].commitment(),
+            s.builder.shared_output().commitment()
+        );
+    }
+
+    #[test]
+    fn test_funding_refused_without_refund() {
+        let s = setup();
+        let (kernel, excess_keys) = keyed_session(&s.secp, 2);
+        let blindings = [s.change_blind.clone(), s.input_blind.clone()];
+        let (shares, partials, revealed, agg_nonce) =
+            co_sign(&kernel, &excess_keys, &blindings, &[0u8; 32], None);
+        let proof = *Output::create(&s.secp, 100, &s.change_blind)
+            .unwrap()
+            .proof();
+
+        assert_eq!(
+            s.builder
+                .finalize(&kernel, proof, &shares, &partials, &revealed, &agg_nonce)
+                .unwrap_err(),
+            Error::MissingRefund
+        );
+        assert!(s.builder.refund_at(u64::MAX).is_none());
+    }
+
+    #[test]
+    fn test_wrong_refund_rejected() {
+        let mut s = setup();
+        let refund = sign_refund(&s);
+
+        // Spending something else
+        let other = Transaction::new(
+            refund.offset().clone(),
+            vec![Input::new(
+                OutputFeatures::Plain,
+                *refund.outputs()[0].commitment(),
+            )],
+            refund.outputs().to_vec(),
+            refund.kernels().to_vec(),
+        );
+        assert!(matches!(
+            s.builder.store_refund(&s.secp, other),
+            Err(Error::InvalidRefund(_))
+        ));
+
+        // Without the agreed lock height
+        let (kernel, sig) = (refund.kernels()[0], *refund.kernels()[0].excess_sig());
+        let unlocked = Transaction::new(
+            refund.offset().clone(),
+            refund.inputs().to_vec(),
+            refund.outputs().to_vec(),
+            vec![TxKernel::new(
+                KernelFeatures::HeightLocked {
+                    fee: 1,
+                    lock_height: 10,
+                },
+                *kernel.excess(),
+                sig,
+            )],
+        );
+        assert!(matches!(
+            s.builder.store_refund(&s.secp, unlocked),
+            Err(Error::InvalidRefund(_))
+        ));
+
+        // Valid, but paying the shared output to someone else
+        let thief_blind = SecretKey::new(&s.secp, &mut thread_rng());
+        let diverted = SpendBuilder::new(*s.builder.shared_output(), s.builder.refund_features)
+            .output(Output::create(&s.secp, 99, &thief_blind).unwrap());
+        let diverted = sign_spend(&s, &diverted, &thief_blind);
+        assert!(validate(&s.secp, &diverted).into_result().is_ok());
+        assert!(matches!(
+            s.builder.store_refund(&s.secp, diverted),
+            Err(Error::InvalidRefund(_))
+        ));
+        assert!(s.builder.signed_refund().is_none());
+    }
+}
@@ -9,6 +9,31 @@
 //! - **Two-Round Nonces**: Commitment protocol prevents adaptive attacks
 //! - **Type Safety**: Uses newtype pattern for strong typing
 //! - **Error Handling**: Proper Rust error types instead of strings
+//! - **Cooperative Offsets**: Every participant contributes a share of the kernel offset
+//! - **Slatepack Transport**: Round messages armor to `BEGINSLATEPACK` text for async exchange
+//! - **Shared Outputs**: Jointly held outputs can be spent into a co-signed Grin transaction
+//! - **Local Validation**: Finished transactions are checked independently before broadcast
+//! - **Node Export**: Transactions serialize to the JSON and binary layouts a Grin node accepts
+//! - **FROST**: Any `t` of `n` key share holders can sign a kernel
+//! - **Verifiable Secret Sharing**: A trusted dealer can split a key with Feldman commitments
+//! - **Distributed Key Generation**: Threshold keys without any machine holding the full key
+//! - **`ChillDKG`**: Key generation over one broadcast channel with encrypted, recoverable shares
+//! - **Proactive Refresh**: Re-randomise every share while the group key stays the same
+//! - **Resharing**: Move a threshold key to a new committee or threshold
+//! - **Share Repair**: Any `t` members can rebuild a member's lost share
+//! - **Identifiable Abort**: Failed threshold rounds name every faulty signer with a checkable proof
+//! - **Robust Signing**: ROAST coordinator finishes once `t` honest signers respond, despite offline or malicious ones
+//! - **Nested `MuSig2`**: An organisation's own N-of-N can sign as one participant
+//! - **Subset Keys**: Emulate a small k-of-n with one `MuSig2` key per qualifying subset
+//! - **Escrow**: Buyer, seller and arbiter with signed approvals for every resolution
+//! - **Refund First**: A height-locked refund is co-signed before a shared output is funded
+//! - **Adaptor Signatures**: Pre-signatures locked to a point, for atomic swaps and conditional payments
+//! - **Atomic Swaps**: Grin↔BTC swap engine with refunds on both chains, tested against mock chains
+//! - **Payment Channels**: Two-party channels with co-signed off-chain updates, NRD-delayed unilateral closes and revocation penalties
+//! - **Discreet Log Contracts**: Oracle-settled contracts with CETs pre-signed on attestation points and a height-locked refund
+//! - **Spending Policies**: Per-signer amount caps, rolling daily limits, counterparty allow lists and minimum lock heights checked before Round 3
+//! - **Co-Signer Service**: Automated co-signing behind the policy, with TOTP or challenge approval above a threshold, rate limits and an audit trail
+//! - **Participant-Set Rotation**: Sweep every group output to a new set's aggregate key, co-signed by the old set, and move the group record over
 //!
 //! # Example
 //!
@@ -52,13 +77,50 @@
 //! - [Grin Documentation](https://github.com/mimblewimble/grin)
 
 // Module declarations
+pub mod adaptor;
+pub mod blame;
+pub mod channel;
+pub mod chilldkg;
+pub mod cosigner;
+pub mod dkg;
+pub mod dlc;
 mod error;
+pub mod escrow;
+pub mod frost;
+pub mod funding;
+mod message;
+#[cfg(test)]
+mod mock;
+pub mod nested;
+pub mod offset;
 mod participant;
+pub mod policy;
+pub mod refresh;
+pub mod repair;
+pub mod reshare;
+pub mod roast;
+pub mod rotation;
+mod scalar;
+mod ser;
 mod session;
+pub mod slatepack;
+pub mod spend;
+pub mod subset;
+pub mod swap;
+#[cfg(test)]
+mod test_util;
+pub mod transaction;
 mod types;
+pub mod validation;
+pub mod vss;
 
 // Re-exports for public API
 pub use error::{Error, Result};
+pub use message::{RoundMessage, MESSAGE_VERSION};
+pub use offset::OffsetShare;
 pub use participant::Participant;
 pub use session::{Round1State, Session};
-pub use types::{Challenge, Coefficient, NonceCommitment, ParticipantId};
+pub use spend::{SharedOutput, SpendBuilder};
+pub use transaction::Transaction;
+pub use types::{Challenge, Coefficient, NonceCommitment, PartialSignature, ParticipantId};
+pub use validation::{validate, ValidationReport};
@@ -0,0 +1,260 @@
+//! Multisig round messages exchanged between participants
+//!
+//! Every message names its sender and can be armored as a Slatepack so it can
+//! travel over any text channel. Importing a message verifies the armor
+//! checksum, the message version and every encoded curve point.
+
+use secp256k1zkp::{PublicKey, SecretKey};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::offset::OffsetShare;
+use crate::ser::{hex_pubkey, hex_seckey};
+use crate::slatepack;
+use crate::types::{NonceCommitment, PartialSignature, ParticipantId};
+
+/// Current round message format version
+pub const MESSAGE_VERSION: u16 = 1;
+
+/// A message sent by one participant during a signing session
+///
+/// # Example
+///
+/// ```rust
+/// use grin_multisig_poc::{NonceCommitment, ParticipantId, RoundMessage};
+///
+/// let message = RoundMessage::NonceCommitment {
+///     participant: ParticipantId::new(1),
+///     commitment: NonceCommitment::new([7u8; 64]),
+/// };
+///
+/// let armored = message.to_slatepack().unwrap();
+/// assert_eq!(RoundMessage::from_slatepack(&armored).unwrap(), message);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RoundMessage {
+    /// Round 1: commitment `H(R1 || R2)` to the sender's public nonces
+    NonceCommitment {
+        /// Sender of the message
+        participant: ParticipantId,
+        /// Nonce commitment
+        commitment: NonceCommitment,
+    },
+
+    /// Round 2: the sender's public nonces `(R1, R2)`
+    NonceReveal {
+        /// Sender of the message
+        participant: ParticipantId,
+        /// First public nonce
+        #[serde(with = "hex_pubkey")]
+        r1: PublicKey,
+        /// Second public nonce
+        #[serde(with = "hex_pubkey")]
+        r2: PublicKey,
+    },
+
+    /// The sender's share of the kernel offset
+    OffsetShare {
+        /// Sender of the message
+        participant: ParticipantId,
+        /// Offset share
+        #[serde(with = "hex_seckey")]
+        offset: SecretKey,
+    },
+
+    /// Round 3: the sender's partial signature
+    PartialSignature {
+        /// Sender of the message
+        participant: ParticipantId,
+        /// Partial signature
+        signature: PartialSignature,
+    },
+}
+
+/// Versioned wire envelope for a round message
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    version: u16,
+    #[serde(flatten)]
+    message: RoundMessage,
+}
+
+impl From<OffsetShare> for RoundMessage {
+    fn from(share: OffsetShare) -> Self {
+        Self::OffsetShare {
+            participant: share.participant(),
+            offset: share.offset().clone(),
+        }
+    }
+}
+
+impl RoundMessage {
+    /// Get the participant that sent this message
+    pub const fn participant(&self) -> ParticipantId {
+        match self {
+            Self::NonceCommitment { participant, .. }
+            | Self::NonceReveal { participant, .. }
+            | Self::OffsetShare { participant, .. }
+            | Self::PartialSignature { participant, .. } => *participant,
+        }
+    }
+
+    /// Encode the message as armored Slatepack text
+    ///
+    /// # Errors
+    /// Returns `Error::Serialization` if the message cannot be serialized
+    pub fn to_slatepack(&self) -> Result<String> {
+        let envelope = Envelope {
+            version: MESSAGE_VERSION,
+            message: self.clone(),
+        };
+        let payload = serde_json::to_vec(&envelope)?;
+        Ok(slatepack::encode(&payload))
+    }
+
+    /// Decode and validate a message from armored Slatepack text
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidArmor` or `Error::ChecksumMismatch` if the armor is damaged
+    /// Returns `Error::Serialization` if the payload is not a valid message of
+    /// a supported version
+    pub fn from_slatepack(armored: &str) -> Result<Self> {
+        let payload = slatepack::decode(armored)?;
+        let envelope: Envelope = serde_json::from_slice(&payload)?;
+        if envelope.version != MESSAGE_VERSION {
+            return Err(Error::Serialization(format!(
+                "unsupported message version {}",
+                envelope.version
+            )));
+        }
+        Ok(envelope.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::participant::Participant;
+    use crate::session::Session;
+    use rand::thread_rng;
+    use secp256k1zkp::{Secp256k1, SecretKey};
+
+    fn create_test_session() -> Session {
+        let secp = Secp256k1::new();
+        let sk = SecretKey::new(&secp, &mut thread_rng());
+        let pk = PublicKey::from_secret_key(&secp, &sk).expect("Failed to derive public key");
+        Session::new(vec![Participant::new(ParticipantId::new(1), pk)])
+    }
+
+    #[test]
+    fn test_round_messages_roundtrip() {
+        let secp = Secp256k1::new();
+        let mut rng = thread_rng();
+        let session = create_test_session();
+        let round1 = session.round1_generate_nonces().unwrap();
+        let (r1, r2) = *round1.public_nonces();
+
+        let messages = [
+            RoundMessage::NonceCommitment {
+                participant: ParticipantId::new(1),
+                commitment: *round1.commitment(),
+            },
+            RoundMessage::NonceReveal {
+                participant: ParticipantId::new(1),
+                r1,
+                r2,
+            },
+            RoundMessage::PartialSignature {
+                participant: ParticipantId::new(1),
+                signature: PartialSignature::new([0x42; 64]),
+            },
+            OffsetShare::new(ParticipantId::new(1), SecretKey::new(&secp, &mut rng)).into(),
+        ];
+
+        for message in messages {
+            let armored = message.to_slatepack().unwrap();
+            assert!(armored.starts_with(slatepack::HEADER));
+            assert!(armored.trim_end().ends_with(slatepack::FOOTER));
+            assert_eq!(RoundMessage::from_slatepack(&armored).unwrap(), message);
+        }
+    }
+
+    #[test]
+    fn test_armor_survives_reformatting() {
+        let message = RoundMessage::NonceCommitment {
+            participant: ParticipantId::new(3),
+            commitment: NonceCommitment::new([1u8; 64]),
+        };
+        let armored = message.to_slatepack().unwrap();
+
+        // Email clients quote and re-wrap lines
+        let quoted = armored
+            .split(' ')
+            .map(|word| format!("> {word}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        assert_eq!(RoundMessage::from_slatepack(&quoted).unwrap(), message);
+    }
+
+    #[test]
+    fn test_corrupted_payload_rejected() {
+        let message = RoundMessage::NonceCommitment {
+            participant: ParticipantId::new(1),
+            commitment: NonceCommitment::new([9u8; 64]),
+        };
+        let armored = message.to_slatepack().unwrap();
+
+        // Swap the 20th Base58 character of the payload for another valid one
+        let position = armored
+            .char_indices()
+            .skip(slatepack::HEADER.len())
+            .filter(|(_, c)| c.is_ascii_alphanumeric())
+            .nth(20)
+            .map(|(i, _)| i)
+            .unwrap();
+        let mut corrupted: Vec<char> = armored.chars().collect();
+        corrupted[position] = if corrupted[position] == '2' { '3' } else { '2' };
+        let corrupted: String = corrupted.into_iter().collect();
+
+        assert_eq!(
+            RoundMessage::from_slatepack(&corrupted),
+            Err(Error::ChecksumMismatch)
+        );
+    }
+
+    #[test]
+    fn test_bad_framing_rejected() {
+        let message = RoundMessage::NonceCommitment {
+            participant: ParticipantId::new(1),
+            commitment: NonceCommitment::new([9u8; 64]),
+        };
+        let armored = message.to_slatepack().unwrap();
+        let truncated = armored.replace(slatepack::FOOTER, "");
+
+        assert!(matches!(
+            RoundMessage::from_slatepack(&truncated),
+            Err(Error::InvalidArmor(_))
+        ));
+        assert!(matches!(
+            RoundMessage::from_slatepack("hello. world. ENDSLATEPACK."),
+            Err(Error::InvalidArmor(_))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_point_rejected() {
+        // Well-formed 33-byte compressed encoding, but x = 0 is not on secp256k1
+        let off_curve = format!("02{}", "00".repeat(32));
+        let payload = format!(
+            r#"{{"version":1,"type":"nonce_reveal","participant":1,"r1":"{off_curve}","r2":"{off_curve}"}}"#
+        );
+        let armored = slatepack::encode(payload.as_bytes());
+
+        assert!(matches!(
+            RoundMessage::from_slatepack(&armored),
+            Err(Error::Serialization(_))
+        ));
+    }
+}
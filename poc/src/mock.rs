@@ -0,0 +1,194 @@
+//! In-process chains for tests
+//!
+//! Stand-ins for a Grin node and a Bitcoin node and wallet, used to drive
+//! swaps, channels, contracts and rotations without a network. Blocks are
+//! mined by hand, so both chains only advance when a test says so.
+
+use secp256k1zkp::pedersen::Commitment;
+use secp256k1zkp::{ContextFlag, PublicKey, Secp256k1, SecretKey};
+
+use crate::error::{Error, Result};
+use crate::swap::{BitcoinChain, BitcoinLock, GrinChain, LockStatus};
+use crate::transaction::{KernelFeatures, Transaction, TxKernel};
+use crate::validation::validate;
+
+/// In-process Grin chain: validates and applies transactions immediately
+///
+/// Height locks and no-recent-duplicate relative locks are enforced against
+/// the current height.
+#[derive(Debug)]
+pub struct MockGrinChain {
+    /// Context for validation
+    secp: Secp256k1,
+
+    /// Current height
+    height: u64,
+
+    /// Unspent outputs
+    utxos: Vec<Commitment>,
+
+    /// Mined kernels with their heights
+    kernels: Vec<(TxKernel, u64)>,
+}
+
+impl MockGrinChain {
+    /// Create an empty chain at height 0
+    pub fn new() -> Self {
+        Self {
+            secp: Secp256k1::with_caps(ContextFlag::Commit),
+            height: 0,
+            utxos: Vec::new(),
+            kernels: Vec::new(),
+        }
+    }
+
+    /// Add an output out of thin air, e.g. a wallet's existing coins
+    pub fn add_output(&mut self, commitment: Commitment) {
+        self.utxos.push(commitment);
+    }
+
+    /// Advance the chain by `blocks`
+    pub const fn mine(&mut self, blocks: u64) {
+        self.height += blocks;
+    }
+}
+
+impl Default for MockGrinChain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GrinChain for MockGrinChain {
+    fn height(&self) -> u64 {
+        self.height
+    }
+
+    fn post(&mut self, tx: &Transaction) -> Result<()> {
+        validate(&self.secp, tx).into_result()?;
+        if let Some(input) = tx
+            .inputs()
+            .iter()
+            .find(|i| !self.is_unspent(i.commitment()))
+        {
+            return Err(Error::ChainRejected(format!(
+                "input {:?} is not unspent",
+                input.commitment()
+            )));
+        }
+        for kernel in tx.kernels() {
+            match *kernel.features() {
+                KernelFeatures::HeightLocked { lock_height, .. } if lock_height > self.height => {
+                    return Err(Error::ChainRejected(format!(
+                        "kernel is locked until height {lock_height}"
+                    )));
+                }
+                KernelFeatures::NoRecentDuplicate {
+                    relative_height, ..
+                } if self.kernels.iter().any(|(k, height)| {
+                    matches!(k.features(), KernelFeatures::NoRecentDuplicate { .. })
+                        && k.excess() == kernel.excess()
+                        && self.height - height < u64::from(relative_height)
+                }) =>
+                {
+                    return Err(Error::ChainRejected(format!(
+                        "duplicate kernel within {relative_height} blocks"
+                    )));
+                }
+                _ => {}
+            }
+        }
+
+        self.utxos
+            .retain(|c| !tx.inputs().iter().any(|i| i.commitment() == c));
+        self.utxos
+            .extend(tx.outputs().iter().map(|o| *o.commitment()));
+        let height = self.height;
+        self.kernels
+            .extend(tx.kernels().iter().map(|k| (*k, height)));
+        Ok(())
+    }
+
+    fn is_unspent(&self, commitment: &Commitment) -> bool {
+        self.utxos.contains(commitment)
+    }
+
+    fn kernel(&self, excess: &Commitment) -> Option<TxKernel> {
+        self.kernels
+            .iter()
+            .find(|(k, _)| k.excess() == excess)
+            .map(|(k, _)| *k)
+    }
+}
+
+/// In-process Bitcoin chain holding point-locked contracts
+#[derive(Debug, Default)]
+pub struct MockBitcoinChain {
+    /// Current height
+    height: u64,
+
+    /// Every lock with its status
+    locks: Vec<(BitcoinLock, LockStatus)>,
+}
+
+impl MockBitcoinChain {
+    /// Create an empty chain at height 0
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Advance the chain by `blocks`
+    pub const fn mine(&mut self, blocks: u64) {
+        self.height += blocks;
+    }
+
+    fn locked(&mut self, lock: &BitcoinLock) -> Result<&mut LockStatus> {
+        match self.locks.iter_mut().find(|(l, _)| l == lock) {
+            Some((_, status)) if *status == LockStatus::Locked => Ok(status),
+            Some(_) => Err(Error::ChainRejected("lock is already spent".into())),
+            None => Err(Error::ChainRejected("lock is not on chain".into())),
+        }
+    }
+}
+
+impl BitcoinChain for MockBitcoinChain {
+    fn height(&self) -> u64 {
+        self.height
+    }
+
+    fn lock(&mut self, lock: &BitcoinLock) -> Result<()> {
+        if self.status(lock).is_some() {
+            return Err(Error::ChainRejected("lock is already on chain".into()));
+        }
+        self.locks.push((lock.clone(), LockStatus::Locked));
+        Ok(())
+    }
+
+    fn status(&self, lock: &BitcoinLock) -> Option<LockStatus> {
+        self.locks
+            .iter()
+            .find(|(l, _)| l == lock)
+            .map(|(_, status)| status.clone())
+    }
+
+    fn redeem(&mut self, lock: &BitcoinLock, secret: &SecretKey) -> Result<()> {
+        if PublicKey::from_secret_key(&Secp256k1::new(), secret)? != *lock.point() {
+            return Err(Error::ChainRejected(
+                "secret does not unlock the redeem path".into(),
+            ));
+        }
+        *self.locked(lock)? = LockStatus::Redeemed(secret.clone());
+        Ok(())
+    }
+
+    fn refund(&mut self, lock: &BitcoinLock) -> Result<()> {
+        if self.height < lock.refund_height() {
+            return Err(Error::ChainRejected(format!(
+                "lock is refundable from height {}",
+                lock.refund_height()
+            )));
+        }
+        *self.locked(lock)? = LockStatus::Refunded;
+        Ok(())
+    }
+}
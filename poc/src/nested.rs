@@ -0,0 +1,411 @@
+//! Nested `MuSig2`: participants that are themselves aggregate keys
+//!
+//! An organisation running its own internal N-of-N joins an outer session as
+//! a single [`Participant`] whose public key is the `MuSig2` aggregate of its
+//! members' keys. The members run an inner session to produce the nonces and
+//! the partial signature the outer session expects, so the outer session
+//! cannot tell a nested participant from a plain one.
+//!
+//! # Algorithm
+//! ```text
+//! Inner members Y_j, inner coefficients b_j,  X = sum(b_j * Y_j)
+//! Outer coefficient a for X
+//! Round 1 (inner): commit to (R1_j, R2_j), reveal, check commitments
+//!                  outer nonces (R1, R2) = (sum R1_j, sum R2_j)
+//! Round 3 (inner): s_j = r_j + c * a * b_j * y_j    (r_j negated like r)
+//!                  check every s_j, then s = sum(s_j) with nonce R1 + R2
+//!                  so s = r + c * a * x, the outer partial signature for X
+//! ```
+
+use secp256k1zkp::aggsig;
+use secp256k1zkp::{Message, PublicKey, SecretKey, Signature};
+
+use crate::error::{Error, Result};
+use crate::participant::Participant;
+use crate::session::Session;
+use crate::types::{NonceCommitment, PartialSignature, ParticipantId};
+
+/// Outer round 1 nonces produced by a nested participant's members
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NestedNonces {
+    /// Public nonces `(sum R1_j, sum R2_j)` sent to the outer session
+    public_nonces: (PublicKey, PublicKey),
+
+    /// Commitment to the public nonces for the outer session
+    commitment: NonceCommitment,
+}
+
+impl NestedNonces {
+    /// Get the public nonces to reveal in the outer session
+    pub const fn public_nonces(&self) -> &(PublicKey, PublicKey) {
+        &self.public_nonces
+    }
+
+    /// Get the nonce commitment to send in the outer session
+    pub const fn commitment(&self) -> &NonceCommitment {
+        &self.commitment
+    }
+}
+
+/// An outer participant backed by an inner N-of-N `MuSig2` group
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use grin_multisig_poc::nested::NestedParticipant;
+/// use grin_multisig_poc::{Participant, ParticipantId};
+/// use secp256k1zkp::{PublicKey, Secp256k1, SecretKey};
+/// use rand::thread_rng;
+///
+/// let secp = Secp256k1::new();
+/// let members = (1..=3)
+///     .map(|i| {
+///         let sk = SecretKey::new(&secp, &mut thread_rng());
+///         let pk = PublicKey::from_secret_key(&secp, &sk).unwrap();
+///         Participant::new(ParticipantId::new(i), pk)
+///     })
+///     .collect();
+///
+/// // The organisation joins the outer session as one participant
+/// let nested = NestedParticipant::new(ParticipantId::new(7), members).unwrap();
+/// let participant: Participant = nested.participant().clone();
+/// ```
+pub struct NestedParticipant {
+    /// How the group appears in the outer session
+    participant: Participant,
+
+    /// Session among the group's members
+    inner: Session,
+}
+
+impl NestedParticipant {
+    /// Create a nested participant from its members' keys
+    ///
+    /// # Arguments
+    /// * `id` - ID of the group in the outer session
+    /// * `members` - The group's members, each with its own key
+    ///
+    /// # Errors
+    /// Returns `Error::NoParticipants` if the group has no members
+    pub fn new(id: ParticipantId, members: Vec<Participant>) -> Result<Self> {
+        let inner = Session::new(members);
+        let participant = Participant::new(id, inner.aggregate_pubkeys()?);
+        Ok(Self { participant, inner })
+    }
+
+    /// Get the participant to add to the outer session
+    pub const fn participant(&self) -> &Participant {
+        &self.participant
+    }
+
+    /// Get the session among the group's members
+    ///
+    /// Members generate their nonces with its
+    /// [`round1_generate_nonces`](Session::round1_generate_nonces).
+    pub const fn inner(&self) -> &Session {
+        &self.inner
+    }
+
+    /// Inner round 2: check the members' nonces and combine them for the outer session
+    ///
+    /// # Arguments
+    /// * `commitments` - Members' nonce commitments, in member order
+    /// * `revealed_nonces` - Members' public nonces, in member order
+    ///
+    /// # Returns
+    /// The group's outer nonces and their commitment
+    ///
+    /// # Errors
+    /// Returns `Error::NoNonces` if no nonces are provided
+    /// Returns `Error::PartialSignatureCount` if there is not one nonce pair per member
+    /// Returns `Error::CommitmentMismatch` if any commitment doesn't match
+    pub fn aggregate_nonces(
+        &self,
+        commitments: &[NonceCommitment],
+        revealed_nonces: &[(PublicKey, PublicKey)],
+    ) -> Result<NestedNonces> {
+        self.check_count(commitments.len().min(revealed_nonces.len()))?;
+        self.inner
+            .round2_aggregate_nonces(commitments, revealed_nonces)?;
+
+        let secp = self.inner.secp();
+        let r1 = PublicKey::from_combination(secp, revealed_nonces.iter().map(|n| &n.0).collect())?;
+        let r2 = PublicKey::from_combination(secp, revealed_nonces.iter().map(|n| &n.1).collect())?;
+        Ok(NestedNonces {
+            public_nonces: (r1, r2),
+            commitment: NonceCommitment::from_nonces(secp, &r1, &r2),
+        })
+    }
+
+    /// Inner round 3: a member's share of the group's outer partial signature
+    ///
+    /// # Arguments
+    /// * `outer` - Outer session the group takes part in
+    /// * `message` - Message to sign (32 bytes)
+    /// * `secret_key` - Member's secret key
+    /// * `secret_nonce` - Member's effective secret nonce from inner round 1
+    /// * `agg_nonce` - Outer aggregated nonce
+    /// * `agg_pubkey` - Outer aggregated public key
+    ///
+    /// # Errors
+    /// Returns `Error::UnknownParticipant` if the group is not in the outer session
+    /// Returns `Error::Crypto` if signing fails
+    pub fn round3_partial_sign(
+        &self,
+        outer: &Session,
+        message: &[u8; 32],
+        secret_key: &SecretKey,
+        secret_nonce: &SecretKey,
+        agg_nonce: &PublicKey,
+        agg_pubkey: &PublicKey,
+    ) -> Result<PartialSignature> {
+        let secp = self.inner.secp();
+        let msg = Message::from_slice(message)?;
+        let mut weighted_key = self.inner.weighted_secret_key(secret_key)?;
+        weighted_key.mul_assign(secp, &self.outer_coefficient(outer)?)?;
+
+        let signature = aggsig::sign_single(
+            secp,
+            &msg,
+            &weighted_key,
+            Some(secret_nonce),
+            None,
+            Some(agg_nonce),
+            Some(agg_pubkey),
+            Some(agg_nonce),
+        )?;
+
+        Ok(PartialSignature::new(signature.to_raw_data()))
+    }
+
+    /// Check the members' shares and combine them into the group's outer partial signature
+    ///
+    /// # Arguments
+    /// * `outer` - Outer session the group takes part in
+    /// * `message` - Signed message (32 bytes)
+    /// * `partials` - Members' shares from inner round 3, in member order
+    /// * `revealed_nonces` - Members' public nonces, in member order
+    /// * `agg_nonce` - Outer aggregated nonce
+    /// * `agg_pubkey` - Outer aggregated public key
+    ///
+    /// # Returns
+    /// The partial signature the group sends to the outer session
+    ///
+    /// # Errors
+    /// Returns `Error::PartialSignatureCount` if there is not exactly one share
+    /// and one nonce pair per member
+    /// Returns `Error::UnknownParticipant` if the group is not in the outer session
+    /// Returns `Error::InvalidPartialSignature` if any member's share fails to verify
+    pub fn aggregate_partials(
+        &self,
+        outer: &Session,
+        message: &[u8; 32],
+        partials: &[PartialSignature],
+        revealed_nonces: &[(PublicKey, PublicKey)],
+        agg_nonce: &PublicKey,
+        agg_pubkey: &PublicKey,
+    ) -> Result<PartialSignature> {
+        self.check_count(partials.len().min(revealed_nonces.len()))?;
+        let secp = self.inner.secp();
+        let msg = Message::from_slice(message)?;
+        let coefficient = self.outer_coefficient(outer)?;
+
+        let mut signatures = Vec::with_capacity(partials.len());
+        for (i, member) in self.inner.participants().iter().enumerate() {
+            let nonce = PublicKey::from_combination(
+                secp,
+                vec![&revealed_nonces[i].0, &revealed_nonces[i].1],
+            )?;
+            let mut weighted = self.inner.weighted_pubkey(member.public_key())?;
+            weighted.mul_assign(secp, &coefficient)?;
+
+            // Same check as the outer session's, with the coefficient a * b_j
+            let serialized_nonce = nonce.serialize_vec(secp, true);
+            let signature = Signature::from_raw_data(partials[i].as_bytes())
+                .ok()
+                .filter(|_| partials[i].as_bytes()[..32] == serialized_nonce[1..])
+                .filter(|signature| {
+                    aggsig::verify_single(
+                        secp,
+                        signature,
+                        &msg,
+                        Some(agg_nonce),
+                        &weighted,
+                        Some(agg_pubkey),
+                        None,
+                        true,
+                    )
+                })
+                .ok_or(Error::InvalidPartialSignature {
+                    participant_index: i,
+                })?;
+            signatures.push(signature);
+        }
+
+        // The group's outer nonce R1 + R2 is the sum of every member's nonces
+        let group_nonce = PublicKey::from_combination(
+            secp,
+            revealed_nonces
+                .iter()
+                .flat_map(|(nonce1, nonce2)| [nonce1, nonce2])
+                .collect(),
+        )?;
+        let signature =
+            aggsig::add_signatures_single(secp, signatures.iter().collect(), &group_nonce)?;
+        Ok(PartialSignature::new(signature.to_raw_data()))
+    }
+
+    /// Outer key aggregation coefficient `a` of the group's key
+    fn outer_coefficient(&self, outer: &Session) -> Result<SecretKey> {
+        let key = self.participant.public_key();
+        if !outer.participants().iter().any(|p| p.public_key() == key) {
+            return Err(Error::UnknownParticipant {
+                participant: self.participant.id(),
+            });
+        }
+        outer
+            .key_agg_coefficient(key)
+            .to_secret_key(self.inner.secp())
+    }
+
+    const fn check_count(&self, actual: usize) -> Result<()> {
+        let expected = self.inner.participant_count();
+        if actual == expected {
+            Ok(())
+        } else {
+            Err(Error::PartialSignatureCount { expected, actual })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+    use secp256k1zkp::Secp256k1;
+
+    fn keys(secp: &Secp256k1, ids: std::ops::Range<u32>) -> (Vec<Participant>, Vec<SecretKey>) {
+        ids.map(|i| {
+            let sk = SecretKey::new(secp, &mut thread_rng());
+            let pk = PublicKey::from_secret_key(secp, &sk).unwrap();
+            (Participant::new(ParticipantId::new(i), pk), sk)
+        })
+        .unzip()
+    }
+
+    /// One plain signer and one organisation of three members sign together
+    fn setup() -> (Session, SecretKey, NestedParticipant, Vec<SecretKey>) {
+        let secp = Secp256k1::new();
+        let (plain, plain_keys) = keys(&secp, 1..2);
+        let (members, member_keys) = keys(&secp, 1..4);
+        let nested = NestedParticipant::new(ParticipantId::new(2), members).unwrap();
+
+        let outer = Session::new(vec![plain[0].clone(), nested.participant().clone()]);
+        (outer, plain_keys[0].clone(), nested, member_keys)
+    }
+
+    /// Run the outer session with the organisation signing through its inner session
+    fn sign(
+        outer: &Session,
+        plain_key: &SecretKey,
+        nested: &NestedParticipant,
+        member_keys: &[SecretKey],
+        tamper: bool,
+    ) -> Result<Signature> {
+        let message = [0x21u8; 32];
+
+        let plain_round1 = outer.round1_generate_nonces()?;
+        let member_round1: Vec<_> = member_keys
+            .iter()
+            .map(|_| nested.inner().round1_generate_nonces().unwrap())
+            .collect();
+        let member_nonces: Vec<_> = member_round1.iter().map(|r| *r.public_nonces()).collect();
+        let group_round1 = nested.aggregate_nonces(
+            &member_round1
+                .iter()
+                .map(|r| *r.commitment())
+                .collect::<Vec<_>>(),
+            &member_nonces,
+        )?;
+
+        let revealed = [*plain_round1.public_nonces(), *group_round1.public_nonces()];
+        let agg_nonce = outer.round2_aggregate_nonces(
+            &[*plain_round1.commitment(), *group_round1.commitment()],
+            &revealed,
+        )?;
+        let agg_pubkey = outer.aggregate_pubkeys()?;
+
+        let plain_partial = outer.round3_partial_sign(
+            &message,
+            plain_key,
+            plain_round1.secret_nonce(),
+            &agg_nonce,
+            &agg_pubkey,
+        )?;
+        let mut member_partials = member_keys
+            .iter()
+            .zip(&member_round1)
+            .map(|(sk, round1)| {
+                nested.round3_partial_sign(
+                    outer,
+                    &message,
+                    sk,
+                    round1.secret_nonce(),
+                    &agg_nonce,
+                    &agg_pubkey,
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if tamper {
+            member_partials.swap(0, 1);
+        }
+        let group_partial = nested.aggregate_partials(
+            outer,
+            &message,
+            &member_partials,
+            &member_nonces,
+            &agg_nonce,
+            &agg_pubkey,
+        )?;
+
+        outer.aggregate_signatures(
+            &message,
+            &[plain_partial, group_partial],
+            &revealed,
+            &agg_nonce,
+        )
+    }
+
+    #[test]
+    fn test_nested_participant_signs_in_outer_session() {
+        let (outer, plain_key, nested, member_keys) = setup();
+        let signature = sign(&outer, &plain_key, &nested, &member_keys, false).unwrap();
+
+        let agg_pubkey = outer.aggregate_pubkeys().unwrap();
+        assert!(outer.verify_signature(&[0x21u8; 32], &signature, &agg_pubkey));
+    }
+
+    #[test]
+    fn test_invalid_member_share_detected() {
+        let (outer, plain_key, nested, member_keys) = setup();
+        assert!(matches!(
+            sign(&outer, &plain_key, &nested, &member_keys, true),
+            Err(Error::InvalidPartialSignature {
+                participant_index: 0
+            })
+        ));
+    }
+
+    #[test]
+    fn test_group_must_be_in_outer_session() {
+        let (_, plain_key, nested, member_keys) = setup();
+        let secp = Secp256k1::new();
+        let (others, _) = keys(&secp, 1..3);
+        let outer = Session::new(others);
+
+        assert!(matches!(
+            sign(&outer, &plain_key, &nested, &member_keys, false),
+            Err(Error::UnknownParticipant { participant }) if participant == ParticipantId::new(2)
+        ));
+    }
+}
@@ -0,0 +1,284 @@
+//! Cooperative kernel offset generation
+//!
+//! A Grin transaction splits its blinding total into a kernel excess (signed
+//! by the participants) and a kernel offset (published in the clear). If a
+//! single party picked the offset it could link the excess to its own inputs
+//! and outputs, so every participant contributes a share of it.
+//!
+//! # Algorithm
+//! ```text
+//! x_i  = random excess key, X_i = x_i * G  (the participant's session key)
+//! b_i  = participant's blinding total (own outputs - own inputs)
+//! o_i  = b_i - a_i * x_i                   (offset share, random because x_i is)
+//!
+//! offset = sum(o_i)
+//! excess = sum(a_i * X_i) = X_agg
+//! sum(b_i) * G = excess + offset * G
+//! ```
+
+use secp256k1zkp::pedersen::Commitment;
+use secp256k1zkp::{PublicKey, SecretKey};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::ser::hex_seckey;
+use crate::session::Session;
+use crate::types::ParticipantId;
+
+/// A participant's share of the kernel offset
+///
+/// Offset shares are not secret: they are published to the other
+/// participants and summed into the transaction offset.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OffsetShare {
+    /// Contributing participant
+    participant: ParticipantId,
+
+    /// Offset share `o_i`
+    #[serde(with = "hex_seckey")]
+    offset: SecretKey,
+}
+
+impl OffsetShare {
+    /// Create an offset share from already known values
+    pub const fn new(participant: ParticipantId, offset: SecretKey) -> Self {
+        Self {
+            participant,
+            offset,
+        }
+    }
+
+    /// Derive the offset share that balances this participant's blinding total
+    ///
+    /// The participant's excess share is `a_i * x_i`, so the offset share is
+    /// `o_i = b_i - a_i * x_i`. Because `x_i` is random, so is `o_i`.
+    ///
+    /// # Arguments
+    /// * `session` - Kernel signing session over every participant's excess key
+    /// * `participant` - ID of the contributing participant
+    /// * `excess_key` - Participant's secret excess key `x_i`
+    /// * `blinding` - Participant's blinding total `b_i` (own outputs - own inputs)
+    ///
+    /// # Errors
+    /// Returns `Error::UnknownParticipant` if the participant is not in the session
+    /// Returns `Error::KeyMismatch` if `excess_key` is not the participant's session key
+    pub fn generate(
+        session: &Session,
+        participant: ParticipantId,
+        excess_key: &SecretKey,
+        blinding: &SecretKey,
+    ) -> Result<Self> {
+        let secp = session.secp();
+        let registered = session
+            .participants()
+            .iter()
+            .find(|p| p.id() == participant)
+            .ok_or(Error::UnknownParticipant { participant })?;
+        if PublicKey::from_secret_key(secp, excess_key)? != *registered.public_key() {
+            return Err(Error::KeyMismatch { participant });
+        }
+
+        let excess_share = session.weighted_secret_key(excess_key)?;
+        let offset = secp.blind_sum(vec![blinding.clone()], vec![excess_share])?;
+
+        Ok(Self::new(participant, offset))
+    }
+
+    /// Get the contributing participant
+    pub const fn participant(&self) -> ParticipantId {
+        self.participant
+    }
+
+    /// Get the offset share
+    pub const fn offset(&self) -> &SecretKey {
+        &self.offset
+    }
+}
+
+/// Combine every participant's share into the transaction offset
+///
+/// # Errors
+/// Returns `Error::UnknownParticipant` if a share comes from outside the session
+/// Returns `Error::MissingOffsetShare` if a participant has not contributed
+/// exactly one share
+pub fn combine_offsets(session: &Session, shares: &[OffsetShare]) -> Result<SecretKey> {
+    if let Some(share) = shares.iter().find(|s| {
+        !session
+            .participants()
+            .iter()
+            .any(|p| p.id() == s.participant)
+    }) {
+        return Err(Error::UnknownParticipant {
+            participant: share.participant,
+        });
+    }
+
+    for participant in session.participants() {
+        let count = shares
+            .iter()
+            .filter(|s| s.participant == participant.id())
+            .count();
+        if count != 1 {
+            return Err(Error::MissingOffsetShare {
+                participant: participant.id(),
+            });
+        }
+    }
+
+    let offsets = shares.iter().map(|s| s.offset.clone()).collect();
+    Ok(session.secp().blind_sum(offsets, vec![])?)
+}
+
+/// Check that the offset and the aggregate excess balance a transaction
+///
+/// Verifies `sum(outputs) + fee*H - sum(inputs) = X_agg + offset*G`, where
+/// `X_agg` is the session's aggregate key (the kernel excess). The fee is the
+/// transaction's overage and is counted on the output side, as in Grin.
+///
+/// # Errors
+/// Returns `Error::UnbalancedTransaction` if the equation does not hold
+pub fn verify_balance(
+    session: &Session,
+    outputs: &[Commitment],
+    inputs: &[Commitment],
+    fee: u64,
+    offset: &SecretKey,
+) -> Result<()> {
+    let secp = session.secp();
+    let excess = Commitment::from_pubkey(secp, &session.aggregate_pubkeys()?)?;
+
+    let mut positive = outputs.to_vec();
+    if fee > 0 {
+        positive.push(secp.commit_value(fee)?);
+    }
+
+    let mut negative = inputs.to_vec();
+    negative.push(excess);
+    negative.push(secp.commit(0, offset.clone())?);
+
+    if secp.verify_commit_sum(positive, negative) {
+        Ok(())
+    } else {
+        Err(Error::UnbalancedTransaction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::participant::Participant;
+    use rand::thread_rng;
+    use secp256k1zkp::{ContextFlag, Secp256k1};
+
+    struct Party {
+        id: ParticipantId,
+        excess_key: SecretKey,
+        blinding: SecretKey,
+    }
+
+    /// Two parties: party 1 spends a 100-coin input into a 60-coin output
+    /// for party 2 and 38 coins of change, paying a fee of 2.
+    fn setup() -> (Secp256k1, Vec<Party>, Vec<Commitment>, Vec<Commitment>) {
+        let secp = Secp256k1::with_caps(ContextFlag::Commit);
+        let mut rng = thread_rng();
+
+        let input_blind = SecretKey::new(&secp, &mut rng);
+        let change_blind = SecretKey::new(&secp, &mut rng);
+        let output_blind = SecretKey::new(&secp, &mut rng);
+
+        let inputs = vec![secp.commit(100, input_blind.clone()).unwrap()];
+        let outputs = vec![
+            secp.commit(38, change_blind.clone()).unwrap(),
+            secp.commit(60, output_blind.clone()).unwrap(),
+        ];
+
+        let parties = vec![
+            Party {
+                id: ParticipantId::new(1),
+                excess_key: SecretKey::new(&secp, &mut rng),
+                blinding: secp
+                    .blind_sum(vec![change_blind], vec![input_blind])
+                    .unwrap(),
+            },
+            Party {
+                id: ParticipantId::new(2),
+                excess_key: SecretKey::new(&secp, &mut rng),
+                blinding: output_blind,
+            },
+        ];
+
+        (secp, parties, outputs, inputs)
+    }
+
+    fn kernel_session(secp: &Secp256k1, parties: &[Party]) -> Session {
+        Session::new(
+            parties
+                .iter()
+                .map(|p| {
+                    let pk = PublicKey::from_secret_key(secp, &p.excess_key).unwrap();
+                    Participant::new(p.id, pk)
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_offset_and_excess_balance() {
+        let (secp, parties, outputs, inputs) = setup();
+        let session = kernel_session(&secp, &parties);
+
+        let shares: Vec<_> = parties
+            .iter()
+            .map(|p| OffsetShare::generate(&session, p.id, &p.excess_key, &p.blinding).unwrap())
+            .collect();
+        let offset = combine_offsets(&session, &shares).unwrap();
+
+        assert!(verify_balance(&session, &outputs, &inputs, 2, &offset).is_ok());
+        assert!(matches!(
+            verify_balance(&session, &outputs, &inputs, 3, &offset),
+            Err(Error::UnbalancedTransaction)
+        ));
+    }
+
+    #[test]
+    fn test_single_offset_share_does_not_balance() {
+        let (secp, parties, outputs, inputs) = setup();
+        let session = kernel_session(&secp, &parties);
+
+        let share = OffsetShare::generate(
+            &session,
+            parties[0].id,
+            &parties[0].excess_key,
+            &parties[0].blinding,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            combine_offsets(&session, std::slice::from_ref(&share)),
+            Err(Error::MissingOffsetShare { participant }) if participant == parties[1].id
+        ));
+        assert!(verify_balance(&session, &outputs, &inputs, 2, share.offset()).is_err());
+    }
+
+    #[test]
+    fn test_wrong_excess_key_rejected() {
+        let (secp, parties, _, _) = setup();
+        let session = kernel_session(&secp, &parties);
+
+        let result = OffsetShare::generate(
+            &session,
+            parties[0].id,
+            &parties[1].excess_key,
+            &parties[0].blinding,
+        );
+        assert!(matches!(result, Err(Error::KeyMismatch { .. })));
+
+        let result = OffsetShare::generate(
+            &session,
+            ParticipantId::new(9),
+            &parties[0].excess_key,
+            &parties[0].blinding,
+        );
+        assert!(matches!(result, Err(Error::UnknownParticipant { .. })));
+    }
+}
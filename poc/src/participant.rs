@@ -1,5 +1,6 @@
 //! Participant data structures
 
+use crate::ser::hex_pubkey;
 use crate::types::ParticipantId;
 use secp256k1zkp::PublicKey;
 use serde::{Deserialize, Serialize};
@@ -54,32 +55,3 @@ impl Participant {
         &self.public_key
     }
 }
-
-// Helper module for hex serialization of PublicKey
-mod hex_pubkey {
-    use secp256k1zkp::{PublicKey, Secp256k1};
-    use serde::{Deserializer, Serializer};
-
-    // Thread-local Secp256k1 context for serialization
-    thread_local! {
-        static SECP: Secp256k1 = Secp256k1::new();
-    }
-
-    pub fn serialize<S>(pubkey: &PublicKey, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let serialized_bytes = SECP.with(|secp| pubkey.serialize_vec(secp, true));
-        serializer.serialize_str(&hex::encode(&serialized_bytes[..]))
-    }
-
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<PublicKey, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        use serde::de::Error;
-        let s: String = serde::Deserialize::deserialize(deserializer)?;
-        let bytes = hex::decode(&s).map_err(Error::custom)?;
-        SECP.with(|secp| PublicKey::from_slice(secp, &bytes).map_err(Error::custom))
-    }
-}
@@ -0,0 +1,577 @@
+//! Signer-side spending policy
+//!
+//! Every signer runs its own [`Policy`] over the spend it is asked to co-sign
+//! and refuses its partial signature unless every rule passes. The
+//! [`SpendRequest`] is decoded from the spend itself: Grin hides amounts, so
+//! the amount is the value of the spent shared outputs, each checked against
+//! the signer's funding session, minus any change the signer can open. Only
+//! the counterparty is declared by the requester. A
+//! failing rule yields a structured [`Denial`] instead of a signature, and
+//! every signed spend is recorded at the signer's own clock time for the
+//! rolling daily limit.
+//!
+//! Policies are plain data and can be loaded from per-signer JSON config.
+//!
+//! # Algorithm
+//! ```text
+//! request = (kernel features, kernel message, inputs, outputs, counterparty)
+//!           every input == v*H + X_agg of the signer's funding session
+//! amount  = sum(input values) - sum(change outputs the signer opened)
+//! now     = signer's clock
+//! for rule in policy:
+//!     AmountCap(c)             amount <= c
+//!     DailyLimit(l)            sum(recorded in (now - 24h, now]) + amount <= l
+//!     AllowedCounterparties(s) counterparty in s
+//!     MinLockHeight(h)         kernel lock height >= h
+//! all pass -> round3_partial_sign(kernel message), record (now, amount)
+//! ```
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use secp256k1zkp::{PublicKey, Secp256k1, SecretKey};
+use serde::{Deserialize, Serialize};
+
+pub use crate::error::Denial;
+use crate::error::{Error, Result};
+use crate::session::Session;
+use crate::spend::{SharedOutput, SpendBuilder};
+use crate::transaction::{KernelFeatures, Output};
+use crate::types::PartialSignature;
+
+/// Length of the rolling window of [`Rule::DailyLimit`], in seconds
+pub const DAY: u64 = 24 * 60 * 60;
+
+/// One condition a spend must meet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    /// Maximum amount of a single spend
+    AmountCap(u64),
+    /// Maximum total of the spends signed in any 24 hours
+    DailyLimit(u64),
+    /// Counterparties spends may pay
+    AllowedCounterparties(Vec<String>),
+    /// Minimum lock height of the spend's kernel
+    MinLockHeight(u64),
+}
+
+/// Source of the current time for time-dependent rules
+///
+/// The signer supplies its own clock; the time is never taken from a request.
+pub trait Clock {
+    /// Current Unix time, in seconds
+    fn now(&self) -> u64;
+}
+
+/// The system's wall clock
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs())
+    }
+}
+
+/// A spend a signer is asked to co-sign
+///
+/// Everything except the counterparty comes from the [`SpendBuilder`]
+/// itself. The signer can check:
+///
+/// - the kernel features and the message it signs
+/// - the spent [`SharedOutput`]s, whose commitments [`Self::new`] recomputes
+///   from their values and the signer's funding session
+/// - change outputs it opens with [`Self::change`]
+///
+/// It cannot check who the other outputs pay, since Grin outputs hide their
+/// owner, so the counterparty is declared by the requester. The amount is
+/// therefore never declared: it is the value of the inputs minus the change
+/// the signer opened, i.e. everything leaving the shared outputs, fee included.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpendRequest {
+    /// Kernel features of the spend
+    features: KernelFeatures,
+
+    /// Message the kernel session signs
+    message: [u8; 32],
+
+    /// Shared outputs the spend consumes
+    inputs: Vec<SharedOutput>,
+
+    /// Outputs the spend creates
+    outputs: Vec<Output>,
+
+    /// Indices of the outputs opened as change, with their values
+    change: Vec<(usize, u64)>,
+
+    /// Who the spend pays, as declared by the requester
+    counterparty: String,
+}
+
+impl SpendRequest {
+    /// Decode a spend and pair it with its declared counterparty
+    ///
+    /// Until change is opened with [`Self::change`], the whole input value
+    /// counts as the amount.
+    ///
+    /// # Arguments
+    /// * `funding_session` - Signer's session the spent outputs were created with
+    /// * `spend` - Spend to co-sign
+    /// * `counterparty` - Who the spend pays; cannot be verified
+    ///
+    /// # Errors
+    /// Returns `Error::UnknownSharedOutput` if an input does not commit to its
+    /// stated value under the funding session's key
+    /// Returns the errors of [`SharedOutput::from_session`]
+    pub fn new(
+        funding_session: &Session,
+        spend: &SpendBuilder,
+        counterparty: &str,
+    ) -> Result<Self> {
+        for input in spend.inputs() {
+            if SharedOutput::from_session(funding_session, input.value())? != *input {
+                return Err(Error::UnknownSharedOutput);
+            }
+        }
+        Ok(Self {
+            features: spend.features(),
+            message: spend.kernel_message(),
+            inputs: spend.inputs().to_vec(),
+            outputs: spend.outputs().to_vec(),
+            change: Vec::new(),
+            counterparty: counterparty.to_string(),
+        })
+    }
+
+    /// Open one of the spend's outputs as change back to the signer's side
+    ///
+    /// # Arguments
+    /// * `secp` - Secp256k1 context with commitment capabilities
+    /// * `value` - Value of the change output
+    /// * `blind` - Blinding factor of the change output
+    ///
+    /// # Errors
+    /// Returns `Error::UnknownChangeOutput` if no output of the spend, not yet
+    /// opened, commits to `value` under `blind`
+    /// Returns `Error::Crypto` if the commitment cannot be created
+    pub fn change(mut self, secp: &Secp256k1, value: u64, blind: &SecretKey) -> Result<Self> {
+        let commitment = secp.commit(value, blind.clone())?;
+        let index = self
+            .outputs
+            .iter()
+            .enumerate()
+            .position(|(i, o)| {
+                *o.commitment() == commitment && !self.change.iter().any(|(c, _)| *c == i)
+            })
+            .ok_or(Error::UnknownChangeOutput)?;
+        self.change.push((index, value));
+        Ok(self)
+    }
+
+    /// Get the kernel features
+    pub const fn features(&self) -> KernelFeatures {
+        self.features
+    }
+
+    /// Get the message to sign
+    pub const fn message(&self) -> &[u8; 32] {
+        &self.message
+    }
+
+    /// Get the shared outputs the spend consumes
+    pub fn inputs(&self) -> &[SharedOutput] {
+        &self.inputs
+    }
+
+    /// Get the outputs the spend creates
+    pub fn outputs(&self) -> &[Output] {
+        &self.outputs
+    }
+
+    /// Get the total value of the spent shared outputs
+    pub fn value(&self) -> u64 {
+        self.inputs.iter().map(SharedOutput::value).sum()
+    }
+
+    /// Get the amount leaving the shared outputs: value minus opened change
+    pub fn amount(&self) -> u64 {
+        let change: u64 = self.change.iter().map(|(_, value)| value).sum();
+        self.value().saturating_sub(change)
+    }
+
+    /// Get the declared counterparty
+    pub fn counterparty(&self) -> &str {
+        &self.counterparty
+    }
+
+    /// Get the kernel's lock height, 0 if it is not height locked
+    pub const fn lock_height(&self) -> u64 {
+        match self.features {
+            KernelFeatures::HeightLocked { lock_height, .. } => lock_height,
+            _ => 0,
+        }
+    }
+}
+
+/// A signer's rules and the spends it signed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Policy {
+    /// Rules every spend must meet
+    rules: Vec<Rule>,
+
+    /// Time and amount of every signed spend
+    #[serde(default)]
+    signed: Vec<(u64, u64)>,
+}
+
+impl Policy {
+    /// Create a policy that allows everything
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a rule
+    #[must_use]
+    pub fn rule(mut self, rule: Rule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Get the rules
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// Amount signed in the 24 hours up to `time`
+    pub fn spent_in_day(&self, time: u64) -> u64 {
+        self.signed
+            .iter()
+            .filter(|(t, _)| *t <= time && time - t < DAY)
+            .map(|(_, amount)| amount)
+            .sum()
+    }
+
+    /// Check a request against every rule
+    ///
+    /// # Arguments
+    /// * `request` - Spend to co-sign
+    /// * `now` - Current Unix time from the signer's own [`Clock`]
+    ///
+    /// # Errors
+    /// Returns `Error::PolicyDenied` with the first rule that fails
+    pub fn evaluate(&self, request: &SpendRequest, now: u64) -> Result<()> {
+        let amount = request.amount();
+        for rule in &self.rules {
+            let denial = match rule {
+                Rule::AmountCap(cap) if amount > *cap => {
+                    Denial::AmountCapExceeded { amount, cap: *cap }
+                }
+                Rule::DailyLimit(limit) => {
+                    let spent = self.spent_in_day(now);
+                    if spent.saturating_add(amount) <= *limit {
+                        continue;
+                    }
+                    Denial::DailyLimitExceeded {
+                        spent,
+                        amount,
+                        limit: *limit,
+                    }
+                }
+                Rule::AllowedCounterparties(allowed)
+                    if !allowed.contains(&request.counterparty) =>
+                {
+                    Denial::CounterpartyNotAllowed {
+                        counterparty: request.counterparty.clone(),
+                    }
+                }
+                Rule::MinLockHeight(minimum) if request.lock_height() < *minimum => {
+                    Denial::LockHeightTooLow {
+                        lock_height: request.lock_height(),
+                        minimum: *minimum,
+                    }
+                }
+                _ => continue,
+            };
+            return Err(Error::PolicyDenied(denial));
+        }
+        Ok(())
+    }
+
+    /// Record a spend signed at `now`
+    pub fn record(&mut self, request: &SpendRequest, now: u64) {
+        self.signed.push((now, request.amount()));
+    }
+
+    /// Serialize the policy and its history
+    ///
+    /// # Errors
+    /// Returns `Error::Serialization` if serialization fails
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Load a policy, e.g. from a signer's config
+    ///
+    /// # Errors
+    /// Returns `Error::Serialization` if the JSON is malformed
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+impl Session {
+    /// Round 3 behind a signer's policy
+    ///
+    /// Runs `policy` over the request and only then
+    /// [`round3_partial_sign`](Session::round3_partial_sign) over its message
+    /// for the session's aggregate key, recording the spend once signed.
+    ///
+    /// # Arguments
+    /// * `policy` - Signer's policy
+    /// * `clock` - Signer's clock, for time-dependent rules
+    /// * `request` - Spend to co-sign
+    /// * `secret_key` - Signer's secret key
+    /// * `secret_nonce` - Signer's secret nonce from Round 1
+    /// * `agg_nonce` - Aggregated nonce from Round 2
+    ///
+    /// # Errors
+    /// Returns `Error::PolicyDenied` if a rule fails
+    /// Returns the errors of `aggregate_pubkeys` and `round3_partial_sign`
+    pub fn round3_partial_sign_with_policy(
+        &self,
+        policy: &mut Policy,
+        clock: &impl Clock,
+        request: &SpendRequest,
+        secret_key: &SecretKey,
+        secret_nonce: &SecretKey,
+        agg_nonce: &PublicKey,
+    ) -> Result<PartialSignature> {
+        let now = clock.now();
+        policy.evaluate(request, now)?;
+        let partial = self.round3_partial_sign(
+            &request.message,
+            secret_key,
+            secret_nonce,
+            agg_nonce,
+            &self.aggregate_pubkeys()?,
+        )?;
+        policy.record(request, now);
+        Ok(partial)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_util::{keyed_session, spend_request};
+
+    use rand::thread_rng;
+    use secp256k1zkp::{ContextFlag, Secp256k1};
+
+    /// Clock stopped at a fixed time
+    struct FixedClock(u64);
+
+    impl Clock for FixedClock {
+        fn now(&self) -> u64 {
+            self.0
+        }
+    }
+
+    fn denial(result: Result<()>) -> Denial {
+        match result {
+            Err(Error::PolicyDenied(denial)) => denial,
+            other => panic!("expected a denial, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_amount_derived_from_spend() {
+        let secp = Secp256k1::with_caps(ContextFlag::Commit);
+        let (session, _) = keyed_session(&secp, 2);
+        let blind = SecretKey::new(&secp, &mut thread_rng());
+        let spend = SpendBuilder::new(
+            SharedOutput::from_session(&session, 1_000).unwrap(),
+            KernelFeatures::Plain { fee: 2 },
+        )
+        .output(Output::create(&secp, 300, &blind).unwrap());
+
+        // Unopened, every input counts as spent
+        let request = SpendRequest::new(&session, &spend, "alice").unwrap();
+        assert_eq!(request.value(), 1_000);
+        assert_eq!(request.amount(), 1_000);
+        assert_eq!(
+            request.clone().change(&secp, 301, &blind),
+            Err(Error::UnknownChangeOutput)
+        );
+
+        let request = request.change(&secp, 300, &blind).unwrap();
+        assert_eq!(request.amount(), 700);
+        assert_eq!(request.inputs(), spend.inputs());
+        assert_eq!(request.message(), &spend.kernel_message());
+        // An output is change at most once
+        assert_eq!(
+            request.change(&secp, 300, &blind),
+            Err(Error::UnknownChangeOutput)
+        );
+    }
+
+    #[test]
+    fn test_forged_input_value_rejected() {
+        let secp = Secp256k1::with_caps(ContextFlag::Commit);
+        let (session, _) = keyed_session(&secp, 2);
+        let (other, _) = keyed_session(&secp, 2);
+        let plain = KernelFeatures::Plain { fee: 2 };
+
+        // The commitment hides 1 000, the value claims 10
+        let mut forged =
+            serde_json::to_value(SharedOutput::from_session(&session, 1_000).unwrap()).unwrap();
+        forged["value"] = 10.into();
+        let forged: SharedOutput = serde_json::from_value(forged).unwrap();
+        let spend = SpendBuilder::new(forged, plain);
+        assert_eq!(
+            SpendRequest::new(&session, &spend, "alice"),
+            Err(Error::UnknownSharedOutput)
+        );
+
+        // An honest output of another group is not the signer's either
+        let spend = SpendBuilder::new(SharedOutput::from_session(&other, 1_000).unwrap(), plain);
+        assert_eq!(
+            SpendRequest::new(&session, &spend, "alice"),
+            Err(Error::UnknownSharedOutput)
+        );
+    }
+
+    #[test]
+    fn test_rules_deny_with_reason() {
+        let secp = Secp256k1::with_caps(ContextFlag::Commit);
+        let (session, _) = keyed_session(&secp, 2);
+        let plain = KernelFeatures::Plain { fee: 2 };
+        let locked = KernelFeatures::HeightLocked {
+            fee: 2,
+            lock_height: 500,
+        };
+
+        let json = r#"{"rules":[{"amount_cap":300},{"allowed_counterparties":["alice","bob"]},{"min_lock_height":100}]}"#;
+        let policy = Policy::from_json(json).unwrap();
+        assert_eq!(policy.rules().len(), 3);
+        assert_eq!(
+            Policy::from_json(&policy.to_json().unwrap()).unwrap(),
+            policy
+        );
+
+        let evaluate = |features, amount, counterparty| {
+            policy.evaluate(
+                &spend_request(&secp, &session, features, amount, counterparty),
+                0,
+            )
+        };
+        assert_eq!(
+            denial(evaluate(locked, 301, "alice")),
+            Denial::AmountCapExceeded {
+                amount: 301,
+                cap: 300
+            }
+        );
+        assert_eq!(
+            denial(evaluate(locked, 300, "mallory")),
+            Denial::CounterpartyNotAllowed {
+                counterparty: "mallory".into()
+            }
+        );
+        assert_eq!(
+            denial(evaluate(plain, 300, "bob")),
+            Denial::LockHeightTooLow {
+                lock_height: 0,
+                minimum: 100
+            }
+        );
+        evaluate(locked, 300, "bob").unwrap();
+    }
+
+    #[test]
+    fn test_daily_limit_rolls() {
+        let secp = Secp256k1::with_caps(ContextFlag::Commit);
+        let (session, _) = keyed_session(&secp, 2);
+        let plain = KernelFeatures::Plain { fee: 2 };
+        let mut policy = Policy::new().rule(Rule::DailyLimit(1_000));
+
+        let first = spend_request(&secp, &session, plain, 600, "alice");
+        policy.evaluate(&first, 1_000).unwrap();
+        policy.record(&first, 1_000);
+
+        let second = spend_request(&secp, &session, plain, 500, "alice");
+        assert_eq!(
+            denial(policy.evaluate(&second, 1_000 + DAY - 1)),
+            Denial::DailyLimitExceeded {
+                spent: 600,
+                amount: 500,
+                limit: 1_000
+            }
+        );
+        policy
+            .evaluate(
+                &spend_request(&secp, &session, plain, 400, "alice"),
+                1_000 + DAY - 1,
+            )
+            .unwrap();
+
+        // The first spend has left the window
+        assert_eq!(policy.spent_in_day(1_000 + DAY), 0);
+        policy.evaluate(&second, 1_000 + DAY).unwrap();
+    }
+
+    #[test]
+    fn test_policy_gates_round3() {
+        let secp = Secp256k1::with_caps(ContextFlag::Commit);
+        let (session, keys) = keyed_session(&secp, 2);
+        let plain = KernelFeatures::Plain { fee: 2 };
+        let mut policy = Policy::new().rule(Rule::AmountCap(100));
+        let clock = FixedClock(1_000);
+
+        let rounds: Vec<_> = keys
+            .iter()
+            .map(|_| session.round1_generate_nonces().unwrap())
+            .collect();
+        let revealed: Vec<_> = rounds.iter().map(|r| *r.public_nonces()).collect();
+        let agg_nonce = session
+            .round2_aggregate_nonces(
+                &rounds.iter().map(|r| *r.commitment()).collect::<Vec<_>>(),
+                &revealed,
+            )
+            .unwrap();
+
+        let sign = |policy: &mut Policy, request: &SpendRequest| {
+            session.round3_partial_sign_with_policy(
+                policy,
+                &clock,
+                request,
+                &keys[0],
+                rounds[0].secret_nonce(),
+                &agg_nonce,
+            )
+        };
+        assert!(matches!(
+            sign(
+                &mut policy,
+                &spend_request(&secp, &session, plain, 101, "alice")
+            ),
+            Err(Error::PolicyDenied(Denial::AmountCapExceeded { .. }))
+        ));
+        assert_eq!(policy.spent_in_day(clock.now()), 0);
+
+        let allowed = spend_request(&secp, &session, plain, 100, "alice");
+        let partial = sign(&mut policy, &allowed).unwrap();
+        assert!(session.verify_partial_signature(
+            allowed.message(),
+            &partial,
+            session.participants()[0].public_key(),
+            &revealed[0],
+            &agg_nonce,
+            &session.aggregate_pubkeys().unwrap(),
+        ));
+        assert_eq!(policy.spent_in_day(clock.now()), 100);
+    }
+}
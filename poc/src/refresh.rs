@@ -0,0 +1,308 @@
+//! Proactive share refresh for threshold groups
+//!
+//! Every current holder deals a random polynomial with a zero constant term
+//! and adds the sum of the sub-shares it receives to its share. The shared
+//! secret, and so the group public key and every output locked to it, stays
+//! the same, while each share changes. An attacker who stole fewer than `t`
+//! shares before a refresh and some more after it cannot combine them: old and
+//! new shares lie on different polynomials.
+//!
+//! # Algorithm
+//! ```text
+//! Holder i:   g_i random of degree t-2,  delta_i(x) = x * g_i(x)
+//!             broadcast D_i = commitments to g_i
+//!             send delta_i(j) to holder j
+//! Holder j:   check delta_i(j)*G == j * sum(D_ik * j^k)
+//!             s_j' = s_j + sum_i delta_i(j)
+//!             Y_m' = Y_m + sum_i delta_i(m)*G          for every holder m
+//! ```
+
+use std::collections::BTreeMap;
+
+use rand::thread_rng;
+use secp256k1zkp::{PublicKey, Secp256k1, SecretKey};
+use serde::{Deserialize, Serialize};
+
+use crate::dkg::Round2Package;
+use crate::error::{Error, Result};
+use crate::frost::{KeyPackage, PublicKeyPackage};
+use crate::scalar;
+use crate::types::ParticipantId;
+use crate::vss::{Polynomial, VssCommitment};
+
+/// A holder's broadcast commitment to its zero-sharing polynomial
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RefreshCommitment {
+    /// Dealer
+    dealer: ParticipantId,
+
+    /// Commitments to `g`, where `delta(x) = x * g(x)`
+    commitment: VssCommitment,
+}
+
+impl RefreshCommitment {
+    /// Get the dealer
+    pub const fn dealer(&self) -> ParticipantId {
+        self.dealer
+    }
+
+    /// Expected public sub-share `delta(j) * G = j * g(j) * G`
+    fn sub_share_point(&self, secp: &Secp256k1, participant: ParticipantId) -> Result<PublicKey> {
+        let point = self.commitment.verifying_share(secp, participant)?;
+        scalar::mul_point(secp, &point, &scalar::share_index(secp, participant)?)
+    }
+}
+
+/// Deal a refresh: commitments to broadcast and one sub-share per holder
+///
+/// # Arguments
+/// * `secp` - Secp256k1 context
+/// * `key_package` - Dealer's current key material
+/// * `public_keys` - Group's current public keys; every member is a recipient
+///
+/// # Returns
+/// The broadcast commitment and a private sub-share for every holder,
+/// including the dealer itself
+///
+/// # Errors
+/// Returns `Error::InvalidThreshold` if `t` is 1, where every share is the
+/// secret itself and cannot be re-randomised
+/// Returns `Error::UnknownParticipant` if the dealer is not a group member
+pub fn deal(
+    secp: &Secp256k1,
+    key_package: &KeyPackage,
+    public_keys: &PublicKeyPackage,
+) -> Result<(RefreshCommitment, BTreeMap<ParticipantId, Round2Package>)> {
+    let dealer = key_package.participant();
+    public_keys.verifying_share(dealer)?;
+    let min_signers = public_keys.min_signers();
+    if min_signers < 2 {
+        return Err(Error::InvalidThreshold {
+            threshold: min_signers,
+            participants: public_keys.verifying_shares().len(),
+        });
+    }
+
+    let polynomial = Polynomial::random(
+        secp,
+        SecretKey::new(secp, &mut thread_rng()),
+        min_signers - 1,
+    );
+    let commitment = RefreshCommitment {
+        dealer,
+        commitment: polynomial.commit(secp)?,
+    };
+
+    let shares = public_keys
+        .verifying_shares()
+        .keys()
+        .map(|&recipient| {
+            let sub_share = scalar::mul(
+                secp,
+                &polynomial.evaluate(secp, recipient)?,
+                &scalar::share_index(secp, recipient)?,
+            )?;
+            Ok((recipient, Round2Package::new(dealer, recipient, sub_share)))
+        })
+        .collect::<Result<_>>()?;
+    Ok((commitment, shares))
+}
+
+/// Apply every holder's sub-share to get the refreshed key material
+///
+/// The old key package must be discarded once this succeeds.
+///
+/// # Arguments
+/// * `secp` - Secp256k1 context
+/// * `key_package` - This holder's current key material
+/// * `public_keys` - Group's current public keys
+/// * `commitments` - Every holder's refresh commitment
+/// * `shares` - Sub-shares received, keyed by dealer
+///
+/// # Returns
+/// Refreshed key package and public keys under the unchanged group key
+///
+/// # Errors
+/// Returns `Error::MissingMessage` if a holder's commitment is missing
+/// Returns `Error::UnknownParticipant` if a commitment comes from a non-member
+/// Returns `Error::InvalidCommitment` if a commitment has the wrong degree
+/// Returns `Error::InvalidDealerShare` if a sub-share is missing or invalid
+pub fn apply(
+    secp: &Secp256k1,
+    key_package: &KeyPackage,
+    public_keys: &PublicKeyPackage,
+    commitments: &[RefreshCommitment],
+    shares: &BTreeMap<ParticipantId, Round2Package>,
+) -> Result<(KeyPackage, PublicKeyPackage)> {
+    let participant = key_package.participant();
+    let members = public_keys.verifying_shares();
+
+    let mut by_dealer = BTreeMap::new();
+    for commitment in commitments {
+        if !members.contains_key(&commitment.dealer) {
+            return Err(Error::UnknownParticipant {
+                participant: commitment.dealer,
+            });
+        }
+        if commitment.commitment.threshold() + 1 != public_keys.min_signers() {
+            return Err(Error::InvalidCommitment {
+                participant: commitment.dealer,
+            });
+        }
+        by_dealer.insert(commitment.dealer, commitment);
+    }
+
+    let mut signing_share = key_package.signing_share().clone();
+    let mut verifying_shares = members.clone();
+    for &dealer in members.keys() {
+        let commitment = by_dealer.get(&dealer).ok_or(Error::MissingMessage {
+            participant: dealer,
+        })?;
+
+        let sub_share = shares
+            .get(&dealer)
+            .filter(|p| p.recipient() == participant)
+            .map(Round2Package::share)
+            .filter(|s| {
+                PublicKey::from_secret_key(secp, s).ok()
+                    == commitment.sub_share_point(secp, participant).ok()
+            })
+            .ok_or(Error::InvalidDealerShare {
+                dealer,
+                participant,
+            })?;
+        signing_share.add_assign(secp, sub_share)?;
+
+        for (&member, verifying_share) in &mut verifying_shares {
+            *verifying_share = PublicKey::from_combination(
+                secp,
+                vec![verifying_share, &commitment.sub_share_point(secp, member)?],
+            )?;
+        }
+    }
+
+    let refreshed = KeyPackage::new(
+        secp,
+        participant,
+        signing_share,
+        *public_keys.group_public_key(),
+        public_keys.min_signers(),
+    )?;
+    let refreshed_public = PublicKeyPackage::new(
+        verifying_shares,
+        *public_keys.group_public_key(),
+        public_keys.min_signers(),
+    );
+    Ok((refreshed, refreshed_public))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{split, try_sign};
+
+    fn refresh_all(
+        secp: &Secp256k1,
+        keys: &[KeyPackage],
+        public_keys: &PublicKeyPackage,
+    ) -> Vec<(KeyPackage, PublicKeyPackage)> {
+        let dealt: Vec<_> = keys
+            .iter()
+            .map(|k| deal(secp, k, public_keys).unwrap())
+            .collect();
+        let commitments: Vec<_> = dealt.iter().map(|(c, _)| c.clone()).collect();
+        keys.iter()
+            .map(|k| {
+                let received = dealt
+                    .iter()
+                    .map(|(c, shares)| (c.dealer(), shares[&k.participant()].clone()))
+                    .collect();
+                apply(secp, k, public_keys, &commitments, &received).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_refresh_keeps_group_key_and_changes_shares() {
+        let secp = Secp256k1::new();
+        let (keys, public_keys) = split(&secp, 2, 3);
+        let refreshed = refresh_all(&secp, &keys, &public_keys);
+
+        let new_public = &refreshed[0].1;
+        assert_eq!(
+            new_public.group_public_key(),
+            public_keys.group_public_key()
+        );
+        for ((new_key, public), old_key) in refreshed.iter().zip(&keys) {
+            assert_eq!(public, new_public);
+            assert_ne!(new_key.signing_share(), old_key.signing_share());
+            assert_eq!(
+                new_key.verifying_share(),
+                new_public.verifying_share(new_key.participant()).unwrap()
+            );
+        }
+
+        assert!(try_sign(&secp, &[&refreshed[0].0, &refreshed[1].0], new_public).is_ok());
+    }
+
+    #[test]
+    fn test_old_and_new_shares_do_not_mix() {
+        let secp = Secp256k1::new();
+        let (keys, public_keys) = split(&secp, 2, 3);
+        let refreshed = refresh_all(&secp, &keys, &public_keys);
+
+        // A stale share fails against either epoch's public keys
+        let mixed = [&keys[0], &refreshed[1].0];
+        assert_eq!(
+            try_sign(&secp, &mixed, &refreshed[0].1).unwrap_err(),
+            Error::InvalidSignatureShare {
+                participant: ParticipantId::new(1)
+            }
+        );
+        assert_eq!(
+            try_sign(&secp, &mixed, &public_keys).unwrap_err(),
+            Error::InvalidSignatureShare {
+                participant: ParticipantId::new(2)
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid_sub_share_rejected() {
+        let secp = Secp256k1::new();
+        let (keys, public_keys) = split(&secp, 2, 3);
+        let dealt: Vec<_> = keys
+            .iter()
+            .map(|k| deal(&secp, k, &public_keys).unwrap())
+            .collect();
+        let commitments: Vec<_> = dealt.iter().map(|(c, _)| c.clone()).collect();
+
+        let p1 = ParticipantId::new(1);
+        let mut received: BTreeMap<_, _> = dealt
+            .iter()
+            .map(|(c, shares)| (c.dealer(), shares[&p1].clone()))
+            .collect();
+        let p3 = ParticipantId::new(3);
+        received.insert(
+            p3,
+            Round2Package::new(p3, p1, SecretKey::new(&secp, &mut thread_rng())),
+        );
+        assert_eq!(
+            apply(&secp, &keys[0], &public_keys, &commitments, &received).unwrap_err(),
+            Error::InvalidDealerShare {
+                dealer: p3,
+                participant: p1
+            }
+        );
+        assert_eq!(
+            apply(&secp, &keys[0], &public_keys, &commitments[..2], &received).unwrap_err(),
+            Error::MissingMessage { participant: p3 }
+        );
+
+        let (solo, solo_public) = split(&secp, 1, 2);
+        assert!(matches!(
+            deal(&secp, &solo[0], &solo_public),
+            Err(Error::InvalidThreshold { .. })
+        ));
+    }
+}
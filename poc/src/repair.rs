@@ -0,0 +1,256 @@
+//! Repairing a lost share with the help of `t` other members
+//!
+//! Each helper weights its share by its Lagrange coefficient at the lost
+//! member's index and splits the result into random additive parts, one per
+//! helper. Helpers only ever see sums of masked parts, so no helper learns
+//! another's share, and the lost member only learns its own share. The
+//! repaired share is checked against the member's existing verification
+//! share.
+//!
+//! # Algorithm
+//! ```text
+//! Helpers H, |H| >= t, lost member r not in H
+//! Step 1 (helper i):   delta_i = lambda_i(r) * s_i
+//!                      split delta_i = sum_j delta_ij randomly, send delta_ij to helper j
+//! Step 2 (helper j):   sigma_j = sum_i delta_ij, send sigma_j to r
+//! Step 3 (member r):   s_r = sum_j sigma_j,  check s_r * G == Y_r
+//! ```
+
+use std::collections::BTreeMap;
+
+use rand::thread_rng;
+use secp256k1zkp::{PublicKey, Secp256k1, SecretKey};
+
+use crate::dkg::Round2Package;
+use crate::error::{Error, Result};
+use crate::frost::{KeyPackage, PublicKeyPackage};
+use crate::scalar::{self, lagrange_coefficient_at};
+use crate::types::ParticipantId;
+
+/// Step 1: split this helper's weighted share among all helpers
+///
+/// # Arguments
+/// * `secp` - Secp256k1 context
+/// * `helpers` - Members taking part in the repair, at least `t`
+/// * `target` - Member whose share is being repaired
+/// * `key_package` - This helper's key material
+///
+/// # Returns
+/// One masked part per helper, including this one, keyed by recipient
+///
+/// # Errors
+/// Returns `Error::NotEnoughSigners` if fewer than `t` members help
+/// Returns `Error::UnknownParticipant` if this member is not a helper
+/// Returns `Error::DuplicateParticipant` if `target` is listed as a helper
+pub fn repair_step1(
+    secp: &Secp256k1,
+    helpers: &[ParticipantId],
+    target: ParticipantId,
+    key_package: &KeyPackage,
+) -> Result<BTreeMap<ParticipantId, Round2Package>> {
+    if helpers.len() < key_package.min_signers() {
+        return Err(Error::NotEnoughSigners {
+            threshold: key_package.min_signers(),
+            actual: helpers.len(),
+        });
+    }
+
+    let helper = key_package.participant();
+    let lambda = lagrange_coefficient_at(secp, helper, helpers, target)?;
+    let delta = scalar::mul(secp, key_package.signing_share(), &lambda)?;
+
+    // Random parts for every helper but the last, which takes the remainder
+    let mut rng = thread_rng();
+    let mut remainder = delta;
+    let mut parts = BTreeMap::new();
+    for &recipient in &helpers[..helpers.len() - 1] {
+        let part = SecretKey::new(secp, &mut rng);
+        remainder = scalar::sub(secp, &remainder, &part)?;
+        parts.insert(recipient, Round2Package::new(helper, recipient, part));
+    }
+    let last = helpers[helpers.len() - 1];
+    parts.insert(last, Round2Package::new(helper, last, remainder));
+    Ok(parts)
+}
+
+/// Step 2: sum the parts this helper received into one value for the target
+///
+/// # Arguments
+/// * `secp` - Secp256k1 context
+/// * `helpers` - Members taking part in the repair
+/// * `helper` - This helper's ID
+/// * `target` - Member whose share is being repaired
+/// * `parts` - Parts received in step 1, keyed by sending helper
+///
+/// # Errors
+/// Returns `Error::MissingMessage` if a helper's part is missing or was
+/// addressed to someone else
+pub fn repair_step2(
+    secp: &Secp256k1,
+    helpers: &[ParticipantId],
+    helper: ParticipantId,
+    target: ParticipantId,
+    parts: &BTreeMap<ParticipantId, Round2Package>,
+) -> Result<Round2Package> {
+    let sigma = sum_from(secp, helpers, helper, parts)?;
+    Ok(Round2Package::new(helper, target, sigma))
+}
+
+/// Step 3: rebuild the lost share and check it against the public keys
+///
+/// # Arguments
+/// * `secp` - Secp256k1 context
+/// * `helpers` - Members taking part in the repair
+/// * `target` - This member's ID
+/// * `sums` - Values received in step 2, keyed by helper
+/// * `public_keys` - Group's public keys, holding this member's verification share
+///
+/// # Errors
+/// Returns `Error::MissingMessage` if a helper's value is missing
+/// Returns `Error::UnknownParticipant` if `target` is not a group member
+/// Returns `Error::KeyMismatch` if the repaired share does not match the
+/// member's verification share
+pub fn repair_step3(
+    secp: &Secp256k1,
+    helpers: &[ParticipantId],
+    target: ParticipantId,
+    sums: &BTreeMap<ParticipantId, Round2Package>,
+    public_keys: &PublicKeyPackage,
+) -> Result<KeyPackage> {
+    let verifying_share = public_keys.verifying_share(target)?;
+    let signing_share = sum_from(secp, helpers, target, sums)?;
+    if PublicKey::from_secret_key(secp, &signing_share)? != *verifying_share {
+        return Err(Error::KeyMismatch {
+            participant: target,
+        });
+    }
+
+    KeyPackage::new(
+        secp,
+        target,
+        signing_share,
+        *public_keys.group_public_key(),
+        public_keys.min_signers(),
+    )
+}
+
+/// Sum one value from every helper, all addressed to `recipient`
+fn sum_from(
+    secp: &Secp256k1,
+    helpers: &[ParticipantId],
+    recipient: ParticipantId,
+    received: &BTreeMap<ParticipantId, Round2Package>,
+) -> Result<SecretKey> {
+    let mut values = helpers.iter().map(|&helper| {
+        received
+            .get(&helper)
+            .filter(|p| p.dealer() == helper && p.recipient() == recipient)
+            .map(Round2Package::share)
+            .ok_or(Error::MissingMessage {
+                participant: helper,
+            })
+    });
+
+    let mut sum = values.next().ok_or(Error::NoParticipants)??.clone();
+    for value in values {
+        sum.add_assign(secp, value?)?;
+    }
+    Ok(sum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::split;
+
+    /// Run steps 1 and 2 and return what the target receives
+    fn help(
+        secp: &Secp256k1,
+        keys: &[&KeyPackage],
+        target: ParticipantId,
+    ) -> BTreeMap<ParticipantId, Round2Package> {
+        let helpers: Vec<_> = keys.iter().map(|k| k.participant()).collect();
+        let dealt: Vec<_> = keys
+            .iter()
+            .map(|k| repair_step1(secp, &helpers, target, k).unwrap())
+            .collect();
+        helpers
+            .iter()
+            .map(|&helper| {
+                let parts = dealt
+                    .iter()
+                    .map(|parts| (parts[&helper].dealer(), parts[&helper].clone()))
+                    .collect();
+                let sum = repair_step2(secp, &helpers, helper, target, &parts).unwrap();
+                (helper, sum)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_repair_rebuilds_original_share() {
+        let secp = Secp256k1::new();
+        let (keys, public_keys) = split(&secp, 3, 5);
+        let lost = ParticipantId::new(2);
+
+        let helpers = [&keys[0], &keys[2], &keys[4]];
+        let ids: Vec<_> = helpers.iter().map(|k| k.participant()).collect();
+        let sums = help(&secp, &helpers, lost);
+        let repaired = repair_step3(&secp, &ids, lost, &sums, &public_keys).unwrap();
+
+        assert_eq!(repaired, keys[1]);
+    }
+
+    #[test]
+    fn test_tampered_repair_detected() {
+        let secp = Secp256k1::new();
+        let (keys, public_keys) = split(&secp, 2, 3);
+        let lost = ParticipantId::new(3);
+        let ids = [ParticipantId::new(1), ParticipantId::new(2)];
+
+        let mut sums = help(&secp, &[&keys[0], &keys[1]], lost);
+        sums.insert(
+            ids[1],
+            Round2Package::new(ids[1], lost, SecretKey::new(&secp, &mut thread_rng())),
+        );
+        assert_eq!(
+            repair_step3(&secp, &ids, lost, &sums, &public_keys).unwrap_err(),
+            Error::KeyMismatch { participant: lost }
+        );
+
+        sums.remove(&ids[0]);
+        assert_eq!(
+            repair_step3(&secp, &ids, lost, &sums, &public_keys).unwrap_err(),
+            Error::MissingMessage {
+                participant: ids[0]
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid_helper_sets() {
+        let secp = Secp256k1::new();
+        let (keys, _) = split(&secp, 2, 3);
+        let (p1, p2, p3) = (
+            ParticipantId::new(1),
+            ParticipantId::new(2),
+            ParticipantId::new(3),
+        );
+
+        assert_eq!(
+            repair_step1(&secp, &[p1], p3, &keys[0]).unwrap_err(),
+            Error::NotEnoughSigners {
+                threshold: 2,
+                actual: 1
+            }
+        );
+        assert_eq!(
+            repair_step1(&secp, &[p1, p2], p2, &keys[0]).unwrap_err(),
+            Error::DuplicateParticipant { participant: p2 }
+        );
+        assert_eq!(
+            repair_step1(&secp, &[p2, p3], p1, &keys[0]).unwrap_err(),
+            Error::UnknownParticipant { participant: p1 }
+        );
+    }
+}
@@ -0,0 +1,451 @@
+//! Resharing a threshold key to a new committee or threshold
+//!
+//! At least `t` members of the old `(t, n)` group each re-deal their
+//! Lagrange-weighted share to the members of the new `(t', n')` group. The
+//! new members sum what they receive and end up with shares of the same
+//! secret under the new threshold, so the group public key does not change.
+//! New members can check every dealer against the old public keys without
+//! trusting it.
+//!
+//! # Algorithm
+//! ```text
+//! Old signer i in S:  w_i = lambda_i(S) * s_i
+//!                     h_i random of degree t'-1 with h_i(0) = w_i
+//!                     broadcast H_i = commitments to h_i, send h_i(j) to new member j
+//! New member j:       check H_i0 == lambda_i(S) * Y_i   (old verification share)
+//!                     check h_i(j)*G against H_i
+//!                     s_j' = sum_i h_i(j),  H = sum_i H_i,  Y = H_0
+//! ```
+//!
+//! Old members are retired by the resharing itself: [`deal`] consumes the
+//! dealer's key package, and the new public keys carry fresh verification
+//! shares, so an old share is rejected under the new keys even when its
+//! holder stays on the new committee. Copies made before dealing, and the
+//! packages of old members who did not deal, must still be deleted by
+//! their holders.
+
+use std::collections::BTreeMap;
+
+use secp256k1zkp::{PublicKey, Secp256k1};
+use serde::{Deserialize, Serialize};
+
+use crate::dkg::Round2Package;
+use crate::error::{Error, Result};
+use crate::frost::{KeyPackage, PublicKeyPackage};
+use crate::scalar::{self, lagrange_coefficient};
+use crate::types::ParticipantId;
+use crate::vss::{check_threshold, Polynomial, VssCommitment};
+
+/// Agreed parameters of a resharing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReshareConfig {
+    /// Old group's public keys
+    old_public_keys: PublicKeyPackage,
+
+    /// Old members who re-deal their shares
+    old_signers: Vec<ParticipantId>,
+
+    /// New threshold `t'`
+    new_min_signers: usize,
+
+    /// New committee
+    new_participants: Vec<ParticipantId>,
+}
+
+impl ReshareConfig {
+    /// Create and check resharing parameters
+    ///
+    /// # Arguments
+    /// * `old_public_keys` - Old group's public keys
+    /// * `old_signers` - At least `t` old members who will re-deal
+    /// * `new_min_signers` - New threshold `t'`
+    /// * `new_participants` - New committee of `n'` members
+    ///
+    /// # Errors
+    /// Returns `Error::UnknownParticipant` if an old signer is not an old member
+    /// Returns `Error::DuplicateParticipant` if an old signer is listed twice
+    /// Returns `Error::NotEnoughSigners` if fewer than `t` old members re-deal
+    /// Returns `Error::InvalidThreshold` unless `1 <= t' <= n'`
+    pub fn new(
+        old_public_keys: PublicKeyPackage,
+        old_signers: Vec<ParticipantId>,
+        new_min_signers: usize,
+        new_participants: Vec<ParticipantId>,
+    ) -> Result<Self> {
+        for (i, &signer) in old_signers.iter().enumerate() {
+            old_public_keys.verifying_share(signer)?;
+            if old_signers[..i].contains(&signer) {
+                return Err(Error::DuplicateParticipant {
+                    participant: signer,
+                });
+            }
+        }
+        if old_signers.len() < old_public_keys.min_signers() {
+            return Err(Error::NotEnoughSigners {
+                threshold: old_public_keys.min_signers(),
+                actual: old_signers.len(),
+            });
+        }
+        check_threshold(new_min_signers, &new_participants)?;
+
+        Ok(Self {
+            old_public_keys,
+            old_signers,
+            new_min_signers,
+            new_participants,
+        })
+    }
+
+    /// Get the old members who re-deal
+    pub fn old_signers(&self) -> &[ParticipantId] {
+        &self.old_signers
+    }
+
+    /// Get the new threshold
+    pub const fn new_min_signers(&self) -> usize {
+        self.new_min_signers
+    }
+
+    /// Get the new committee
+    pub fn new_participants(&self) -> &[ParticipantId] {
+        &self.new_participants
+    }
+}
+
+/// An old signer's broadcast commitment to its re-dealt share
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReshareCommitment {
+    /// Old signer
+    dealer: ParticipantId,
+
+    /// Commitments to `h`, with `h(0) = lambda * s`
+    commitment: VssCommitment,
+}
+
+impl ReshareCommitment {
+    /// Get the old signer
+    pub const fn dealer(&self) -> ParticipantId {
+        self.dealer
+    }
+
+    /// Get the commitments
+    pub const fn commitment(&self) -> &VssCommitment {
+        &self.commitment
+    }
+}
+
+/// Re-deal an old member's share to the new committee, retiring the old one
+///
+/// # Arguments
+/// * `secp` - Secp256k1 context
+/// * `config` - Agreed resharing parameters
+/// * `key_package` - Old member's key material, consumed
+///
+/// # Returns
+/// The broadcast commitment and a private sub-share for every new member
+///
+/// # Errors
+/// Returns `Error::UnknownParticipant` if the member is not an old signer
+pub fn deal(
+    secp: &Secp256k1,
+    config: &ReshareConfig,
+    key_package: KeyPackage,
+) -> Result<(ReshareCommitment, BTreeMap<ParticipantId, Round2Package>)> {
+    let dealer = key_package.participant();
+    let lambda = lagrange_coefficient(secp, dealer, &config.old_signers)?;
+    let weighted = scalar::mul(secp, &key_package.into_signing_share(), &lambda)?;
+
+    let polynomial = Polynomial::random(secp, weighted, config.new_min_signers);
+    let commitment = ReshareCommitment {
+        dealer,
+        commitment: polynomial.commit(secp)?,
+    };
+    let shares = config
+        .new_participants
+        .iter()
+        .map(|&recipient| {
+            let share = polynomial.evaluate(secp, recipient)?;
+            Ok((recipient, Round2Package::new(dealer, recipient, share)))
+        })
+        .collect::<Result<_>>()?;
+    Ok((commitment, shares))
+}
+
+/// Combine the sub-shares received by a new member
+///
+/// # Arguments
+/// * `secp` - Secp256k1 context
+/// * `config` - Agreed resharing parameters
+/// * `participant` - New member's ID
+/// * `commitments` - Every old signer's commitment
+/// * `shares` - Sub-shares received, keyed by old signer
+///
+/// # Returns
+/// The new member's key package and the new committee's public keys
+///
+/// # Errors
+/// Returns `Error::UnknownParticipant` if `participant` is not a new member
+/// or a commitment comes from someone other than an old signer
+/// Returns `Error::MissingMessage` if an old signer's commitment is missing
+/// Returns `Error::InvalidCommitment` if a commitment has the wrong degree or
+/// does not commit to the signer's weighted old share
+/// Returns `Error::InvalidDealerShare` if a sub-share is missing or invalid
+pub fn finish(
+    secp: &Secp256k1,
+    config: &ReshareConfig,
+    participant: ParticipantId,
+    commitments: &[ReshareCommitment],
+    shares: &BTreeMap<ParticipantId, Round2Package>,
+) -> Result<(KeyPackage, PublicKeyPackage)> {
+    if !config.new_participants.contains(&participant) {
+        return Err(Error::UnknownParticipant { participant });
+    }
+    if let Some(stranger) = commitments
+        .iter()
+        .find(|c| !config.old_signers.contains(&c.dealer))
+    {
+        return Err(Error::UnknownParticipant {
+            participant: stranger.dealer,
+        });
+    }
+
+    let mut verified = Vec::with_capacity(config.old_signers.len());
+    let mut sub_shares = Vec::with_capacity(config.old_signers.len());
+    for &dealer in &config.old_signers {
+        let commitment =
+            commitments
+                .iter()
+                .find(|c| c.dealer == dealer)
+                .ok_or(Error::MissingMessage {
+                    participant: dealer,
+                })?;
+
+        // The dealer must re-deal exactly its weighted old share
+        let lambda = lagrange_coefficient(secp, dealer, &config.old_signers)?;
+        let expected = scalar::mul_point(
+            secp,
+            config.old_public_keys.verifying_share(dealer)?,
+            &lambda,
+        )?;
+        if commitment.commitment.threshold() != config.new_min_signers
+            || commitment.commitment.group_public_key()? != expected
+        {
+            return Err(Error::InvalidCommitment {
+                participant: dealer,
+            });
+        }
+
+        let expected_share = commitment.commitment.verifying_share(secp, participant)?;
+        let share = shares
+            .get(&dealer)
+            .filter(|p| p.recipient() == participant)
+            .map(Round2Package::share)
+            .filter(|s| PublicKey::from_secret_key(secp, s).ok() == Some(expected_share))
+            .ok_or(Error::InvalidDealerShare {
+                dealer,
+                participant,
+            })?;
+
+        verified.push(&commitment.commitment);
+        sub_shares.push(share);
+    }
+
+    let mut signing_share = sub_shares[0].clone();
+    for share in &sub_shares[1..] {
+        signing_share.add_assign(secp, share)?;
+    }
+
+    let group_commitment = VssCommitment::sum(secp, &verified)?;
+    let group_public_key = group_commitment.group_public_key()?;
+    if group_public_key != *config.old_public_keys.group_public_key() {
+        return Err(Error::InvalidCommitment {
+            participant: config.old_signers[0],
+        });
+    }
+    let verifying_shares = config
+        .new_participants
+        .iter()
+        .map(|&member| Ok((member, group_commitment.verifying_share(secp, member)?)))
+        .collect::<Result<BTreeMap<_, _>>>()?;
+
+    let key_package = KeyPackage::new(
+        secp,
+        participant,
+        signing_share,
+        group_public_key,
+        config.new_min_signers,
+    )?;
+    let public_keys =
+        PublicKeyPackage::new(verifying_shares, group_public_key, config.new_min_signers);
+    Ok((key_package, public_keys))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{split, try_sign};
+    use rand::thread_rng;
+    use secp256k1zkp::SecretKey;
+
+    fn ids(range: std::ops::RangeInclusive<u32>) -> Vec<ParticipantId> {
+        range.map(ParticipantId::new).collect()
+    }
+
+    #[test]
+    fn test_reshare_to_new_committee_and_threshold() {
+        let secp = Secp256k1::new();
+        let (old_keys, old_public) = split(&secp, 2, 3);
+
+        // Old members 1 and 3 move control to a 3-of-4 committee 11..=14
+        let config = ReshareConfig::new(
+            old_public.clone(),
+            vec![ParticipantId::new(1), ParticipantId::new(3)],
+            3,
+            ids(11..=14),
+        )
+        .unwrap();
+        let dealt: Vec<_> = [old_keys[0].clone(), old_keys[2].clone()]
+            .into_iter()
+            .map(|k| deal(&secp, &config, k).unwrap())
+            .collect();
+        let commitments: Vec<_> = dealt.iter().map(|(c, _)| c.clone()).collect();
+
+        let results: Vec<_> = config
+            .new_participants()
+            .iter()
+            .map(|&member| {
+                let received = dealt
+                    .iter()
+                    .map(|(c, shares)| (c.dealer(), shares[&member].clone()))
+                    .collect();
+                finish(&secp, &config, member, &commitments, &received).unwrap()
+            })
+            .collect();
+
+        let new_public = &results[0].1;
+        assert_eq!(new_public.group_public_key(), old_public.group_public_key());
+        assert!(results.iter().all(|(_, p)| p == new_public));
+
+        let new_keys: Vec<_> = results.iter().map(|(k, _)| k).collect();
+        assert!(try_sign(&secp, &new_keys[1..], new_public).is_ok());
+        assert_eq!(
+            try_sign(&secp, &new_keys[..2], new_public).unwrap_err(),
+            Error::NotEnoughSigners {
+                threshold: 3,
+                actual: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_old_share_cannot_reenter() {
+        let secp = Secp256k1::new();
+        let (old_keys, old_public) = split(&secp, 2, 3);
+        let kept = old_keys[0].clone();
+
+        // The same three members move to 2-of-3 with fresh shares
+        let config = ReshareConfig::new(old_public, ids(1..=2), 2, ids(1..=3)).unwrap();
+        let dealt: Vec<_> = old_keys
+            .into_iter()
+            .take(2)
+            .map(|k| deal(&secp, &config, k).unwrap())
+            .collect();
+        let commitments: Vec<_> = dealt.iter().map(|(c, _)| c.clone()).collect();
+        let new_keys: Vec<_> = config
+            .new_participants()
+            .iter()
+            .map(|&member| {
+                let received = dealt
+                    .iter()
+                    .map(|(c, shares)| (c.dealer(), shares[&member].clone()))
+                    .collect();
+                finish(&secp, &config, member, &commitments, &received).unwrap()
+            })
+            .collect();
+        let new_public = &new_keys[0].1;
+
+        // Member 1's pre-reshare share no longer matches its verification share
+        assert_ne!(kept.signing_share(), new_keys[0].0.signing_share());
+        assert_eq!(
+            try_sign(&secp, &[&kept, &new_keys[1].0], new_public).unwrap_err(),
+            Error::InvalidSignatureShare {
+                participant: ParticipantId::new(1)
+            }
+        );
+        assert!(try_sign(&secp, &[&new_keys[0].0, &new_keys[1].0], new_public).is_ok());
+    }
+
+    #[test]
+    fn test_dishonest_dealer_rejected() {
+        let secp = Secp256k1::new();
+        let (old_keys, old_public) = split(&secp, 2, 3);
+        let config = ReshareConfig::new(old_public, ids(1..=2), 2, ids(1..=3)).unwrap();
+        let (honest, honest_shares) = deal(&secp, &config, old_keys[0].clone()).unwrap();
+
+        // Member 2 re-deals member 3's share instead of its own
+        let impostor = KeyPackage::new(
+            &secp,
+            ParticipantId::new(2),
+            old_keys[2].signing_share().clone(),
+            *old_keys[2].group_public_key(),
+            2,
+        )
+        .unwrap();
+        let (forged, forged_shares) = deal(&secp, &config, impostor).unwrap();
+
+        let p1 = ParticipantId::new(1);
+        let received = BTreeMap::from([
+            (honest.dealer(), honest_shares[&p1].clone()),
+            (forged.dealer(), forged_shares[&p1].clone()),
+        ]);
+        assert_eq!(
+            finish(&secp, &config, p1, &[honest.clone(), forged], &received).unwrap_err(),
+            Error::InvalidCommitment {
+                participant: ParticipantId::new(2)
+            }
+        );
+
+        // A sub-share that does not match the dealer's commitments
+        let (genuine, _) = deal(&secp, &config, old_keys[1].clone()).unwrap();
+        let mut tampered = received;
+        tampered.insert(
+            genuine.dealer(),
+            Round2Package::new(
+                genuine.dealer(),
+                p1,
+                SecretKey::new(&secp, &mut thread_rng()),
+            ),
+        );
+        assert_eq!(
+            finish(&secp, &config, p1, &[honest, genuine], &tampered).unwrap_err(),
+            Error::InvalidDealerShare {
+                dealer: ParticipantId::new(2),
+                participant: p1
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let secp = Secp256k1::new();
+        let (_, old_public) = split(&secp, 2, 3);
+
+        assert_eq!(
+            ReshareConfig::new(old_public.clone(), ids(1..=1), 2, ids(1..=3)).unwrap_err(),
+            Error::NotEnoughSigners {
+                threshold: 2,
+                actual: 1
+            }
+        );
+        assert_eq!(
+            ReshareConfig::new(old_public.clone(), ids(3..=4), 2, ids(1..=3)).unwrap_err(),
+            Error::UnknownParticipant {
+                participant: ParticipantId::new(4)
+            }
+        );
+        assert!(matches!(
+            ReshareConfig::new(old_public, ids(1..=2), 4, ids(1..=3)),
+            Err(Error::InvalidThreshold { .. })
+        ));
+    }
+}
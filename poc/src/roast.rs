@@ -0,0 +1,394 @@
+//! ROAST: robust asynchronous coordination of FROST signing
+//!
+//! A fixed set of `t` signers stalls as soon as one of them is offline or
+//! malicious. The ROAST coordinator instead keeps a queue of responsive
+//! signers, each holding a fresh nonce commitment, and starts a new signing
+//! attempt whenever `t` of them are ready. Attempts run concurrently; a signer
+//! that answers one attempt is immediately ready for the next, and a signer
+//! whose share fails to verify is marked malicious and ignored from then on.
+//!
+//! With at most `n - t` faulty signers, the coordinator finishes once `t`
+//! honest signers respond, whatever the message timing: each attempt either
+//! completes or contains a faulty signer that never frees up again, so at most
+//! `n - t + 1` attempts are started.
+//!
+//! # Algorithm
+//! ```text
+//! On response (i, share?, next commitment):
+//!   if i is malicious: ignore
+//!   if i was in attempt a: verify share; invalid -> mark i malicious, stop
+//!                          store share; a complete -> aggregate, finish
+//!   add (i, next commitment) to ready queue
+//!   if |ready| >= t: start attempt with the first t ready signers
+//! ```
+//!
+//! The coordinator performs no I/O: it consumes [`Response`]s and emits
+//! [`Event`]s, so any transport, or a simulated network, can drive it.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use secp256k1zkp::{Secp256k1, Signature};
+
+use crate::error::{Error, Result};
+use crate::frost::{
+    self, KeyPackage, PublicKeyPackage, SignatureShare, SigningCommitments, SigningNonces,
+    SigningPackage,
+};
+use crate::types::ParticipantId;
+
+/// A signer's message to the coordinator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Response {
+    /// Sender
+    participant: ParticipantId,
+
+    /// Share for the signer's current attempt; `None` when first joining
+    share: Option<SignatureShare>,
+
+    /// Fresh commitments for the signer's next attempt
+    next: SigningCommitments,
+}
+
+impl Response {
+    /// Get the sender
+    pub const fn participant(&self) -> ParticipantId {
+        self.participant
+    }
+
+    /// Get the share for the signer's current attempt
+    pub const fn share(&self) -> Option<&SignatureShare> {
+        self.share.as_ref()
+    }
+
+    /// Get the commitments for the signer's next attempt
+    pub const fn next(&self) -> &SigningCommitments {
+        &self.next
+    }
+}
+
+/// What the coordinator asks the transport to do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// Send `package` to every signer in it
+    Started {
+        /// Attempt number
+        attempt: usize,
+        /// Signing package of the attempt
+        package: SigningPackage,
+    },
+    /// Signing finished with the group signature
+    Finished(Signature),
+}
+
+/// One signing attempt
+#[derive(Debug, Clone)]
+struct Attempt {
+    /// Signing package sent to the attempt's signers
+    package: SigningPackage,
+
+    /// Valid shares received so far
+    shares: Vec<SignatureShare>,
+}
+
+/// ROAST coordinator for one message
+#[derive(Debug, Clone)]
+pub struct Coordinator {
+    /// Group's public keys
+    public_keys: PublicKeyPackage,
+
+    /// Message being signed
+    message: [u8; 32],
+
+    /// Responsive signers with fresh commitments, in arrival order
+    ready: Vec<SigningCommitments>,
+
+    /// Signers whose shares failed to verify
+    malicious: BTreeSet<ParticipantId>,
+
+    /// Every attempt started so far
+    attempts: Vec<Attempt>,
+
+    /// Open attempt of each busy signer
+    assigned: BTreeMap<ParticipantId, usize>,
+
+    /// Group signature, once finished
+    signature: Option<Signature>,
+}
+
+impl Coordinator {
+    /// Create a coordinator for signing `message`
+    pub const fn new(public_keys: PublicKeyPackage, message: [u8; 32]) -> Self {
+        Self {
+            public_keys,
+            message,
+            ready: Vec::new(),
+            malicious: BTreeSet::new(),
+            attempts: Vec::new(),
+            assigned: BTreeMap::new(),
+            signature: None,
+        }
+    }
+
+    /// Get the signers marked malicious so far
+    pub const fn malicious(&self) -> &BTreeSet<ParticipantId> {
+        &self.malicious
+    }
+
+    /// Get the number of attempts started so far
+    pub const fn attempts(&self) -> usize {
+        self.attempts.len()
+    }
+
+    /// Get the group signature, once finished
+    pub const fn signature(&self) -> Option<&Signature> {
+        self.signature.as_ref()
+    }
+
+    /// Process a signer's response
+    ///
+    /// Responses from malicious signers, unsolicited shares and anything
+    /// arriving after the signature is done are ignored.
+    ///
+    /// # Returns
+    /// Attempts to start and, once done, the group signature
+    ///
+    /// # Errors
+    /// Returns `Error::UnknownParticipant` if the sender is not a group member
+    /// Returns `Error::NotEnoughSigners` if so many signers are malicious that
+    /// fewer than `t` honest ones remain
+    pub fn receive(&mut self, secp: &Secp256k1, response: &Response) -> Result<Vec<Event>> {
+        let participant = response.participant;
+        self.public_keys.verifying_share(participant)?;
+        if self.signature.is_some() || self.malicious.contains(&participant) {
+            return Ok(Vec::new());
+        }
+        if response.next.participant() != participant {
+            return self.mark_malicious(participant);
+        }
+
+        let mut events = Vec::new();
+        if let Some(index) = self.assigned.remove(&participant) {
+            let attempt = &mut self.attempts[index];
+            let valid = response.share.filter(|share| {
+                share.participant() == participant
+                    && frost::verify_signature_share(
+                        secp,
+                        &attempt.package,
+                        share,
+                        &self.public_keys,
+                    )
+                    .is_ok()
+            });
+            let Some(share) = valid else {
+                return self.mark_malicious(participant);
+            };
+
+            attempt.shares.push(share);
+            if attempt.shares.len() == attempt.package.commitments().len() {
+                let signature =
+                    frost::aggregate(secp, &attempt.package, &attempt.shares, &self.public_keys)?;
+                self.signature = Some(signature);
+                return Ok(vec![Event::Finished(signature)]);
+            }
+        } else if response.share.is_some() {
+            return Ok(events);
+        }
+
+        self.ready.retain(|c| c.participant() != participant);
+        self.ready.push(response.next);
+        let min_signers = self.public_keys.min_signers();
+        if self.ready.len() >= min_signers {
+            let commitments: Vec<_> = self.ready.drain(..min_signers).collect();
+            let package = SigningPackage::new(self.message, commitments)?;
+            let attempt = self.attempts.len();
+            for signer in package.signers() {
+                self.assigned.insert(signer, attempt);
+            }
+            self.attempts.push(Attempt {
+                package: package.clone(),
+                shares: Vec::new(),
+            });
+            events.push(Event::Started { attempt, package });
+        }
+        Ok(events)
+    }
+
+    fn mark_malicious(&mut self, participant: ParticipantId) -> Result<Vec<Event>> {
+        self.malicious.insert(participant);
+        self.assigned.remove(&participant);
+        self.ready.retain(|c| c.participant() != participant);
+
+        let honest = self.public_keys.verifying_shares().len() - self.malicious.len();
+        if honest < self.public_keys.min_signers() {
+            return Err(Error::NotEnoughSigners {
+                threshold: self.public_keys.min_signers(),
+                actual: honest,
+            });
+        }
+        Ok(Vec::new())
+    }
+}
+
+/// Signer side of ROAST: always holds fresh nonces for the next attempt
+#[derive(Debug)]
+pub struct Signer {
+    /// Signer's key material
+    key_package: KeyPackage,
+
+    /// Nonces committed to in the signer's last response
+    nonces: SigningNonces,
+}
+
+impl Signer {
+    /// Create a signer and its first response announcing readiness
+    ///
+    /// # Errors
+    /// Returns `Error::Crypto` if nonce generation fails
+    pub fn new(secp: &Secp256k1, key_package: KeyPackage) -> Result<(Self, Response)> {
+        let nonces = frost::commit(secp, &key_package)?;
+        let response = Response {
+            participant: key_package.participant(),
+            share: None,
+            next: *nonces.commitments(),
+        };
+        Ok((
+            Self {
+                key_package,
+                nonces,
+            },
+            response,
+        ))
+    }
+
+    /// Sign an attempt's package and move on to fresh nonces
+    ///
+    /// The nonces of the last response are spent even if signing fails.
+    ///
+    /// # Errors
+    /// Returns the errors of [`frost::sign`]
+    pub fn sign(&mut self, secp: &Secp256k1, package: &SigningPackage) -> Result<Response> {
+        let next = frost::commit(secp, &self.key_package)?;
+        let nonces = std::mem::replace(&mut self.nonces, next);
+        let share = frost::sign(secp, package, nonces, &self.key_package)?;
+        Ok(Response {
+            participant: self.key_package.participant(),
+            share: Some(share),
+            next: *self.nonces.commitments(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::split;
+
+    /// How a simulated signer behaves
+    #[derive(Clone, Copy)]
+    enum Behaviour {
+        /// Answers after the given delay
+        Honest(u64),
+        /// Never answers
+        Offline,
+        /// Answers after the given delay with a share for another message
+        Malicious(u64),
+    }
+
+    /// Drive a coordinator over a simulated network until it finishes
+    fn simulate(secp: &Secp256k1, t: usize, behaviour: &[Behaviour]) -> Result<Coordinator> {
+        let n = u32::try_from(behaviour.len()).unwrap();
+        let (keys, public_keys) = split(secp, t, n);
+
+        // Deliveries to the coordinator, ordered by (time, sequence number)
+        let mut queue = BTreeMap::new();
+        let mut seq = 0..;
+        let mut signers = BTreeMap::new();
+        for (key, &behaviour) in keys.into_iter().zip(behaviour) {
+            let id = key.participant();
+            let (signer, hello) = Signer::new(secp, key)?;
+            if !matches!(behaviour, Behaviour::Offline) {
+                queue.insert((0, seq.next()), hello);
+            }
+            signers.insert(id, (signer, behaviour));
+        }
+
+        let mut coordinator = Coordinator::new(public_keys, [4u8; 32]);
+        while let Some(((now, _), response)) = queue.pop_first() {
+            for event in coordinator.receive(secp, &response)? {
+                let Event::Started { package, .. } = event else {
+                    return Ok(coordinator);
+                };
+                for id in package.signers() {
+                    let (signer, behaviour) = signers.get_mut(&id).unwrap();
+                    let (delay, package) = match *behaviour {
+                        Behaviour::Honest(delay) => (delay, package.clone()),
+                        Behaviour::Malicious(delay) => (
+                            delay,
+                            SigningPackage::new([0xee; 32], package.commitments().to_vec())?,
+                        ),
+                        Behaviour::Offline => continue,
+                    };
+                    queue.insert((now + delay, seq.next()), signer.sign(secp, &package)?);
+                }
+            }
+        }
+        Ok(coordinator)
+    }
+
+    #[test]
+    fn test_finishes_despite_offline_and_malicious_signers() {
+        let secp = Secp256k1::new();
+        let coordinator = simulate(
+            &secp,
+            3,
+            &[
+                Behaviour::Offline,
+                Behaviour::Malicious(1),
+                Behaviour::Honest(5),
+                Behaviour::Honest(2),
+                Behaviour::Honest(9),
+            ],
+        )
+        .unwrap();
+
+        assert!(coordinator.signature().is_some());
+        assert_eq!(
+            coordinator.malicious(),
+            &BTreeSet::from([ParticipantId::new(2)])
+        );
+        assert!(coordinator.attempts() <= 5 - 3 + 1);
+    }
+
+    #[test]
+    fn test_finishes_under_varied_timing() {
+        let secp = Secp256k1::new();
+        for delays in [[1, 1, 1, 1], [1, 50, 3, 7], [40, 30, 20, 10]] {
+            let mut behaviour: Vec<_> = delays.into_iter().map(Behaviour::Honest).collect();
+            behaviour[1] = Behaviour::Offline;
+            let coordinator = simulate(&secp, 3, &behaviour).unwrap();
+            assert!(coordinator.signature().is_some());
+            assert!(coordinator.malicious().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_too_many_malicious_signers() {
+        let secp = Secp256k1::new();
+        assert_eq!(
+            simulate(
+                &secp,
+                2,
+                &[
+                    Behaviour::Malicious(1),
+                    Behaviour::Malicious(2),
+                    Behaviour::Honest(3),
+                ],
+            )
+            .unwrap_err(),
+            Error::NotEnoughSigners {
+                threshold: 2,
+                actual: 1
+            }
+        );
+    }
+}
@@ -0,0 +1,490 @@
+//! Participant-set rotation
+//!
+//! When someone leaves the group, or joins it, the group's funds have to move
+//! under a new aggregate key. A [`GroupRecord`] tracks the current participant
+//! set and every [`SharedOutput`] it holds. Rotating derives the new set's
+//! aggregate key and builds a sweep spending every recorded output into one
+//! output under that key. The old set co-signs the sweep; members who only
+//! join contribute their share of the new key to balance it. Once the sweep is
+//! on chain, the record moves to the new set and its output.
+//!
+//! # Algorithm
+//! ```text
+//! Old:    C_j = v_j*H + X_old                 for every recorded output j
+//! New:    X_new = sum(a'_i * K'_i)            (aggregate of the new set)
+//!         C' = (sum(v_j) - fee)*H + X_new
+//! Sweep:  C_1..C_n -> C', co-signed over one excess key per member
+//!         b_i = a'_i * k'_i - n * a_i * k_i   (each term only if a member)
+//! Record: participants = new set, outputs = [C'], generation += 1
+//! ```
+//!
+//! The new output needs a range proof, which has to come from a joint range
+//! proof protocol among the new set; this crate does not provide one.
+
+use secp256k1zkp::pedersen::RangeProof;
+use secp256k1zkp::{ContextFlag, Secp256k1, SecretKey};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::participant::Participant;
+use crate::session::Session;
+use crate::spend::{SharedOutput, SpendBuilder};
+use crate::transaction::{KernelFeatures, Output, OutputFeatures, Transaction};
+use crate::validation::validate;
+
+/// A group's participant set and the outputs it holds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupRecord {
+    /// Number of rotations so far
+    generation: u64,
+
+    /// Current participant set
+    participants: Vec<Participant>,
+
+    /// Outputs held under the set's aggregate key
+    outputs: Vec<SharedOutput>,
+}
+
+impl GroupRecord {
+    /// Start a record for a participant set holding nothing yet
+    ///
+    /// # Errors
+    /// Returns `Error::NoParticipants` if the set is empty
+    pub fn new(participants: Vec<Participant>) -> Result<Self> {
+        if participants.is_empty() {
+            return Err(Error::NoParticipants);
+        }
+        Ok(Self {
+            generation: 0,
+            participants,
+            outputs: Vec::new(),
+        })
+    }
+
+    /// Get the number of rotations so far
+    pub const fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Get the current participant set
+    pub fn participants(&self) -> &[Participant] {
+        &self.participants
+    }
+
+    /// Get the outputs the group holds
+    pub fn outputs(&self) -> &[SharedOutput] {
+        &self.outputs
+    }
+
+    /// Get the total value the group holds
+    pub fn value(&self) -> u64 {
+        self.outputs.iter().map(SharedOutput::value).sum()
+    }
+
+    /// Session over the current set's keys
+    pub fn session(&self) -> Session {
+        Session::new(self.participants.clone())
+    }
+
+    /// Record an output held under the current aggregate key
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidRotation` if the output is not the group's
+    /// Returns `Error::Crypto` if the expected commitment cannot be created
+    pub fn record_output(&mut self, output: SharedOutput) -> Result<()> {
+        if SharedOutput::from_session(&self.session(), output.value())? != output {
+            return Err(Error::InvalidRotation(
+                "output is not held under the group's key".into(),
+            ));
+        }
+        self.outputs.push(output);
+        Ok(())
+    }
+
+    /// Start moving every recorded output to a new participant set
+    ///
+    /// # Arguments
+    /// * `participants` - New participant set
+    /// * `fee` - Fee of the sweep
+    ///
+    /// # Errors
+    /// Returns `Error::NoParticipants` if the new set is empty
+    /// Returns `Error::InvalidRotation` if the group holds nothing to cover the
+    /// fee or the new set has the same aggregate key
+    /// Returns `Error::Crypto` if the new commitment cannot be created
+    pub fn rotate(&self, participants: Vec<Participant>, fee: u64) -> Result<Rotation> {
+        if participants.is_empty() {
+            return Err(Error::NoParticipants);
+        }
+        if self.value() <= fee {
+            return Err(Error::InvalidRotation(
+                "group holds too little to cover the sweep fee".into(),
+            ));
+        }
+        let new_session = Session::new(participants.clone());
+        if new_session.aggregate_pubkeys()? == self.session().aggregate_pubkeys()? {
+            return Err(Error::InvalidRotation(
+                "new set has the same aggregate key".into(),
+            ));
+        }
+
+        let (first, rest) = self
+            .outputs
+            .split_first()
+            .ok_or_else(|| Error::InvalidRotation("group holds nothing to sweep".into()))?;
+        let sweep = rest.iter().fold(
+            SpendBuilder::new(*first, KernelFeatures::Plain { fee }),
+            |sweep, output| sweep.input(*output),
+        );
+        Ok(Rotation {
+            generation: self.generation + 1,
+            old_participants: self.participants.clone(),
+            inputs: self.outputs.clone(),
+            participants,
+            output: SharedOutput::from_session(&new_session, self.value() - fee)?,
+            sweep,
+        })
+    }
+
+    /// Move the record to the new set once the sweep is final
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidRotation` if the rotation does not start from
+    /// this record, or the sweep does not spend every recorded output into
+    /// the new output
+    /// Returns `Error::ValidationFailed` if the sweep does not validate
+    pub fn complete(&mut self, rotation: &Rotation, sweep: &Transaction) -> Result<()> {
+        let old_key = Session::new(rotation.old_participants.clone()).aggregate_pubkeys()?;
+        if rotation.generation != self.generation + 1
+            || old_key != self.session().aggregate_pubkeys()?
+            || rotation.inputs != self.outputs
+        {
+            return Err(Error::InvalidRotation(
+                "rotation does not start from this record".into(),
+            ));
+        }
+        let spends_all = sweep.inputs().len() == self.outputs.len()
+            && self
+                .outputs
+                .iter()
+                .all(|o| sweep.inputs().contains(&o.input()));
+        let creates = sweep
+            .outputs()
+            .iter()
+            .any(|o| o.commitment() == rotation.output.commitment());
+        if !spends_all || !creates {
+            return Err(Error::InvalidRotation(
+                "sweep does not move every output to the new set".into(),
+            ));
+        }
+        validate(&Secp256k1::with_caps(ContextFlag::Commit), sweep).into_result()?;
+
+        self.generation = rotation.generation;
+        self.participants.clone_from(&rotation.participants);
+        self.outputs = vec![rotation.output];
+        Ok(())
+    }
+
+    /// Serialize the record
+    ///
+    /// # Errors
+    /// Returns `Error::Serialization` if serialization fails
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Restore a persisted record
+    ///
+    /// # Errors
+    /// Returns `Error::Serialization` if the JSON is malformed
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+/// A pending move of a group's outputs to a new participant set
+#[derive(Debug, Clone)]
+pub struct Rotation {
+    /// Generation of the record after the rotation
+    generation: u64,
+
+    /// Participant set the outputs move away from
+    old_participants: Vec<Participant>,
+
+    /// Outputs being swept
+    inputs: Vec<SharedOutput>,
+
+    /// Participant set the outputs move to
+    participants: Vec<Participant>,
+
+    /// Output under the new aggregate key
+    output: SharedOutput,
+
+    /// Sweep of every input, still without the new output
+    sweep: SpendBuilder,
+}
+
+impl Rotation {
+    /// Get the new participant set
+    pub fn participants(&self) -> &[Participant] {
+        &self.participants
+    }
+
+    /// Session over the new set's keys
+    pub fn new_session(&self) -> Session {
+        Session::new(self.participants.clone())
+    }
+
+    /// Get the output the sweep creates under the new aggregate key
+    pub const fn output(&self) -> &SharedOutput {
+        &self.output
+    }
+
+    /// Build the sweep around the new output's range proof
+    pub fn sweep(&self, proof: RangeProof) -> SpendBuilder {
+        self.sweep.clone().output(Output::new(
+            OutputFeatures::Plain,
+            *self.output.commitment(),
+            proof,
+        ))
+    }
+
+    /// Compute a member's sweep blinding total `b_i`
+    ///
+    /// # Arguments
+    /// * `old_key` - Member's key in the old set, if it is a member
+    /// * `new_key` - Member's key in the new set, if it is a member
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidRotation` if the member is in neither set
+    /// Returns `Error::Crypto` if the blinding factors sum to zero
+    pub fn blinding(
+        &self,
+        old_key: Option<&SecretKey>,
+        new_key: Option<&SecretKey>,
+    ) -> Result<SecretKey> {
+        if old_key.is_none() && new_key.is_none() {
+            return Err(Error::InvalidRotation(
+                "member of neither participant set".into(),
+            ));
+        }
+        let new_session = self.new_session();
+        let positive = new_key
+            .map(|k| new_session.weighted_secret_key(k))
+            .transpose()?
+            .into_iter()
+            .collect();
+        let negative = match old_key {
+            Some(k) => {
+                let share = Session::new(self.old_participants.clone()).weighted_secret_key(k)?;
+                vec![share; self.inputs.len()]
+            }
+            None => Vec::new(),
+        };
+        Ok(new_session.secp().blind_sum(positive, negative)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockGrinChain;
+
+    use crate::swap::GrinChain;
+    use crate::test_util::{finalize_spend, keyed_session};
+    use crate::types::ParticipantId;
+    use rand::thread_rng;
+    use secp256k1zkp::PublicKey;
+
+    /// Alice, Bob and Carol hold the funds; Carol leaves and Dave joins
+    struct Group {
+        secp: Secp256k1,
+        grin: MockGrinChain,
+        record: GroupRecord,
+        members: Vec<Participant>,
+        keys: Vec<SecretKey>,
+    }
+
+    impl Group {
+        fn new() -> Self {
+            let secp = Secp256k1::with_caps(ContextFlag::Commit);
+            let keys: Vec<_> = (0..4)
+                .map(|_| SecretKey::new(&secp, &mut thread_rng()))
+                .collect();
+            let members: Vec<_> = keys
+                .iter()
+                .zip(1..)
+                .map(|(k, i)| {
+                    let pk = PublicKey::from_secret_key(&secp, k).unwrap();
+                    Participant::new(ParticipantId::new(i), pk)
+                })
+                .collect();
+            let mut record = GroupRecord::new(members[..3].to_vec()).unwrap();
+            let mut grin = MockGrinChain::new();
+            for value in [300, 200] {
+                let output = SharedOutput::from_session(&record.session(), value).unwrap();
+                record.record_output(output).unwrap();
+                grin.add_output(*output.commitment());
+            }
+            Self {
+                secp,
+                grin,
+                record,
+                members,
+                keys,
+            }
+        }
+
+        fn set(&self, indices: &[usize]) -> Vec<Participant> {
+            indices.iter().map(|i| self.members[*i].clone()).collect()
+        }
+
+        /// Joint range proof stand-in for the new output
+        fn proof(&self, rotation: &Rotation) -> RangeProof {
+            let session = rotation.new_session();
+            let shares = session
+                .participants()
+                .iter()
+                .map(|p| {
+                    let i = self.members.iter().position(|m| m.id() == p.id()).unwrap();
+                    session.weighted_secret_key(&self.keys[i]).unwrap()
+                })
+                .collect();
+            let blind = self.secp.blind_sum(shares, vec![]).unwrap();
+            *Output::create(&self.secp, rotation.output().value(), &blind)
+                .unwrap()
+                .proof()
+        }
+
+        /// Co-sign the sweep among the old set and every joining member
+        fn sign_sweep(&self, rotation: &Rotation) -> Transaction {
+            let is_member =
+                |set: &[Participant], i: usize| set.iter().any(|p| p.id() == self.members[i].id());
+            let signers: Vec<_> = (0..self.members.len())
+                .filter(|i| {
+                    is_member(self.record.participants(), *i)
+                        || is_member(rotation.participants(), *i)
+                })
+                .collect();
+            let blindings: Vec<_> = signers
+                .iter()
+                .map(|i| {
+                    let key = &self.keys[*i];
+                    rotation
+                        .blinding(
+                            is_member(self.record.participants(), *i).then_some(key),
+                            is_member(rotation.participants(), *i).then_some(key),
+                        )
+                        .unwrap()
+                })
+                .collect();
+            sign_spend(
+                &self.secp,
+                &rotation.sweep(self.proof(rotation)),
+                &blindings,
+            )
+        }
+    }
+
+    /// Run a kernel session with one fresh excess key per blinding and finalize
+    fn sign_spend(
+        secp: &Secp256k1,
+        builder: &SpendBuilder,
+        blindings: &[SecretKey],
+    ) -> Transaction {
+        let n = u32::try_from(blindings.len()).unwrap();
+        let (session, excess_keys) = keyed_session(secp, n);
+        finalize_spend(&session, &excess_keys, builder, blindings).unwrap()
+    }
+
+    #[test]
+    fn test_rotation_moves_funds_to_new_set() {
+        let mut group = Group::new();
+        let rotation = group.record.rotate(group.set(&[0, 1, 3]), 2).unwrap();
+        assert_eq!(rotation.output().value(), 498);
+
+        let sweep = group.sign_sweep(&rotation);
+        group.grin.post(&sweep).unwrap();
+        group.record.complete(&rotation, &sweep).unwrap();
+        assert_eq!(group.record.generation(), 1);
+        assert_eq!(group.record.outputs(), [*rotation.output()]);
+        let ids: Vec<_> = group
+            .record
+            .participants()
+            .iter()
+            .map(Participant::id)
+            .collect();
+        assert_eq!(ids, [1, 2, 4].map(ParticipantId::new));
+
+        // Only the new set can spend the funds now
+        let new_session = group.record.session();
+        let blind = SecretKey::new(&group.secp, &mut thread_rng());
+        let output = Output::create(&group.secp, 496, &blind).unwrap();
+        let spend = SpendBuilder::new(group.record.outputs()[0], KernelFeatures::Plain { fee: 2 })
+            .output(output);
+        let blindings: Vec<_> = [0, 1, 3]
+            .iter()
+            .zip([vec![blind], vec![], vec![]])
+            .map(|(i, b)| {
+                group.record.outputs()[0]
+                    .spend_blinding(&new_session, &group.keys[*i], &b)
+                    .unwrap()
+            })
+            .collect();
+        group
+            .grin
+            .post(&sign_spend(&group.secp, &spend, &blindings))
+            .unwrap();
+        assert!(group.grin.is_unspent(output.commitment()));
+    }
+
+    #[test]
+    fn test_record_rejects_invalid_rotations() {
+        let mut group = Group::new();
+        let json = group.record.to_json().unwrap();
+        assert_eq!(
+            GroupRecord::from_json(&json).unwrap().to_json().unwrap(),
+            json
+        );
+
+        let foreign = SharedOutput::from_session(&Session::new(group.set(&[3])), 100).unwrap();
+        assert!(matches!(
+            group.record.record_output(foreign),
+            Err(Error::InvalidRotation(_))
+        ));
+        assert!(matches!(
+            group.record.rotate(group.set(&[0, 1, 2]), 2),
+            Err(Error::InvalidRotation(_))
+        ));
+        assert!(matches!(
+            group.record.rotate(group.set(&[0, 1]), 500),
+            Err(Error::InvalidRotation(_))
+        ));
+
+        let rotation = group.record.rotate(group.set(&[0, 1]), 2).unwrap();
+        assert!(matches!(
+            rotation.blinding(None, None),
+            Err(Error::InvalidRotation(_))
+        ));
+    }
+
+    #[test]
+    fn test_complete_checks_the_sweep() {
+        let mut group = Group::new();
+        let to_dave = group.record.rotate(group.set(&[0, 1, 3]), 2).unwrap();
+        let to_pair = group.record.rotate(group.set(&[0, 1]), 2).unwrap();
+        let sweep = group.sign_sweep(&to_dave);
+
+        assert!(matches!(
+            group.record.complete(&to_pair, &sweep),
+            Err(Error::InvalidRotation(_))
+        ));
+        group.record.complete(&to_dave, &sweep).unwrap();
+
+        // The record has moved on; the other rotation is stale
+        assert!(matches!(
+            group.record.complete(&to_pair, &sweep),
+            Err(Error::InvalidRotation(_))
+        ));
+    }
+}
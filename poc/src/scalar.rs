@@ -0,0 +1,147 @@
+//! Scalar helpers shared by the threshold protocols
+//!
+//! `SecretKey` doubles as a scalar modulo the curve order. These helpers add
+//! the few operations the threshold modules need on top of its in-place API.
+
+use blake2::{Blake2b512, Digest};
+use secp256k1zkp::{PublicKey, Secp256k1, SecretKey};
+
+use crate::error::{Error, Result};
+use crate::types::ParticipantId;
+
+/// Scalar for a small non-zero integer
+pub fn from_u32(secp: &Secp256k1, value: u32) -> Result<SecretKey> {
+    let mut bytes = [0u8; 32];
+    bytes[28..].copy_from_slice(&value.to_be_bytes());
+    Ok(SecretKey::from_slice(secp, &bytes)?)
+}
+
+/// Share index of a participant: its ID as a scalar
+///
+/// # Errors
+/// Returns `Error::InvalidShareIndex` for ID 0, where the secret itself lives
+pub fn share_index(secp: &Secp256k1, participant: ParticipantId) -> Result<SecretKey> {
+    if participant.inner() == 0 {
+        return Err(Error::InvalidShareIndex { participant });
+    }
+    from_u32(secp, participant.inner())
+}
+
+/// Hash domain-separated data to a scalar (first 32 bytes of Blake2b-512)
+pub fn hash_to_scalar(secp: &Secp256k1, domain: &[u8], parts: &[&[u8]]) -> Result<SecretKey> {
+    let mut hasher = Blake2b512::new();
+    hasher.update(domain);
+    for part in parts {
+        hasher.update(part);
+    }
+    let hash = hasher.finalize();
+    Ok(SecretKey::from_slice(secp, &hash[..32])?)
+}
+
+/// `a + b`
+pub fn add(secp: &Secp256k1, a: &SecretKey, b: &SecretKey) -> Result<SecretKey> {
+    let mut sum = a.clone();
+    sum.add_assign(secp, b)?;
+    Ok(sum)
+}
+
+/// `a - b`
+pub fn sub(secp: &Secp256k1, a: &SecretKey, b: &SecretKey) -> Result<SecretKey> {
+    let mut negated = b.clone();
+    negated.neg_assign(secp)?;
+    add(secp, a, &negated)
+}
+
+/// `a * b`
+pub fn mul(secp: &Secp256k1, a: &SecretKey, b: &SecretKey) -> Result<SecretKey> {
+    let mut product = a.clone();
+    product.mul_assign(secp, b)?;
+    Ok(product)
+}
+
+/// `s * P`
+pub fn mul_point(secp: &Secp256k1, point: &PublicKey, scalar: &SecretKey) -> Result<PublicKey> {
+    let mut product = *point;
+    product.mul_assign(secp, scalar)?;
+    Ok(product)
+}
+
+/// Serialize a point in compressed form
+pub fn point_bytes(secp: &Secp256k1, point: &PublicKey) -> Vec<u8> {
+    point.serialize_vec(secp, true).to_vec()
+}
+
+/// Lagrange coefficient of `participant` for interpolating at zero
+///
+/// ```text
+/// lambda_i = prod_{j != i} x_j / (x_j - x_i)
+/// ```
+///
+/// # Errors
+/// Returns `Error::UnknownParticipant` if `participant` is not in `signers`
+/// Returns `Error::DuplicateParticipant` if `signers` repeats an ID
+/// Returns `Error::InvalidShareIndex` if any ID is 0
+pub fn lagrange_coefficient(
+    secp: &Secp256k1,
+    participant: ParticipantId,
+    signers: &[ParticipantId],
+) -> Result<SecretKey> {
+    lagrange(secp, participant, signers, None)
+}
+
+/// Lagrange coefficient of `participant` for interpolating at another share index
+///
+/// ```text
+/// lambda_i(x) = prod_{j != i} (x_j - x) / (x_j - x_i)
+/// ```
+///
+/// # Errors
+/// As [`lagrange_coefficient`], and returns `Error::DuplicateParticipant` if
+/// `at` is itself in `signers`
+pub fn lagrange_coefficient_at(
+    secp: &Secp256k1,
+    participant: ParticipantId,
+    signers: &[ParticipantId],
+    at: ParticipantId,
+) -> Result<SecretKey> {
+    if signers.contains(&at) {
+        return Err(Error::DuplicateParticipant { participant: at });
+    }
+    let x = share_index(secp, at)?;
+    lagrange(secp, participant, signers, Some(&x))
+}
+
+fn lagrange(
+    secp: &Secp256k1,
+    participant: ParticipantId,
+    signers: &[ParticipantId],
+    at: Option<&SecretKey>,
+) -> Result<SecretKey> {
+    if !signers.contains(&participant) {
+        return Err(Error::UnknownParticipant { participant });
+    }
+    let x_i = share_index(secp, participant)?;
+
+    let mut numerator = from_u32(secp, 1)?;
+    let mut denominator = from_u32(secp, 1)?;
+    let mut seen = Vec::with_capacity(signers.len());
+    for &other in signers {
+        if seen.contains(&other) {
+            return Err(Error::DuplicateParticipant { participant: other });
+        }
+        seen.push(other);
+        if other == participant {
+            continue;
+        }
+
+        let x_j = share_index(secp, other)?;
+        match at {
+            Some(x) => numerator.mul_assign(secp, &sub(secp, &x_j, x)?)?,
+            None => numerator.mul_assign(secp, &x_j)?,
+        }
+        denominator.mul_assign(secp, &sub(secp, &x_j, &x_i)?)?;
+    }
+
+    denominator.inv_assign(secp)?;
+    mul(secp, &numerator, &denominator)
+}
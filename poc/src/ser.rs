@@ -0,0 +1,273 @@
+//! Serde helpers for hex encoding of keys and fixed-size byte arrays
+
+// Helper module for hex serialization of PublicKey
+pub mod hex_pubkey {
+    use secp256k1zkp::{PublicKey, Secp256k1};
+    use serde::{Deserializer, Serializer};
+
+    // Thread-local Secp256k1 context for serialization
+    thread_local! {
+        static SECP: Secp256k1 = Secp256k1::new();
+    }
+
+    pub fn serialize<S>(pubkey: &PublicKey, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let serialized_bytes = SECP.with(|secp| pubkey.serialize_vec(secp, true));
+        serializer.serialize_str(&hex::encode(&serialized_bytes[..]))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<PublicKey, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::Error;
+        let s: String = serde::Deserialize::deserialize(deserializer)?;
+        let bytes = hex::decode(&s).map_err(Error::custom)?;
+        SECP.with(|secp| PublicKey::from_slice(secp, &bytes).map_err(Error::custom))
+    }
+}
+
+// Helper module for hex serialization of fixed-size byte arrays
+pub mod hex_array {
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S, const N: usize>(bytes: &[u8; N], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D, const N: usize>(deserializer: D) -> Result<[u8; N], D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::Error;
+        let s: String = serde::Deserialize::deserialize(deserializer)?;
+        let bytes = hex::decode(&s).map_err(Error::custom)?;
+        bytes
+            .try_into()
+            .map_err(|b: Vec<u8>| Error::invalid_length(b.len(), &"fixed-size hex string"))
+    }
+}
+
+// Helper module for hex serialization of SecretKey (offsets and other shared scalars)
+pub mod hex_seckey {
+    use secp256k1zkp::{Secp256k1, SecretKey};
+    use serde::{Deserializer, Serializer};
+
+    // Thread-local Secp256k1 context for serialization
+    thread_local! {
+        static SECP: Secp256k1 = Secp256k1::without_caps();
+    }
+
+    pub fn serialize<S>(key: &SecretKey, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&hex::encode(key.0))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<SecretKey, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::Error;
+        let s: String = serde::Deserialize::deserialize(deserializer)?;
+        let bytes = hex::decode(&s).map_err(Error::custom)?;
+        SECP.with(|secp| SecretKey::from_slice(secp, &bytes).map_err(Error::custom))
+    }
+}
+
+// Helper module for hex serialization of Pedersen commitments
+pub mod hex_commitment {
+    use secp256k1zkp::constants::PEDERSEN_COMMITMENT_SIZE;
+    use secp256k1zkp::pedersen::Commitment;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S>(commit: &Commitment, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&hex::encode(commit.0))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Commitment, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::Error;
+        let s: String = serde::Deserialize::deserialize(deserializer)?;
+        let bytes = hex::decode(&s).map_err(Error::custom)?;
+        if bytes.len() != PEDERSEN_COMMITMENT_SIZE {
+            return Err(Error::invalid_length(bytes.len(), &"33-byte commitment"));
+        }
+        Ok(Commitment::from_vec(bytes))
+    }
+}
+
+// Helper module for hex serialization of range proofs
+pub mod hex_rangeproof {
+    use secp256k1zkp::constants::MAX_PROOF_SIZE;
+    use secp256k1zkp::pedersen::RangeProof;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S>(proof: &RangeProof, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&hex::encode(proof))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<RangeProof, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::Error;
+        let s: String = serde::Deserialize::deserialize(deserializer)?;
+        let bytes = hex::decode(&s).map_err(Error::custom)?;
+        if bytes.len() > MAX_PROOF_SIZE {
+            return Err(Error::invalid_length(bytes.len(), &"range proof"));
+        }
+        let mut proof = [0u8; MAX_PROOF_SIZE];
+        proof[..bytes.len()].copy_from_slice(&bytes);
+        Ok(RangeProof {
+            proof,
+            plen: bytes.len(),
+        })
+    }
+}
+
+// Helper module for hex serialization of signatures in compact form, as Grin's JSON does
+pub mod hex_signature {
+    use secp256k1zkp::{Secp256k1, Signature};
+    use serde::{Deserializer, Serializer};
+
+    // Thread-local Secp256k1 context for serialization
+    thread_local! {
+        static SECP: Secp256k1 = Secp256k1::without_caps();
+    }
+
+    pub fn serialize<S>(sig: &Signature, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let compact = SECP.with(|secp| sig.serialize_compact(secp));
+        serializer.serialize_str(&hex::encode(compact))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Signature, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::Error;
+        let s: String = serde::Deserialize::deserialize(deserializer)?;
+        let bytes = hex::decode(&s).map_err(Error::custom)?;
+        if bytes.len() != 64 {
+            return Err(Error::invalid_length(bytes.len(), &"64-byte signature"));
+        }
+        SECP.with(|secp| Signature::from_compact(secp, &bytes).map_err(Error::custom))
+    }
+}
+
+// Helper module for hex serialization of a list of PublicKeys
+pub mod hex_pubkey_vec {
+    use secp256k1zkp::{PublicKey, Secp256k1};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    // Thread-local Secp256k1 context for serialization
+    thread_local! {
+        static SECP: Secp256k1 = Secp256k1::new();
+    }
+
+    pub fn serialize<S>(pubkeys: &[PublicKey], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        SECP.with(|secp| {
+            pubkeys
+                .iter()
+                .map(|pk| hex::encode(&pk.serialize_vec(secp, true)[..]))
+                .collect::<Vec<_>>()
+        })
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<PublicKey>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::Error;
+        let strings = Vec::<String>::deserialize(deserializer)?;
+        SECP.with(|secp| {
+            strings
+                .iter()
+                .map(|s| {
+                    let bytes = hex::decode(s).map_err(Error::custom)?;
+                    PublicKey::from_slice(secp, &bytes).map_err(Error::custom)
+                })
+                .collect()
+        })
+    }
+}
+
+// Helper module for hex serialization of a list of SecretKeys
+pub mod hex_seckey_vec {
+    use secp256k1zkp::{Secp256k1, SecretKey};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    // Thread-local Secp256k1 context for serialization
+    thread_local! {
+        static SECP: Secp256k1 = Secp256k1::without_caps();
+    }
+
+    pub fn serialize<S>(keys: &[SecretKey], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        keys.iter()
+            .map(|sk| hex::encode(sk.0))
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<SecretKey>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::Error;
+        let strings = Vec::<String>::deserialize(deserializer)?;
+        SECP.with(|secp| {
+            strings
+                .iter()
+                .map(|s| {
+                    let bytes = hex::decode(s).map_err(Error::custom)?;
+                    SecretKey::from_slice(secp, &bytes).map_err(Error::custom)
+                })
+                .collect()
+        })
+    }
+}
+
+// Helper module for hex serialization of raw byte strings
+pub mod hex_bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::Error;
+        let s = String::deserialize(deserializer)?;
+        hex::decode(s).map_err(Error::custom)
+    }
+}
@@ -2,11 +2,12 @@
 
 use blake2::{Blake2b512, Digest};
 use rand::thread_rng;
-use secp256k1zkp::{PublicKey, Secp256k1, SecretKey};
+use secp256k1zkp::aggsig;
+use secp256k1zkp::{ContextFlag, Message, PublicKey, Secp256k1, SecretKey, Signature};
 
 use crate::error::{Error, Result};
 use crate::participant::Participant;
-use crate::types::{Challenge, Coefficient, NonceCommitment};
+use crate::types::{Coefficient, NonceCommitment, PartialSignature};
 
 /// `MuSig2` Round 1 state (nonce commitment phase)
 #[derive(Debug, Clone)]
@@ -19,6 +20,9 @@ pub struct Round1State {
 
     /// Commitment to public nonces H(R1, R2)
     commitment: NonceCommitment,
+
+    /// Effective secret nonce r = r1 + r2 - MUST be kept private!
+    secret_nonce: SecretKey,
 }
 
 impl Round1State {
@@ -27,6 +31,11 @@ impl Round1State {
         &self.secret_nonces
     }
 
+    /// Get the effective secret nonce `r1 + r2` used in Round 3 (use with caution!)
+    pub const fn secret_nonce(&self) -> &SecretKey {
+        &self.secret_nonce
+    }
+
     /// Get the public nonces
     pub const fn public_nonces(&self) -> &(PublicKey, PublicKey) {
         &self.public_nonces
@@ -63,7 +72,7 @@ impl Session {
     pub fn new(participants: Vec<Participant>) -> Self {
         Self {
             participants,
-            secp: Secp256k1::new(),
+            secp: Secp256k1::with_caps(ContextFlag::Commit),
         }
     }
 
@@ -72,6 +81,16 @@ impl Session {
         self.participants.len()
     }
 
+    /// Get all participants
+    pub fn participants(&self) -> &[Participant] {
+        &self.participants
+    }
+
+    /// Get the Secp256k1 context (capable of Pedersen commitments)
+    pub(crate) const fn secp(&self) -> &Secp256k1 {
+        &self.secp
+    }
+
     /// Compute key aggregation coefficient: `a_i` = H(L || `X_i`)
     ///
     /// This prevents rogue key attacks without requiring proofs of possession.
@@ -110,33 +129,56 @@ impl Session {
         Coefficient::new(coefficient)
     }
 
-    /// Aggregate public keys: `X_agg` = `sum(a_i` * `X_i`)
+    /// Compute a participant's weighted public key: `a_i * X_i`
+    ///
+    /// # Errors
+    /// Returns `Error::Crypto` if the coefficient is not a valid scalar
+    pub fn weighted_pubkey(&self, pubkey: &PublicKey) -> Result<PublicKey> {
+        let coefficient = self.key_agg_coefficient(pubkey).to_secret_key(&self.secp)?;
+        let mut weighted = *pubkey;
+        weighted.mul_assign(&self.secp, &coefficient)?;
+        Ok(weighted)
+    }
+
+    /// Compute a participant's weighted secret key: `a_i * x_i`
     ///
-    /// # Note
-    /// This is a simplified `PoC` implementation.
-    /// Production requires proper scalar multiplication.
+    /// This is the participant's share of the aggregate secret key.
+    ///
+    /// # Errors
+    /// Returns `Error::Crypto` if the coefficient is not a valid scalar
+    pub fn weighted_secret_key(&self, secret_key: &SecretKey) -> Result<SecretKey> {
+        let pubkey = PublicKey::from_secret_key(&self.secp, secret_key)?;
+        let coefficient = self
+            .key_agg_coefficient(&pubkey)
+            .to_secret_key(&self.secp)?;
+        let mut weighted = secret_key.clone();
+        weighted.mul_assign(&self.secp, &coefficient)?;
+        Ok(weighted)
+    }
+
+    /// Aggregate public keys: `X_agg` = `sum(a_i` * `X_i`)
     ///
     /// # Returns
-    /// Aggregated public key (simplified for `PoC`)
+    /// Aggregated public key
     ///
     /// # Errors
     /// Returns `Error::NoParticipants` if no participants are in the session
+    /// Returns `Error::Crypto` if the weighted keys sum to the point at infinity
     pub fn aggregate_pubkeys(&self) -> Result<PublicKey> {
         if self.participants.is_empty() {
             return Err(Error::NoParticipants);
         }
 
-        // For PoC: return first participant's key
-        // TODO: Implement proper aggregation
-        // X_agg = sum(a_i * X_i) for all participants
+        let weighted = self
+            .participants
+            .iter()
+            .map(|participant| self.weighted_pubkey(participant.public_key()))
+            .collect::<Result<Vec<_>>>()?;
 
-        #[cfg(debug_assertions)]
-        {
-            eprintln!("⚠️  Note: Using simplified aggregation for PoC");
-            eprintln!("   Production needs: X_agg = sum(a_i * X_i)");
-        }
-
-        Ok(*self.participants[0].public_key())
+        Ok(PublicKey::from_combination(
+            &self.secp,
+            weighted.iter().collect(),
+        )?)
     }
 
     /// Round 1: Generate nonce commitment
@@ -167,10 +209,15 @@ impl Session {
         // Compute commitment H(R1 || R2)
         let commitment = NonceCommitment::from_nonces(&self.secp, &public_nonce1, &public_nonce2);
 
+        // Effective secret nonce r = r1 + r2, matching R = R1 + R2
+        let mut secret_nonce = secret_nonce1.clone();
+        secret_nonce.add_assign(&self.secp, &secret_nonce2)?;
+
         Ok(Round1State {
             secret_nonces: (secret_nonce1, secret_nonce2),
             public_nonces: (public_nonce1, public_nonce2),
             commitment,
+            secret_nonce,
         })
     }
 
@@ -185,7 +232,7 @@ impl Session {
     /// * `revealed_nonces` - Public nonces revealed in Round 2
     ///
     /// # Returns
-    /// Aggregated nonce R (simplified for `PoC`)
+    /// Aggregated nonce R
     ///
     /// # Errors
     /// Returns `Error::NoNonces` if no nonces are provided
@@ -209,72 +256,210 @@ impl Session {
         }
 
         // Aggregate nonces: R = sum(R1_i + R2_i) for all participants
-        // For PoC: return first nonce
-        // TODO: Implement proper point addition
-
-        #[cfg(debug_assertions)]
-        {
-            eprintln!("⚠️  Note: Using simplified nonce aggregation for PoC");
-            eprintln!("   Production needs: R = sum(R1_i + R2_i)");
-        }
+        let all_nonces = revealed_nonces
+            .iter()
+            .flat_map(|(nonce1, nonce2)| [nonce1, nonce2])
+            .collect();
 
-        Ok(revealed_nonces[0].0)
+        Ok(PublicKey::from_combination(&self.secp, all_nonces)?)
     }
 
-    /// Round 3: Compute challenge hash (partial signature structure)
+    /// Round 3: Compute partial signature
     ///
-    /// In a complete implementation, this would compute:
-    /// `s_i` = `r_i` + c * `a_i` * `x_i`
+    /// Computes `s_i` = `r_i` + c * `a_i` * `x_i`
     ///
     /// Where:
-    /// - `r_i`: secret nonce
-    /// - c: challenge hash
+    /// - `r_i`: effective secret nonce `r1_i + r2_i`
+    /// - c: challenge hash, computed as in Grin's aggsig: SHA256(R.x || `X_agg` || m)
     /// - `a_i`: key aggregation coefficient
     /// - `x_i`: secret key
     ///
-    /// # Note
-    /// This `PoC` only computes the challenge hash.
-    /// Production requires proper scalar arithmetic.
+    /// If R has a non-square y coordinate every signer negates `r_i`, so the
+    /// aggregated signature verifies under Grin's kernel signature rules.
     ///
     /// # Arguments
     /// * `message` - Message to sign (32 bytes)
-    /// * `_secret_key` - Participant's secret key (unused in `PoC`)
-    /// * `_secret_nonce` - Secret nonce from Round 1 (unused in `PoC`)
+    /// * `secret_key` - Participant's secret key
+    /// * `secret_nonce` - Effective secret nonce from Round 1
     /// * `agg_nonce` - Aggregated nonce from Round 2
     /// * `agg_pubkey` - Aggregated public key
     ///
     /// # Returns
-    /// Challenge hash c = `H(X_agg` || R || m)
+    /// Partial signature `R_i.x || s_i`
     ///
     /// # Errors
-    /// Returns `Error::Crypto` if challenge computation fails
+    /// Returns `Error::Crypto` if signing fails
     pub fn round3_partial_sign(
         &self,
         message: &[u8; 32],
-        _secret_key: &SecretKey,
-        _secret_nonce: &SecretKey,
+        secret_key: &SecretKey,
+        secret_nonce: &SecretKey,
+        agg_nonce: &PublicKey,
+        agg_pubkey: &PublicKey,
+    ) -> Result<PartialSignature> {
+        let msg = Message::from_slice(message)?;
+        let weighted_key = self.weighted_secret_key(secret_key)?;
+
+        let signature = aggsig::sign_single(
+            &self.secp,
+            &msg,
+            &weighted_key,
+            Some(secret_nonce),
+            None,
+            Some(agg_nonce),
+            Some(agg_pubkey),
+            Some(agg_nonce),
+        )?;
+
+        Ok(PartialSignature::new(signature.to_raw_data()))
+    }
+
+    /// Verify a partial signature against a participant's key and nonces
+    ///
+    /// Checks `s_i * G = ±R_i + c * a_i * X_i` and that the signature commits
+    /// to the participant's revealed nonce `R_i = R1_i + R2_i`.
+    ///
+    /// # Arguments
+    /// * `message` - Signed message (32 bytes)
+    /// * `partial` - Partial signature to verify
+    /// * `pubkey` - Signer's public key `X_i`
+    /// * `revealed_nonces` - Signer's public nonces `(R1_i, R2_i)` from Round 2
+    /// * `agg_nonce` - Aggregated nonce from Round 2
+    /// * `agg_pubkey` - Aggregated public key
+    pub fn verify_partial_signature(
+        &self,
+        message: &[u8; 32],
+        partial: &PartialSignature,
+        pubkey: &PublicKey,
+        revealed_nonces: &(PublicKey, PublicKey),
         agg_nonce: &PublicKey,
         agg_pubkey: &PublicKey,
-    ) -> Result<Challenge> {
-        // Compute challenge: c = H(X_agg || R || m)
-        let challenge = Challenge::from_message(&self.secp, agg_pubkey, agg_nonce, message);
+    ) -> bool {
+        let (Ok(msg), Ok(weighted), Ok(nonce)) = (
+            Message::from_slice(message),
+            self.weighted_pubkey(pubkey),
+            PublicKey::from_combination(&self.secp, vec![&revealed_nonces.0, &revealed_nonces.1]),
+        ) else {
+            return false;
+        };
+
+        // The partial signature must carry this signer's nonce, not another one
+        let serialized_nonce = nonce.serialize_vec(&self.secp, true);
+        if partial.as_bytes()[..32] != serialized_nonce[1..] {
+            return false;
+        }
 
-        #[cfg(debug_assertions)]
+        let Ok(signature) = Signature::from_raw_data(partial.as_bytes()) else {
+            return false;
+        };
+        aggsig::verify_single(
+            &self.secp,
+            &signature,
+            &msg,
+            Some(agg_nonce),
+            &weighted,
+            Some(agg_pubkey),
+            None,
+            true,
+        )
+    }
+
+    /// Aggregate partial signatures into a final signature: s = `sum(s_i)`
+    ///
+    /// Every partial signature is verified first; partial signatures and
+    /// revealed nonces are ordered like the session's participants.
+    ///
+    /// # Arguments
+    /// * `message` - Signed message (32 bytes)
+    /// * `partials` - Partial signatures from Round 3
+    /// * `revealed_nonces` - Public nonces revealed in Round 2
+    /// * `agg_nonce` - Aggregated nonce from Round 2
+    ///
+    /// # Returns
+    /// Schnorr signature `(R.x, s)` valid for the aggregated public key
+    ///
+    /// # Errors
+    /// Returns `Error::NoParticipants` if no participants are in the session
+    /// Returns `Error::PartialSignatureCount` if there is not exactly one
+    /// partial signature and one nonce pair per participant
+    /// Returns `Error::InvalidPartialSignature` if any partial signature fails to verify
+    pub fn aggregate_signatures(
+        &self,
+        message: &[u8; 32],
+        partials: &[PartialSignature],
+        revealed_nonces: &[(PublicKey, PublicKey)],
+        agg_nonce: &PublicKey,
+    ) -> Result<Signature> {
+        let agg_pubkey = self.aggregate_pubkeys()?;
+        if partials.len() != self.participants.len()
+            || revealed_nonces.len() != self.participants.len()
         {
-            eprintln!("⚠️  Note: Returning challenge hash only (PoC)");
-            eprintln!("   Production needs: s_i = r_i + c * a_i * x_i");
+            return Err(Error::PartialSignatureCount {
+                expected: self.participants.len(),
+                actual: partials.len().min(revealed_nonces.len()),
+            });
         }
 
-        // In production: compute s_i = r_i + c * a_i * x_i
-        // using proper scalar arithmetic
+        for (i, participant) in self.participants.iter().enumerate() {
+            if !self.verify_partial_signature(
+                message,
+                &partials[i],
+                participant.public_key(),
+                &revealed_nonces[i],
+                agg_nonce,
+                &agg_pubkey,
+            ) {
+                return Err(Error::InvalidPartialSignature {
+                    participant_index: i,
+                });
+            }
+        }
 
-        Ok(challenge)
+        let signatures = partials
+            .iter()
+            .map(|partial| Signature::from_raw_data(partial.as_bytes()))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let signature =
+            aggsig::add_signatures_single(&self.secp, signatures.iter().collect(), agg_nonce)?;
+
+        if !self.verify_signature(message, &signature, &agg_pubkey) {
+            return Err(Error::Crypto("aggregated signature does not verify".into()));
+        }
+
+        Ok(signature)
+    }
+
+    /// Verify a final signature under Grin's kernel signature rules
+    ///
+    /// # Arguments
+    /// * `message` - Signed message (32 bytes)
+    /// * `signature` - Aggregated signature
+    /// * `pubkey` - Public key the signature is for (e.g. the kernel excess)
+    pub fn verify_signature(
+        &self,
+        message: &[u8; 32],
+        signature: &Signature,
+        pubkey: &PublicKey,
+    ) -> bool {
+        Message::from_slice(message).is_ok_and(|msg| {
+            aggsig::verify_single(
+                &self.secp,
+                signature,
+                &msg,
+                None,
+                pubkey,
+                Some(pubkey),
+                None,
+                false,
+            )
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::{keyed_session, sign_all};
     use crate::types::ParticipantId;
 
     fn create_test_session(n_participants: u32) -> Session {
@@ -374,7 +559,7 @@ mod tests {
         let round1 = session.round1_generate_nonces().unwrap();
 
         let message = [0x42u8; 32];
-        let challenge = session
+        let partial = session
             .round3_partial_sign(
                 &message,
                 &sk,
@@ -385,12 +570,76 @@ mod tests {
             .unwrap();
 
         assert_eq!(
-            challenge.as_bytes().len(),
-            32,
-            "Challenge should be 32 bytes"
+            partial.as_bytes().len(),
+            64,
+            "Partial signature should be 64 bytes"
         );
     }
 
+    #[test]
+    fn test_aggregate_pubkey_matches_weighted_secrets() {
+        let secp = Secp256k1::new();
+        let mut rng = thread_rng();
+
+        let secret_keys: Vec<_> = (0..3).map(|_| SecretKey::new(&secp, &mut rng)).collect();
+        let participants: Vec<_> = secret_keys
+            .iter()
+            .zip(1..)
+            .map(|(sk, i)| {
+                let pk = PublicKey::from_secret_key(&secp, sk).unwrap();
+                Participant::new(ParticipantId::new(i), pk)
+            })
+            .collect();
+        let session = Session::new(participants);
+
+        let mut agg_secret = session.weighted_secret_key(&secret_keys[0]).unwrap();
+        for sk in &secret_keys[1..] {
+            agg_secret
+                .add_assign(&secp, &session.weighted_secret_key(sk).unwrap())
+                .unwrap();
+        }
+
+        assert_eq!(
+            session.aggregate_pubkeys().unwrap(),
+            PublicKey::from_secret_key(&secp, &agg_secret).unwrap(),
+            "Aggregate key should equal sum(a_i * x_i) * G"
+        );
+    }
+
+    #[test]
+    fn test_full_signing_flow() {
+        for n in 1..=4 {
+            let (session, secret_keys) = keyed_session(&Secp256k1::new(), n);
+            let message = [0x17u8; 32];
+
+            let (partials, revealed, agg_nonce) = sign_all(&session, &secret_keys, &message, None);
+            let signature = session
+                .aggregate_signatures(&message, &partials, &revealed, &agg_nonce)
+                .unwrap();
+
+            let agg_pubkey = session.aggregate_pubkeys().unwrap();
+            assert!(session.verify_signature(&message, &signature, &agg_pubkey));
+            assert!(!session.verify_signature(&[0x18u8; 32], &signature, &agg_pubkey));
+        }
+    }
+
+    #[test]
+    fn test_invalid_partial_signature_detected() {
+        let (session, secret_keys) = keyed_session(&Secp256k1::new(), 3);
+        let message = [0x17u8; 32];
+
+        let (mut partials, revealed, agg_nonce) = sign_all(&session, &secret_keys, &message, None);
+        partials.swap(1, 2);
+
+        let result = session.aggregate_signatures(&message, &partials, &revealed, &agg_nonce);
+        assert!(matches!(
+            result,
+            Err(Error::InvalidPartialSignature {
+                participant_index: 1
+            })
+        ));
+    }
+
     #[test]
     fn test_empty_participants() {
         let session = Session::new(vec![]);
@@ -0,0 +1,188 @@
+//! Slatepack armor for asynchronous transport
+//!
+//! Follows the framing used by grin-wallet so that round messages can be
+//! pasted into chat or email like any other Slatepack:
+//!
+//! ```text
+//! 1. checksum = first 4 bytes of SHA256(SHA256(payload))
+//! 2. encoded  = Base58(checksum || payload)
+//! 3. armored  = "BEGINSLATEPACK. " + words(encoded) + ". ENDSLATEPACK."
+//! ```
+
+use sha2::{Digest, Sha256};
+
+use crate::error::{Error, Result};
+
+/// Armor header, including the terminating period
+pub const HEADER: &str = "BEGINSLATEPACK.";
+
+/// Armor footer, including the leading period
+pub const FOOTER: &str = ". ENDSLATEPACK.";
+
+/// Characters per word in the formatted payload
+const WORD_LENGTH: usize = 15;
+
+/// Words per line in the formatted payload
+const WORDS_PER_LINE: usize = 200;
+
+/// Characters ignored anywhere inside the armor (quoting and whitespace)
+const WHITESPACE: [char; 5] = ['>', '\n', '\r', '\t', ' '];
+
+/// Armor a binary payload
+///
+/// # Example
+///
+/// ```rust
+/// use grin_multisig_poc::slatepack;
+///
+/// let armored = slatepack::encode(b"round data");
+/// assert!(armored.starts_with("BEGINSLATEPACK."));
+/// assert_eq!(slatepack::decode(&armored).unwrap(), b"round data");
+/// ```
+pub fn encode(payload: &[u8]) -> String {
+    let mut buf = checksum(payload).to_vec();
+    buf.extend_from_slice(payload);
+    let encoded = bs58::encode(buf).into_string();
+
+    format!("{}{FOOTER}\n", format_words(&format!("{HEADER}{encoded}")))
+}
+
+/// Remove the armor and return the verified binary payload
+///
+/// # Errors
+/// Returns `Error::InvalidArmor` if the framing or Base58 payload is malformed
+/// Returns `Error::ChecksumMismatch` if the payload was altered
+pub fn decode(armored: &str) -> Result<Vec<u8>> {
+    let mut sections = armored.splitn(3, '.');
+    let header = sections.next().unwrap_or_default();
+    let payload = sections
+        .next()
+        .ok_or_else(|| Error::InvalidArmor("missing payload".into()))?;
+    let footer = sections
+        .next()
+        .ok_or_else(|| Error::InvalidArmor("missing footer".into()))?;
+
+    if strip_whitespace(header) != HEADER.trim_end_matches('.') {
+        return Err(Error::InvalidArmor("bad header".into()));
+    }
+    if strip_whitespace(footer) != FOOTER.trim_start_matches(['.', ' ']) {
+        return Err(Error::InvalidArmor("bad footer".into()));
+    }
+
+    let decoded = bs58::decode(strip_whitespace(payload))
+        .into_vec()
+        .map_err(|e| Error::InvalidArmor(e.to_string()))?;
+    if decoded.len() < 4 {
+        return Err(Error::InvalidArmor("payload too short".into()));
+    }
+
+    let (check, data) = decoded.split_at(4);
+    if check != checksum(data) {
+        return Err(Error::ChecksumMismatch);
+    }
+
+    Ok(data.to_vec())
+}
+
+/// First four bytes of `SHA256(SHA256(payload))`
+fn checksum(payload: &[u8]) -> [u8; 4] {
+    let hash = Sha256::digest(Sha256::digest(payload));
+    let mut check = [0u8; 4];
+    check.copy_from_slice(&hash[..4]);
+    check
+}
+
+/// Split armored text into space-separated words and newline-separated lines
+fn format_words(armored: &str) -> String {
+    let mut formatted = String::with_capacity(armored.len() + armored.len() / WORD_LENGTH);
+    for (i, c) in armored.chars().enumerate() {
+        if i != 0 && i % WORD_LENGTH == 0 {
+            if i % (WORD_LENGTH * WORDS_PER_LINE) == 0 {
+                formatted.push('\n');
+            } else {
+                formatted.push(' ');
+            }
+        }
+        formatted.push(c);
+    }
+    formatted
+}
+
+fn strip_whitespace(section: &str) -> String {
+    section
+        .chars()
+        .filter(|c| !WHITESPACE.contains(c))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Armor raw Base58 text without computing a checksum
+    fn frame(encoded: &str) -> String {
+        format!("{HEADER} {encoded}{FOOTER}")
+    }
+
+    #[test]
+    fn test_round_trip_over_many_lines() {
+        let payload: Vec<u8> = (0..=255).cycle().take(4_000).collect();
+        let armored = encode(&payload);
+        assert!(armored.lines().count() > 1);
+        assert!(armored
+            .split_whitespace()
+            .all(|word| word.len() <= WORD_LENGTH + 1));
+        assert_eq!(decode(&armored).unwrap(), payload);
+
+        // Quoted in an email reply
+        let quoted = armored.replace('\n', "\n> ");
+        assert_eq!(decode(&quoted).unwrap(), payload);
+    }
+
+    #[test]
+    fn test_checksum_mismatch() {
+        let mut buf = checksum(b"round data").to_vec();
+        buf[0] ^= 1;
+        buf.extend_from_slice(b"round data");
+        let armored = frame(&bs58::encode(buf).into_string());
+        assert_eq!(decode(&armored), Err(Error::ChecksumMismatch));
+
+        let short = frame(&bs58::encode([1, 2, 3]).into_string());
+        assert_eq!(
+            decode(&short),
+            Err(Error::InvalidArmor("payload too short".into()))
+        );
+    }
+
+    #[test]
+    fn test_bad_framing_and_alphabet() {
+        let armored = encode(b"round data");
+        let body = armored
+            .trim_end()
+            .trim_start_matches(HEADER)
+            .trim_end_matches(FOOTER);
+
+        assert_eq!(
+            decode(&format!("BEGINSLATEPAK.{body}{FOOTER}")),
+            Err(Error::InvalidArmor("bad header".into()))
+        );
+        assert_eq!(
+            decode(&format!("{HEADER}{body}. ENDSLATEPAK.")),
+            Err(Error::InvalidArmor("bad footer".into()))
+        );
+        assert_eq!(
+            decode(&format!("{HEADER}{body}")),
+            Err(Error::InvalidArmor("missing footer".into()))
+        );
+        assert_eq!(
+            decode("BEGINSLATEPACK"),
+            Err(Error::InvalidArmor("missing payload".into()))
+        );
+
+        // 0, O, I and l are outside the Base58 alphabet
+        assert!(matches!(
+            decode(&frame("0OIl")),
+            Err(Error::InvalidArmor(_))
+        ));
+    }
+}
@@ -0,0 +1,364 @@
+//! Spending a jointly held multisig output
+//!
+//! A shared output commits to `C = v*H + X_agg`, where `X_agg = sum(a_i * K_i)`
+//! is the aggregate of the holders' funding keys. Participant `i` owns the
+//! blinding share `a_i * k_i` and subtracts it inside its own kernel share.
+//!
+//! # Algorithm
+//! ```text
+//! b_i    = sum(own output blinds) - a_i * k_i     (spend blinding total)
+//! o_i    = b_i - a'_i * x_i                       (offset share, see `offset`)
+//! kernel = MuSig2 over the excess keys x_i, signing features.kernel_sig_msg()
+//! ```
+//!
+//! The funding keys `k_i` and excess keys `x_i` belong to two separate
+//! sessions so that the kernel excess does not reveal the spent output.
+
+use secp256k1zkp::pedersen::Commitment;
+use secp256k1zkp::{PublicKey, Secp256k1, SecretKey, Signature};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::offset::{combine_offsets, verify_balance, OffsetShare};
+use crate::ser::hex_commitment;
+use crate::session::Session;
+use crate::transaction::{Input, KernelFeatures, Output, OutputFeatures, Transaction, TxKernel};
+use crate::types::PartialSignature;
+
+/// An output whose blinding factor is held jointly by a funding session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SharedOutput {
+    /// Output value
+    value: u64,
+
+    /// Commitment `v*H + X_agg`
+    #[serde(with = "hex_commitment")]
+    commit: Commitment,
+}
+
+impl SharedOutput {
+    /// Compute the shared output commitment for a funding session
+    ///
+    /// # Arguments
+    /// * `session` - Session over every holder's funding key `K_i`
+    /// * `value` - Output value
+    ///
+    /// # Errors
+    /// Returns `Error::NoParticipants` if the session is empty
+    /// Returns `Error::Crypto` if the commitment cannot be created
+    pub fn from_session(session: &Session, value: u64) -> Result<Self> {
+        Self::from_public_key(session.secp(), &session.aggregate_pubkeys()?, value)
+    }
+
+    /// Compute the shared output commitment `v*H + X` for a jointly held key
+    ///
+    /// # Arguments
+    /// * `secp` - Secp256k1 context capable of Pedersen commitments
+    /// * `public_key` - Key whose secret the holders share, e.g. a FROST group key
+    /// * `value` - Output value
+    ///
+    /// # Errors
+    /// Returns `Error::Crypto` if the commitment cannot be created
+    pub fn from_public_key(secp: &Secp256k1, public_key: &PublicKey, value: u64) -> Result<Self> {
+        let blinding = Commitment::from_pubkey(secp, public_key)?;
+        let commit = secp.commit_sum(vec![secp.commit_value(value)?, blinding], vec![])?;
+        Ok(Self { value, commit })
+    }
+
+    /// Get the output value
+    pub const fn value(&self) -> u64 {
+        self.value
+    }
+
+    /// Get the output commitment
+    pub const fn commitment(&self) -> &Commitment {
+        &self.commit
+    }
+
+    /// Input spending this output
+    pub const fn input(&self) -> Input {
+        Input::new(OutputFeatures::Plain, self.commit)
+    }
+
+    /// Compute a holder's spend blinding total `b_i`
+    ///
+    /// # Arguments
+    /// * `session` - Funding session the output was created with
+    /// * `funding_key` - Holder's secret funding key `k_i`
+    /// * `output_blinds` - Blinding factors of the outputs this holder creates
+    ///   in the spend (e.g. its change), possibly none
+    ///
+    /// # Errors
+    /// Returns `Error::Crypto` if the blinding factors sum to zero
+    pub fn spend_blinding(
+        &self,
+        session: &Session,
+        funding_key: &SecretKey,
+        output_blinds: &[SecretKey],
+    ) -> Result<SecretKey> {
+        let share = session.weighted_secret_key(funding_key)?;
+        Ok(session
+            .secp()
+            .blind_sum(output_blinds.to_vec(), vec![share])?)
+    }
+}
+
+/// Builds the transaction that spends one or more [`SharedOutput`]s
+#[derive(Debug, Clone)]
+pub struct SpendBuilder {
+    /// Outputs being spent
+    inputs: Vec<SharedOutput>,
+
+    /// Kernel features, including the fee
+    features: KernelFeatures,
+
+    /// Outputs created by the spend, including any change
+    outputs: Vec<Output>,
+}
+
+impl SpendBuilder {
+    /// Start a spend of `input` with the given kernel features
+    pub fn new(input: SharedOutput, features: KernelFeatures) -> Self {
+        Self {
+            inputs: vec![input],
+            features,
+            outputs: Vec::new(),
+        }
+    }
+
+    /// Add another shared output to spend
+    ///
+    /// Each holder's spend blinding then subtracts its share of every input.
+    #[must_use]
+    pub fn input(mut self, input: SharedOutput) -> Self {
+        self.inputs.push(input);
+        self
+    }
+
+    /// Add an output to the spend
+    #[must_use]
+    pub fn output(mut self, output: Output) -> Self {
+        self.outputs.push(output);
+        self
+    }
+
+    /// Get the outputs being spent
+    pub fn inputs(&self) -> &[SharedOutput] {
+        &self.inputs
+    }
+
+    /// Get the outputs created by the spend
+    pub fn outputs(&self) -> &[Output] {
+        &self.outputs
+    }
+
+    /// Get the kernel features, including the fee
+    pub const fn features(&self) -> KernelFeatures {
+        self.features
+    }
+
+    /// Message every participant signs in the kernel session
+    pub fn kernel_message(&self) -> [u8; 32] {
+        self.features.kernel_sig_msg()
+    }
+
+    /// Combine offset shares and check that the spend balances
+    ///
+    /// # Arguments
+    /// * `session` - Kernel session over every participant's excess key
+    /// * `offset_shares` - One offset share per participant
+    ///
+    /// # Returns
+    /// The kernel offset of the spend
+    ///
+    /// # Errors
+    /// Returns `Error::MissingOffsetShare` or `Error::UnknownParticipant` if the
+    /// offset shares do not match the session
+    /// Returns `Error::UnbalancedTransaction` if inputs, outputs, fee and kernel
+    /// do not balance
+    pub fn balanced_offset(
+        &self,
+        session: &Session,
+        offset_shares: &[OffsetShare],
+    ) -> Result<SecretKey> {
+        let offset = combine_offsets(session, offset_shares)?;
+        let output_commits: Vec<_> = self.outputs.iter().map(|o| *o.commitment()).collect();
+        verify_balance(
+            session,
+            &output_commits,
+            &self
+                .inputs
+                .iter()
+                .map(|i| *i.commitment())
+                .collect::<Vec<_>>(),
+            self.features.fee(),
+            &offset,
+        )?;
+        Ok(offset)
+    }
+
+    /// Combine offset shares and signatures into the final transaction
+    ///
+    /// # Arguments
+    /// * `session` - Kernel session over every participant's excess key
+    /// * `offset_shares` - One offset share per participant
+    /// * `partials` - Partial signatures over [`Self::kernel_message`]
+    /// * `revealed_nonces` - Public nonces revealed in Round 2
+    /// * `agg_nonce` - Aggregated nonce from Round 2
+    ///
+    /// # Errors
+    /// Returns `Error::MissingOffsetShare` or `Error::UnknownParticipant` if the
+    /// offset shares do not match the session
+    /// Returns `Error::UnbalancedTransaction` if inputs, outputs, fee and kernel
+    /// do not balance
+    /// Returns `Error::InvalidPartialSignature` if any partial signature is invalid
+    pub fn finalize(
+        &self,
+        session: &Session,
+        offset_shares: &[OffsetShare],
+        partials: &[PartialSignature],
+        revealed_nonces: &[(PublicKey, PublicKey)],
+        agg_nonce: &PublicKey,
+    ) -> Result<Transaction> {
+        let offset = self.balanced_offset(session, offset_shares)?;
+        let excess_sig = session.aggregate_signatures(
+            &self.kernel_message(),
+            partials,
+            revealed_nonces,
+            agg_nonce,
+        )?;
+        self.assemble(session, offset, excess_sig)
+    }
+
+    /// Build the final transaction around an already aggregated kernel signature
+    ///
+    /// Used when the signature does not come straight out of Round 3, e.g. a
+    /// completed adaptor pre-signature.
+    ///
+    /// # Arguments
+    /// * `session` - Kernel session over every participant's excess key
+    /// * `offset_shares` - One offset share per participant
+    /// * `excess_sig` - Signature over [`Self::kernel_message`] for the aggregate key
+    ///
+    /// # Errors
+    /// Returns the errors of [`Self::balanced_offset`]
+    /// Returns `Error::Crypto` if the signature does not verify
+    pub fn finalize_with_signature(
+        &self,
+        session: &Session,
+        offset_shares: &[OffsetShare],
+        excess_sig: Signature,
+    ) -> Result<Transaction> {
+        let offset = self.balanced_offset(session, offset_shares)?;
+        if !session.verify_signature(
+            &self.kernel_message(),
+            &excess_sig,
+            &session.aggregate_pubkeys()?,
+        ) {
+            return Err(Error::Crypto("kernel signature does not verify".into()));
+        }
+        self.assemble(session, offset, excess_sig)
+    }
+
+    fn assemble(
+        &self,
+        session: &Session,
+        offset: SecretKey,
+        excess_sig: Signature,
+    ) -> Result<Transaction> {
+        let excess = Commitment::from_pubkey(session.secp(), &session.aggregate_pubkeys()?)?;
+        Ok(Transaction::new(
+            offset,
+            self.inputs.iter().map(SharedOutput::input).collect(),
+            self.outputs.clone(),
+            vec![TxKernel::new(self.features, excess, excess_sig)],
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_util::{finalize_spend, keyed_session};
+
+    use rand::thread_rng;
+    use secp256k1zkp::{ContextFlag, Secp256k1};
+
+    #[test]
+    fn test_spend_shared_output_with_change() {
+        let secp = Secp256k1::with_caps(ContextFlag::Commit);
+        let mut rng = thread_rng();
+
+        for n in 1..=3 {
+            let (funding, funding_keys) = keyed_session(&secp, n);
+            let (kernel, excess_keys) = keyed_session(&secp, n);
+            let shared = SharedOutput::from_session(&funding, 100).unwrap();
+
+            // 60 to a recipient held by the last participant, 38 back to the first
+            let payment_blind = SecretKey::new(&secp, &mut rng);
+            let change_blind = SecretKey::new(&secp, &mut rng);
+            let builder = SpendBuilder::new(shared, KernelFeatures::Plain { fee: 2 })
+                .output(Output::create(&secp, 60, &payment_blind).unwrap())
+                .output(Output::create(&secp, 38, &change_blind).unwrap());
+
+            let last = funding_keys.len() - 1;
+            let blindings: Vec<_> = funding_keys
+                .iter()
+                .enumerate()
+                .map(|(i, k)| {
+                    let mut own = Vec::new();
+                    if i == 0 {
+                        own.push(change_blind.clone());
+                    }
+                    if i == last {
+                        own.push(payment_blind.clone());
+                    }
+                    shared.spend_blinding(&funding, k, &own).unwrap()
+                })
+                .collect();
+
+            let tx = finalize_spend(&kernel, &excess_keys, &builder, &blindings).unwrap();
+            assert_eq!(tx.fee(), 2);
+            assert_eq!(tx.inputs()[0].commitment(), shared.commitment());
+
+            let kernel_tx = &tx.kernels()[0];
+            let excess = kernel_tx.excess().to_pubkey(&secp).unwrap();
+            assert!(kernel.verify_signature(
+                &builder.kernel_message(),
+                kernel_tx.excess_sig(),
+                &excess
+            ));
+            for output in tx.outputs() {
+                assert!(secp
+                    .verify_bullet_proof(*output.commitment(), *output.proof(), None)
+                    .is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn test_spend_missing_holder_share_unbalanced() {
+        let secp = Secp256k1::with_caps(ContextFlag::Commit);
+        let (funding, funding_keys) = keyed_session(&secp, 2);
+        let (kernel, excess_keys) = keyed_session(&secp, 2);
+        let shared = SharedOutput::from_session(&funding, 50).unwrap();
+
+        let blind = SecretKey::new(&secp, &mut thread_rng());
+        let builder = SpendBuilder::new(shared, KernelFeatures::Plain { fee: 1 })
+            .output(Output::create(&secp, 49, &blind).unwrap());
+
+        // The second holder forgets to subtract its funding share
+        let blindings = vec![
+            shared
+                .spend_blinding(&funding, &funding_keys[0], &[blind])
+                .unwrap(),
+            SecretKey::new(&secp, &mut thread_rng()),
+        ];
+
+        assert!(matches!(
+            finalize_spend(&kernel, &excess_keys, &builder, &blindings),
+            Err(Error::UnbalancedTransaction)
+        ));
+    }
+}
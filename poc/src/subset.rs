@@ -0,0 +1,303 @@
+//! k-of-n spending through precomputed `MuSig2` subset keys
+//!
+//! For small groups a threshold can be emulated without FROST: every subset of
+//! `k` participants gets its own `MuSig2` aggregate key, and funds are locked to
+//! one of those keys. A spend then needs only the signers of that subset and
+//! runs as a plain n-of-n [`Session`] among them.
+//!
+//! There are `C(n, k)` subsets, so this only suits small groups such as 2-of-3
+//! escrow. Subsets and their signers keep the order of the participant list,
+//! which every party must therefore agree on.
+//!
+//! # Algorithm
+//! ```text
+//! For every S ⊆ {P_1..P_n} with |S| = k, in lexicographic order:
+//!   X_S = sum(a_i * X_i) over S        (Session::aggregate_pubkeys)
+//! Output locked to S:  C = v*H + X_S
+//! Spend:               MuSig2 among the signers of S only
+//! ```
+
+use secp256k1zkp::PublicKey;
+
+use crate::error::{Error, Result};
+use crate::participant::Participant;
+use crate::session::Session;
+use crate::spend::SharedOutput;
+use crate::types::ParticipantId;
+
+/// Aggregate key of one qualifying subset of participants
+#[derive(Debug, Clone)]
+pub struct SubsetKey {
+    /// Signers of the subset, in participant order
+    signers: Vec<Participant>,
+
+    /// `MuSig2` aggregate of the signers' keys
+    aggregate_key: PublicKey,
+}
+
+impl SubsetKey {
+    /// Get the signers of the subset
+    pub fn signers(&self) -> &[Participant] {
+        &self.signers
+    }
+
+    /// Get the IDs of the subset's signers
+    pub fn signer_ids(&self) -> Vec<ParticipantId> {
+        self.signers.iter().map(Participant::id).collect()
+    }
+
+    /// Get the subset's aggregate key
+    pub const fn aggregate_key(&self) -> &PublicKey {
+        &self.aggregate_key
+    }
+
+    /// Session among the subset's signers, for funding and spending
+    pub fn session(&self) -> Session {
+        Session::new(self.signers.clone())
+    }
+}
+
+/// Aggregate keys for every `k`-subset of a group
+#[derive(Debug, Clone)]
+pub struct SubsetKeys {
+    /// Number of signers each spend needs
+    threshold: usize,
+
+    /// Every qualifying subset, in lexicographic order
+    subsets: Vec<SubsetKey>,
+}
+
+impl SubsetKeys {
+    /// Derive the aggregate key of every `threshold`-subset of `participants`
+    ///
+    /// # Arguments
+    /// * `participants` - Every party, in an order all parties agree on
+    /// * `threshold` - Number of signers each spend needs (`k`)
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidThreshold` if `threshold` is 0 or exceeds the group size
+    /// Returns `Error::DuplicateParticipant` if a participant ID repeats
+    /// Returns `Error::Crypto` if a subset's keys aggregate to an invalid key
+    pub fn new(participants: &[Participant], threshold: usize) -> Result<Self> {
+        if threshold == 0 || threshold > participants.len() {
+            return Err(Error::InvalidThreshold {
+                threshold,
+                participants: participants.len(),
+            });
+        }
+        for (i, participant) in participants.iter().enumerate() {
+            if participants[..i].iter().any(|p| p.id() == participant.id()) {
+                return Err(Error::DuplicateParticipant {
+                    participant: participant.id(),
+                });
+            }
+        }
+
+        // Walk the k-combinations of indices in lexicographic order
+        let n = participants.len();
+        let mut indices: Vec<usize> = (0..threshold).collect();
+        let mut subsets = Vec::new();
+        loop {
+            let signers: Vec<_> = indices.iter().map(|&i| participants[i].clone()).collect();
+            let aggregate_key = Session::new(signers.clone()).aggregate_pubkeys()?;
+            subsets.push(SubsetKey {
+                signers,
+                aggregate_key,
+            });
+
+            let Some(position) = (0..threshold)
+                .rev()
+                .find(|&p| indices[p] < n - threshold + p)
+            else {
+                break;
+            };
+            indices[position] += 1;
+            for next in position + 1..threshold {
+                indices[next] = indices[next - 1] + 1;
+            }
+        }
+
+        Ok(Self { threshold, subsets })
+    }
+
+    /// Get the number of signers each spend needs
+    pub const fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// Get every qualifying subset
+    pub fn subsets(&self) -> &[SubsetKey] {
+        &self.subsets
+    }
+
+    /// Find the subset with the given aggregate key
+    pub fn find(&self, aggregate_key: &PublicKey) -> Option<&SubsetKey> {
+        self.subsets
+            .iter()
+            .find(|subset| subset.aggregate_key == *aggregate_key)
+    }
+
+    /// Find the first subset made up only of available signers
+    pub fn for_signers(&self, available: &[ParticipantId]) -> Option<&SubsetKey> {
+        self.subsets.iter().find(|subset| {
+            subset
+                .signers
+                .iter()
+                .all(|signer| available.contains(&signer.id()))
+        })
+    }
+
+    /// Find the subset a shared output is locked to
+    ///
+    /// # Returns
+    /// The subset whose signers can spend `output`, or `None` if it is not
+    /// locked to any of this group's subset keys
+    ///
+    /// # Errors
+    /// Returns `Error::Crypto` if an expected commitment cannot be created
+    pub fn locked_to(&self, output: &SharedOutput) -> Result<Option<&SubsetKey>> {
+        for subset in &self.subsets {
+            let expected = SharedOutput::from_session(&subset.session(), output.value())?;
+            if expected.commitment() == output.commitment() {
+                return Ok(Some(subset));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+    use secp256k1zkp::{Secp256k1, SecretKey};
+
+    fn group(n: u32) -> (Vec<Participant>, Vec<SecretKey>) {
+        let secp = Secp256k1::new();
+        (1..=n)
+            .map(|i| {
+                let sk = SecretKey::new(&secp, &mut thread_rng());
+                let pk = PublicKey::from_secret_key(&secp, &sk).unwrap();
+                (Participant::new(ParticipantId::new(i), pk), sk)
+            })
+            .unzip()
+    }
+
+    #[test]
+    fn test_every_subset_enumerated() {
+        let (participants, _) = group(4);
+        let keys = SubsetKeys::new(&participants, 2).unwrap();
+
+        let ids: Vec<_> = keys
+            .subsets()
+            .iter()
+            .map(|s| {
+                s.signer_ids()
+                    .iter()
+                    .map(ParticipantId::inner)
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                vec![1, 2],
+                vec![1, 3],
+                vec![1, 4],
+                vec![2, 3],
+                vec![2, 4],
+                vec![3, 4]
+            ]
+        );
+        for (i, subset) in keys.subsets().iter().enumerate() {
+            assert_eq!(
+                keys.find(subset.aggregate_key()).unwrap().signer_ids(),
+                subset.signer_ids()
+            );
+            assert!(keys.subsets()[..i]
+                .iter()
+                .all(|other| other.aggregate_key() != subset.aggregate_key()));
+        }
+
+        assert_eq!(
+            SubsetKeys::new(&participants, 4).unwrap().subsets().len(),
+            1
+        );
+        assert_eq!(
+            SubsetKeys::new(&participants, 1).unwrap().subsets().len(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_locked_output_spent_by_its_subset() {
+        let (participants, secret_keys) = group(3);
+        let keys = SubsetKeys::new(&participants, 2).unwrap();
+        let (p1, p3) = (ParticipantId::new(1), ParticipantId::new(3));
+
+        // The buyer and the arbiter are around, so funds go to their key
+        let subset = keys.for_signers(&[p1, p3]).unwrap();
+        assert_eq!(subset.signer_ids(), vec![p1, p3]);
+        let session = subset.session();
+        let output = SharedOutput::from_session(&session, 5_000).unwrap();
+        assert_eq!(
+            keys.locked_to(&output).unwrap().unwrap().signer_ids(),
+            vec![p1, p3]
+        );
+
+        // Only those two sign
+        let message = [0x41u8; 32];
+        let rounds: Vec<_> = (0..2)
+            .map(|_| session.round1_generate_nonces().unwrap())
+            .collect();
+        let revealed: Vec<_> = rounds.iter().map(|r| *r.public_nonces()).collect();
+        let agg_nonce = session
+            .round2_aggregate_nonces(
+                &rounds.iter().map(|r| *r.commitment()).collect::<Vec<_>>(),
+                &revealed,
+            )
+            .unwrap();
+        let partials: Vec<_> = [&secret_keys[0], &secret_keys[2]]
+            .iter()
+            .zip(&rounds)
+            .map(|(sk, round1)| {
+                session
+                    .round3_partial_sign(
+                        &message,
+                        sk,
+                        round1.secret_nonce(),
+                        &agg_nonce,
+                        subset.aggregate_key(),
+                    )
+                    .unwrap()
+            })
+            .collect();
+        let signature = session
+            .aggregate_signatures(&message, &partials, &revealed, &agg_nonce)
+            .unwrap();
+        assert!(session.verify_signature(&message, &signature, subset.aggregate_key()));
+
+        let (strangers, _) = group(2);
+        let foreign = SharedOutput::from_session(&Session::new(strangers), 5_000).unwrap();
+        assert!(keys.locked_to(&foreign).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_invalid_groups_rejected() {
+        let (mut participants, _) = group(3);
+        assert!(matches!(
+            SubsetKeys::new(&participants, 0),
+            Err(Error::InvalidThreshold { .. })
+        ));
+        assert!(matches!(
+            SubsetKeys::new(&participants, 4),
+            Err(Error::InvalidThreshold { .. })
+        ));
+
+        participants.push(participants[1].clone());
+        assert!(matches!(
+            SubsetKeys::new(&participants, 2),
+            Err(Error::DuplicateParticipant { participant }) if participant == ParticipantId::new(2)
+        ));
+    }
+}
@@ -0,0 +1,927 @@
+//! Grin↔Bitcoin atomic swaps
+//!
+//! The seller holds Grin and wants BTC; the buyer holds BTC and wants Grin.
+//! The Grin side is a [`SharedOutput`](crate::spend::SharedOutput) of both
+//! parties, funded behind a co-signed refund ([`FundingBuilder`]). The buyer's
+//! redeem of that output is co-signed as an adaptor pre-signature locked to a
+//! point `T` whose secret `t` only the buyer knows. The BTC is locked to the
+//! same `T`: publishing the completed Grin redeem reveals `t` to the seller,
+//! who then claims the BTC. Either both legs go through or neither does.
+//!
+//! All co-signing happens up front, so afterwards [`Swap::poll`] only watches
+//! both chains and broadcasts the transaction each phase calls for. Each party
+//! runs its own [`Swap`]; the chains are reached through [`GrinChain`] and
+//! [`BitcoinChain`].
+//!
+//! The buyer only redeems before the Grin refund height, and the seller only
+//! funds while the BTC lock is still live. The BTC refund height must leave
+//! the seller time to claim after the Grin refund height has passed, so
+//! [`Swap::new`] converts both timeouts to seconds from the current heights
+//! and requires the BTC refund to come [`CLAIM_WINDOW`] seconds later.
+//!
+//! # Algorithm
+//! ```text
+//! Negotiate:  co-sign Grin refund   C -> seller, HeightLocked { grin_refund }
+//!             co-sign Grin funding  seller inputs -> C + change
+//!             pre-sign Grin redeem  C -> buyer, adaptor point T = t*G
+//! Lock:       buyer locks BTC to (seller + t) or buyer after btc_refund
+//!             seller sees BTC lock, broadcasts Grin funding
+//! Redeem:     buyer completes redeem with t, broadcasts before grin_refund
+//!             seller finds the redeem kernel, extracts t, claims BTC
+//! Timeouts:   grin_refund reached unredeemed -> seller broadcasts Grin refund
+//!             btc_refund reached unclaimed   -> buyer refunds BTC
+//! ```
+
+use secp256k1zkp::pedersen::Commitment;
+use secp256k1zkp::{PublicKey, Secp256k1, SecretKey};
+
+use crate::adaptor::PreSignature;
+use crate::error::{Error, Result};
+use crate::funding::FundingBuilder;
+use crate::offset::OffsetShare;
+use crate::participant::Participant;
+use crate::session::Session;
+use crate::spend::SpendBuilder;
+use crate::transaction::{KernelFeatures, Output, Transaction, TxKernel};
+use crate::validation::validate;
+
+/// Target Grin block time in seconds
+pub const GRIN_BLOCK_TIME: u64 = 60;
+
+/// Target Bitcoin block time in seconds
+pub const BITCOIN_BLOCK_TIME: u64 = 600;
+
+/// Seconds the seller has to claim the BTC after the Grin refund height
+pub const CLAIM_WINDOW: u64 = 6 * BITCOIN_BLOCK_TIME;
+
+/// Side of the swap a party is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// Sells Grin for BTC
+    Seller,
+    /// Buys Grin with BTC and holds the adaptor secret
+    Buyer,
+}
+
+/// Phase of a swap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapState {
+    /// Refund, funding and redeem are still being co-signed
+    Negotiating,
+    /// Everything is co-signed; waiting for the BTC lock
+    Negotiated,
+    /// BTC is locked; waiting for the Grin funding
+    BitcoinLocked,
+    /// Both legs are locked; waiting for the buyer's Grin redeem
+    GrinLocked,
+    /// Grin went to the buyer and revealed `t`; waiting for the BTC claim
+    GrinRedeemed,
+    /// Both legs went through
+    Completed,
+    /// Grin went back to the seller; waiting for the BTC refund
+    GrinRefunded,
+    /// Every locked leg went back to its owner
+    Refunded,
+}
+
+/// BTC locked to the adaptor point
+///
+/// Stands for a script paying to the seller's key together with `t`, or to
+/// the buyer after a timelock.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitcoinLock {
+    /// Locked amount in satoshis
+    amount: u64,
+
+    /// Adaptor point `T` whose secret unlocks the redeem path
+    point: PublicKey,
+
+    /// Seller's address, paid on redeem
+    redeem_address: String,
+
+    /// Buyer's address, paid on refund
+    refund_address: String,
+
+    /// Height from which the buyer can refund
+    refund_height: u64,
+}
+
+impl BitcoinLock {
+    /// Describe a BTC lock
+    pub const fn new(
+        amount: u64,
+        point: PublicKey,
+        redeem_address: String,
+        refund_address: String,
+        refund_height: u64,
+    ) -> Self {
+        Self {
+            amount,
+            point,
+            redeem_address,
+            refund_address,
+            refund_height,
+        }
+    }
+
+    /// Get the locked amount in satoshis
+    pub const fn amount(&self) -> u64 {
+        self.amount
+    }
+
+    /// Get the adaptor point
+    pub const fn point(&self) -> &PublicKey {
+        &self.point
+    }
+
+    /// Get the seller's address
+    pub fn redeem_address(&self) -> &str {
+        &self.redeem_address
+    }
+
+    /// Get the buyer's address
+    pub fn refund_address(&self) -> &str {
+        &self.refund_address
+    }
+
+    /// Get the height from which the buyer can refund
+    pub const fn refund_height(&self) -> u64 {
+        self.refund_height
+    }
+}
+
+/// On-chain status of a BTC lock
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LockStatus {
+    /// Locked and unspent
+    Locked,
+    /// Claimed by the seller; the witness reveals the adaptor secret
+    Redeemed(SecretKey),
+    /// Returned to the buyer
+    Refunded,
+}
+
+/// What the swap needs from a Grin node
+pub trait GrinChain {
+    /// Get the current chain height
+    fn height(&self) -> u64;
+
+    /// Broadcast a transaction
+    ///
+    /// # Errors
+    /// Returns an error if the transaction is rejected
+    fn post(&mut self, tx: &Transaction) -> Result<()>;
+
+    /// Check whether an output is unspent
+    fn is_unspent(&self, commitment: &Commitment) -> bool;
+
+    /// Find a mined kernel by its excess
+    fn kernel(&self, excess: &Commitment) -> Option<TxKernel>;
+}
+
+/// What the swap needs from a Bitcoin node and wallet
+pub trait BitcoinChain {
+    /// Get the current chain height
+    fn height(&self) -> u64;
+
+    /// Lock the buyer's BTC
+    ///
+    /// # Errors
+    /// Returns an error if the lock cannot be broadcast
+    fn lock(&mut self, lock: &BitcoinLock) -> Result<()>;
+
+    /// Get the status of a lock, or `None` if it is not on chain
+    fn status(&self, lock: &BitcoinLock) -> Option<LockStatus>;
+
+    /// Claim a lock for the seller with the adaptor secret
+    ///
+    /// # Errors
+    /// Returns an error if the claim is rejected
+    fn redeem(&mut self, lock: &BitcoinLock, secret: &SecretKey) -> Result<()>;
+
+    /// Return a lock to the buyer after its refund height
+    ///
+    /// # Errors
+    /// Returns an error if the refund is rejected
+    fn refund(&mut self, lock: &BitcoinLock) -> Result<()>;
+}
+
+/// Co-signed Grin redeem, ready to be completed with `t`
+#[derive(Debug, Clone)]
+struct Redeem {
+    /// Kernel session participants
+    participants: Vec<Participant>,
+
+    /// Offset shares of the redeem
+    offset_shares: Vec<OffsetShare>,
+
+    /// Pre-signature locked to the BTC lock's point
+    pre_signature: PreSignature,
+
+    /// Kernel excess the redeem will carry
+    excess: Commitment,
+}
+
+/// One party's view of a Grin↔BTC swap
+#[derive(Debug, Clone)]
+pub struct Swap {
+    /// Side this party is on
+    role: Role,
+
+    /// Current phase
+    state: SwapState,
+
+    /// Funding of the shared output, with its refund
+    funding: FundingBuilder,
+
+    /// Co-signed funding transaction
+    funding_tx: Option<Transaction>,
+
+    /// Spend of the shared output to the buyer
+    redeem: SpendBuilder,
+
+    /// Co-signed redeem data
+    signed_redeem: Option<Redeem>,
+
+    /// BTC leg
+    bitcoin_lock: BitcoinLock,
+
+    /// Adaptor secret: the buyer's from the start, the seller's once extracted
+    secret: Option<SecretKey>,
+}
+
+impl Swap {
+    /// Start negotiating a swap
+    ///
+    /// # Arguments
+    /// * `role` - Side this party is on
+    /// * `funding` - Seller's funding of the shared output; its lock height is
+    ///   the Grin refund height
+    /// * `redeem_fee` - Fee of the redeem transaction
+    /// * `redeem_output` - Buyer's output receiving the Grin
+    /// * `bitcoin_lock` - BTC leg, locked to the adaptor point
+    /// * `grin_height` - Current Grin chain height
+    /// * `bitcoin_height` - Current Bitcoin chain height
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidSwap` if either refund height has passed, or the
+    /// BTC refund does not come at least [`CLAIM_WINDOW`] seconds after the
+    /// Grin refund
+    pub fn new(
+        role: Role,
+        funding: FundingBuilder,
+        redeem_fee: u64,
+        redeem_output: Output,
+        bitcoin_lock: BitcoinLock,
+        grin_height: u64,
+        bitcoin_height: u64,
+    ) -> Result<Self> {
+        let grin_blocks = funding
+            .lock_height()
+            .checked_sub(grin_height)
+            .filter(|blocks| *blocks > 0)
+            .ok_or_else(|| Error::InvalidSwap("Grin refund height has passed".into()))?;
+        let bitcoin_blocks = bitcoin_lock
+            .refund_height
+            .checked_sub(bitcoin_height)
+            .filter(|blocks| *blocks > 0)
+            .ok_or_else(|| Error::InvalidSwap("BTC refund height has passed".into()))?;
+        let grin_refund = grin_blocks.saturating_mul(GRIN_BLOCK_TIME);
+        let bitcoin_refund = bitcoin_blocks.saturating_mul(BITCOIN_BLOCK_TIME);
+        if bitcoin_refund < grin_refund.saturating_add(CLAIM_WINDOW) {
+            return Err(Error::InvalidSwap(format!(
+                "BTC refund in {bitcoin_refund}s leaves no claim window after the Grin refund in {grin_refund}s"
+            )));
+        }
+
+        let redeem = SpendBuilder::new(
+            *funding.shared_output(),
+            KernelFeatures::Plain { fee: redeem_fee },
+        )
+        .output(redeem_output);
+        Ok(Self {
+            role,
+            state: SwapState::Negotiating,
+            funding,
+            funding_tx: None,
+            redeem,
+            signed_redeem: None,
+            bitcoin_lock,
+            secret: None,
+        })
+    }
+
+    /// Give the buyer's swap its adaptor secret
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidAdaptorSecret` if `t * G` is not the lock's point
+    pub fn with_secret(mut self, secp: &Secp256k1, secret: SecretKey) -> Result<Self> {
+        if PublicKey::from_secret_key(secp, &secret)? != self.bitcoin_lock.point {
+            return Err(Error::InvalidAdaptorSecret);
+        }
+        self.secret = Some(secret);
+        Ok(self)
+    }
+
+    /// Get the side this party is on
+    pub const fn role(&self) -> Role {
+        self.role
+    }
+
+    /// Get the current phase
+    pub const fn state(&self) -> SwapState {
+        self.state
+    }
+
+    /// Get the funding builder, to co-sign the refund and the funding
+    pub const fn funding(&self) -> &FundingBuilder {
+        &self.funding
+    }
+
+    /// Get the redeem to pre-sign, with [`SharedOutput::spend_blinding`](crate::spend::SharedOutput::spend_blinding)
+    pub const fn redeem(&self) -> &SpendBuilder {
+        &self.redeem
+    }
+
+    /// Get the BTC leg
+    pub const fn bitcoin_lock(&self) -> &BitcoinLock {
+        &self.bitcoin_lock
+    }
+
+    /// Get the adaptor secret, if known
+    pub const fn secret(&self) -> Option<&SecretKey> {
+        self.secret.as_ref()
+    }
+
+    /// Check and store the co-signed Grin refund
+    ///
+    /// # Errors
+    /// Returns the errors of [`FundingBuilder::store_refund`]
+    pub fn store_refund(&mut self, secp: &Secp256k1, refund: Transaction) -> Result<()> {
+        self.funding.store_refund(secp, refund)?;
+        self.check_negotiated();
+        Ok(())
+    }
+
+    /// Check and store the co-signed Grin funding
+    ///
+    /// # Errors
+    /// Returns `Error::MissingRefund` until the refund is stored
+    /// Returns `Error::InvalidSwap` if the transaction does not create the shared output
+    /// Returns `Error::ValidationFailed` if the transaction does not validate
+    pub fn store_funding(&mut self, secp: &Secp256k1, funding: Transaction) -> Result<()> {
+        if self.funding.signed_refund().is_none() {
+            return Err(Error::MissingRefund);
+        }
+        let shared = self.funding.shared_output().commitment();
+        if !funding.outputs().iter().any(|o| o.commitment() == shared) {
+            return Err(Error::InvalidSwap(
+                "funding does not create the shared output".into(),
+            ));
+        }
+        validate(secp, &funding).into_result()?;
+
+        self.funding_tx = Some(funding);
+        self.check_negotiated();
+        Ok(())
+    }
+
+    /// Check and store the pre-signed Grin redeem
+    ///
+    /// # Arguments
+    /// * `kernel_session` - Kernel session of the redeem
+    /// * `offset_shares` - One offset share per participant
+    /// * `pre_signature` - Pre-signature over the redeem's kernel message
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidSwap` if the pre-signature is locked to another
+    /// point or does not verify
+    /// Returns the errors of [`SpendBuilder::balanced_offset`]
+    pub fn store_redeem(
+        &mut self,
+        kernel_session: &Session,
+        offset_shares: &[OffsetShare],
+        pre_signature: PreSignature,
+    ) -> Result<()> {
+        if *pre_signature.adaptor_point() != self.bitcoin_lock.point {
+            return Err(Error::InvalidSwap(
+                "redeem is locked to another point than the BTC".into(),
+            ));
+        }
+        self.redeem.balanced_offset(kernel_session, offset_shares)?;
+        let agg_pubkey = kernel_session.aggregate_pubkeys()?;
+        if !kernel_session.verify_pre_signature(
+            &self.redeem.kernel_message(),
+            &pre_signature,
+            &agg_pubkey,
+        ) {
+            return Err(Error::InvalidSwap(
+                "redeem pre-signature does not verify".into(),
+            ));
+        }
+
+        self.signed_redeem = Some(Redeem {
+            participants: kernel_session.participants().to_vec(),
+            offset_shares: offset_shares.to_vec(),
+            pre_signature,
+            excess: Commitment::from_pubkey(kernel_session.secp(), &agg_pubkey)?,
+        });
+        self.check_negotiated();
+        Ok(())
+    }
+
+    /// Observe both chains and take this party's next step
+    ///
+    /// Moves at most one phase forward per call, broadcasting whatever the
+    /// phase calls for: the BTC lock, the Grin funding, the Grin redeem, the
+    /// BTC claim, or a refund once its height is reached.
+    ///
+    /// # Returns
+    /// The phase after this step
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidTransition` while the swap is still being negotiated
+    /// Returns `Error::InvalidSwap` if the buyer has no adaptor secret
+    /// Returns any error of the chains or of completing the redeem
+    pub fn poll(
+        &mut self,
+        grin: &mut impl GrinChain,
+        bitcoin: &mut impl BitcoinChain,
+    ) -> Result<SwapState> {
+        let lock = &self.bitcoin_lock;
+        let shared = *self.funding.shared_output().commitment();
+        let next = match (self.state, self.role) {
+            (SwapState::Negotiating, _) => {
+                return Err(Error::InvalidTransition(
+                    "swap is still being negotiated".into(),
+                ))
+            }
+            (SwapState::Negotiated, Role::Buyer) => {
+                if self.secret.is_none() {
+                    return Err(Error::InvalidSwap("buyer has no adaptor secret".into()));
+                }
+                bitcoin.lock(lock)?;
+                Some(SwapState::BitcoinLocked)
+            }
+            (SwapState::Negotiated, Role::Seller) => match bitcoin.status(lock) {
+                Some(LockStatus::Locked) => Some(SwapState::BitcoinLocked),
+                Some(LockStatus::Refunded) => Some(SwapState::Refunded),
+                _ => None,
+            },
+            (SwapState::BitcoinLocked, Role::Seller) => match bitcoin.status(lock) {
+                Some(LockStatus::Locked)
+                    if bitcoin.height() < lock.refund_height
+                        && grin.height() < self.funding.lock_height() =>
+                {
+                    grin.post(self.funding_tx()?)?;
+                    Some(SwapState::GrinLocked)
+                }
+                Some(LockStatus::Refunded) => Some(SwapState::Refunded),
+                _ => None,
+            },
+            (SwapState::BitcoinLocked, Role::Buyer) => {
+                if grin.is_unspent(&shared) {
+                    Some(SwapState::GrinLocked)
+                } else {
+                    refund_bitcoin(bitcoin, lock)?
+                }
+            }
+            (SwapState::GrinLocked, Role::Buyer) => {
+                if grin.height() < self.funding.lock_height() && grin.is_unspent(&shared) {
+                    grin.post(&self.complete_redeem()?)?;
+                    Some(SwapState::GrinRedeemed)
+                } else {
+                    refund_bitcoin(bitcoin, lock)?
+                }
+            }
+            (SwapState::GrinLocked, Role::Seller) => {
+                let redeem = self.signed_redeem()?;
+                if let Some(kernel) = grin.kernel(&redeem.excess) {
+                    let session = Session::new(redeem.participants.clone());
+                    self.secret = Some(
+                        session
+                            .extract_adaptor_secret(&redeem.pre_signature, kernel.excess_sig())?,
+                    );
+                    Some(SwapState::GrinRedeemed)
+                } else if let Some(refund) = self.funding.refund_at(grin.height()) {
+                    grin.post(refund)?;
+                    Some(SwapState::GrinRefunded)
+                } else {
+                    None
+                }
+            }
+            (SwapState::GrinRedeemed, Role::Seller) => {
+                let secret = self
+                    .secret
+                    .as_ref()
+                    .ok_or_else(|| Error::InvalidSwap("adaptor secret not extracted".into()))?;
+                bitcoin.redeem(lock, secret)?;
+                Some(SwapState::Completed)
+            }
+            (SwapState::GrinRedeemed, Role::Buyer) => {
+                matches!(bitcoin.status(lock), Some(LockStatus::Redeemed(_)))
+                    .then_some(SwapState::Completed)
+            }
+            (SwapState::GrinRefunded, _) => {
+                (bitcoin.status(lock) == Some(LockStatus::Refunded)).then_some(SwapState::Refunded)
+            }
+            (SwapState::Completed | SwapState::Refunded, _) => None,
+        };
+
+        if let Some(state) = next {
+            self.state = state;
+        }
+        Ok(self.state)
+    }
+
+    fn check_negotiated(&mut self) {
+        if self.state == SwapState::Negotiating
+            && self.funding.signed_refund().is_some()
+            && self.funding_tx.is_some()
+            && self.signed_redeem.is_some()
+        {
+            self.state = SwapState::Negotiated;
+        }
+    }
+
+    fn funding_tx(&self) -> Result<&Transaction> {
+        self.funding_tx
+            .as_ref()
+            .ok_or_else(|| Error::InvalidTransition("funding not co-signed".into()))
+    }
+
+    fn signed_redeem(&self) -> Result<&Redeem> {
+        self.signed_redeem
+            .as_ref()
+            .ok_or_else(|| Error::InvalidTransition("redeem not pre-signed".into()))
+    }
+
+    /// Complete the redeem pre-signature with the buyer's secret
+    fn complete_redeem(&self) -> Result<Transaction> {
+        let redeem = self.signed_redeem()?;
+        let secret = self
+            .secret
+            .as_ref()
+            .ok_or_else(|| Error::InvalidSwap("buyer has no adaptor secret".into()))?;
+        let session = Session::new(redeem.participants.clone());
+        let signature = session.complete_pre_signature(&redeem.pre_signature, secret)?;
+        self.redeem
+            .finalize_with_signature(&session, &redeem.offset_shares, signature)
+    }
+}
+
+/// Refund the BTC once its refund height is reached
+fn refund_bitcoin(
+    bitcoin: &mut impl BitcoinChain,
+    lock: &BitcoinLock,
+) -> Result<Option<SwapState>> {
+    if bitcoin.height() < lock.refund_height {
+        return Ok(None);
+    }
+    bitcoin.refund(lock)?;
+    Ok(Some(SwapState::Refunded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockBitcoinChain, MockGrinChain};
+    use crate::test_util::{co_sign, keyed_session};
+    use crate::transaction::{Input, OutputFeatures};
+
+    use rand::thread_rng;
+    use secp256k1zkp::ContextFlag;
+
+    const GRIN_REFUND: u64 = 20;
+    const BTC_REFUND: u64 = 40;
+
+    /// Both parties' swaps after negotiation, and the mock chains
+    struct Market {
+        secp: Secp256k1,
+        grin: MockGrinChain,
+        bitcoin: MockBitcoinChain,
+        seller: Swap,
+        buyer: Swap,
+        secret: SecretKey,
+        redeem_output: Output,
+        refund_output: Output,
+        funding: Session,
+        funding_keys: Vec<SecretKey>,
+        redeem_blind: SecretKey,
+    }
+
+    impl Market {
+        /// Pre-sign the redeem to the buyer, locked to `point`
+        fn pre_sign_redeem(&self, point: &PublicKey) -> (Session, Vec<OffsetShare>, PreSignature) {
+            let (kernel, excess_keys) = keyed_session(&self.secp, 2);
+            let output = self.seller.funding().shared_output();
+            let blindings = [
+                output
+                    .spend_blinding(&self.funding, &self.funding_keys[0], &[])
+                    .unwrap(),
+                output
+                    .spend_blinding(
+                        &self.funding,
+                        &self.funding_keys[1],
+                        std::slice::from_ref(&self.redeem_blind),
+                    )
+                    .unwrap(),
+            ];
+            let message = self.seller.redeem().kernel_message();
+            let (shares, partials, revealed, agg_nonce) =
+                co_sign(&kernel, &excess_keys, &blindings, &message, Some(point));
+            let pre_signature = kernel
+                .aggregate_pre_signatures(&message, &partials, &revealed, &agg_nonce, point)
+                .unwrap();
+            (kernel, shares, pre_signature)
+        }
+
+        /// Co-sign the refund first and hand it to both parties
+        fn sign_refund(&mut self, refund_blind: &SecretKey) {
+            let output = *self.seller.funding().shared_output();
+            let (kernel, excess_keys) = keyed_session(&self.secp, 2);
+            let blindings = [
+                output
+                    .spend_blinding(
+                        &self.funding,
+                        &self.funding_keys[0],
+                        std::slice::from_ref(refund_blind),
+                    )
+                    .unwrap(),
+                output
+                    .spend_blinding(&self.funding, &self.funding_keys[1], &[])
+                    .unwrap(),
+            ];
+            let refund = self.seller.funding().refund();
+            let (shares, partials, revealed, agg_nonce) = co_sign(
+                &kernel,
+                &excess_keys,
+                &blindings,
+                &refund.kernel_message(),
+                None,
+            );
+            let refund = refund
+                .finalize(&kernel, &shares, &partials, &revealed, &agg_nonce)
+                .unwrap();
+            self.seller
+                .store_refund(&self.secp, refund.clone())
+                .unwrap();
+            self.buyer.store_refund(&self.secp, refund).unwrap();
+        }
+
+        /// Co-sign the funding, with a stand-in for a joint range proof
+        fn sign_funding(&mut self, input_blind: SecretKey, change_blind: SecretKey) {
+            let (kernel, excess_keys) = keyed_session(&self.secp, 2);
+            let blindings = [
+                FundingBuilder::funding_blinding(
+                    &self.funding,
+                    &self.funding_keys[0],
+                    &[change_blind],
+                    &[input_blind],
+                )
+                .unwrap(),
+                FundingBuilder::funding_blinding(&self.funding, &self.funding_keys[1], &[], &[])
+                    .unwrap(),
+            ];
+            let message = self.seller.funding().kernel_message().unwrap();
+            let (shares, partials, revealed, agg_nonce) =
+                co_sign(&kernel, &excess_keys, &blindings, &message, None);
+            let shared_blind = self
+                .secp
+                .blind_sum(
+                    self.funding_keys
+                        .iter()
+                        .map(|k| self.funding.weighted_secret_key(k).unwrap())
+                        .collect(),
+                    vec![],
+                )
+                .unwrap();
+            let proof = *Output::create(&self.secp, 100, &shared_blind)
+                .unwrap()
+                .proof();
+            let funding_tx = self
+                .seller
+                .funding()
+                .finalize(&kernel, proof, &shares, &partials, &revealed, &agg_nonce)
+                .unwrap();
+            self.seller
+                .store_funding(&self.secp, funding_tx.clone())
+                .unwrap();
+            self.buyer.store_funding(&self.secp, funding_tx).unwrap();
+        }
+
+        /// Poll whichever parties are online, then mine a block on each chain
+        fn run(&mut self, blocks: u64, seller: bool, buyer: bool) {
+            for _ in 0..blocks {
+                if seller {
+                    self.seller.poll(&mut self.grin, &mut self.bitcoin).unwrap();
+                }
+                if buyer {
+                    self.buyer.poll(&mut self.grin, &mut self.bitcoin).unwrap();
+                }
+                self.grin.mine(1);
+                self.bitcoin.mine(1);
+            }
+        }
+    }
+
+    /// Seller swaps 100 Grin from a 150 input for the buyer's 50 000 sats
+    fn negotiate() -> Market {
+        let secp = Secp256k1::with_caps(ContextFlag::Commit);
+        let mut rng = thread_rng();
+        let (funding, funding_keys) = keyed_session(&secp, 2);
+        let [input_blind, change_blind, refund_blind, redeem_blind] =
+            std::array::from_fn(|_| SecretKey::new(&secp, &mut rng));
+
+        let mut grin = MockGrinChain::new();
+        let input_commit = secp.commit(150, input_blind.clone()).unwrap();
+        grin.add_output(input_commit);
+
+        let refund_output = Output::create(&secp, 99, &refund_blind).unwrap();
+        let redeem_output = Output::create(&secp, 99, &redeem_blind).unwrap();
+        let builder = FundingBuilder::new(
+            &funding,
+            100,
+            KernelFeatures::Plain { fee: 2 },
+            1,
+            GRIN_REFUND,
+            refund_output,
+        )
+        .unwrap()
+        .input(Input::new(OutputFeatures::Plain, input_commit))
+        .change(Output::create(&secp, 48, &change_blind).unwrap());
+
+        let secret = SecretKey::new(&secp, &mut rng);
+        let lock = BitcoinLock::new(
+            50_000,
+            PublicKey::from_secret_key(&secp, &secret).unwrap(),
+            "bc1-seller".into(),
+            "bc1-buyer".into(),
+            BTC_REFUND,
+        );
+        let seller = Swap::new(
+            Role::Seller,
+            builder.clone(),
+            1,
+            redeem_output,
+            lock.clone(),
+            0,
+            0,
+        )
+        .unwrap();
+        let buyer = Swap::new(Role::Buyer, builder, 1, redeem_output, lock, 0, 0)
+            .unwrap()
+            .with_secret(&secp, secret.clone())
+            .unwrap();
+
+        let mut market = Market {
+            secp,
+            grin,
+            bitcoin: MockBitcoinChain::new(),
+            seller,
+            buyer,
+            secret,
+            redeem_output,
+            refund_output,
+            funding,
+            funding_keys,
+            redeem_blind,
+        };
+        market.sign_refund(&refund_blind);
+        market.sign_funding(input_blind, change_blind);
+
+        // Finally the redeem, locked to the buyer's point
+        let m = &mut market;
+        let point = *m.seller.bitcoin_lock().point();
+        let (kernel, shares, pre_signature) = m.pre_sign_redeem(&point);
+        m.seller
+            .store_redeem(&kernel, &shares, pre_signature)
+            .unwrap();
+        m.buyer
+            .store_redeem(&kernel, &shares, pre_signature)
+            .unwrap();
+
+        assert_eq!(m.seller.state(), SwapState::Negotiated);
+        assert_eq!(m.buyer.state(), SwapState::Negotiated);
+        market
+    }
+
+    #[test]
+    fn test_swap_completes() {
+        let mut m = negotiate();
+        m.run(6, true, true);
+
+        assert_eq!(m.seller.state(), SwapState::Completed);
+        assert_eq!(m.buyer.state(), SwapState::Completed);
+        assert_eq!(m.seller.secret(), Some(&m.secret));
+        assert_eq!(
+            m.bitcoin.status(m.seller.bitcoin_lock()),
+            Some(LockStatus::Redeemed(m.secret.clone()))
+        );
+        assert!(m.grin.is_unspent(m.redeem_output.commitment()));
+        assert!(!m
+            .grin
+            .is_unspent(m.seller.funding().shared_output().commitment()));
+    }
+
+    #[test]
+    fn test_grin_refunded_when_buyer_stalls() {
+        let mut m = negotiate();
+
+        // The buyer locks BTC, the seller funds, then the buyer goes quiet
+        m.buyer.poll(&mut m.grin, &mut m.bitcoin).unwrap();
+        m.run(2, true, false);
+        assert_eq!(m.seller.state(), SwapState::GrinLocked);
+
+        m.run(GRIN_REFUND, true, false);
+        assert_eq!(m.seller.state(), SwapState::GrinRefunded);
+        assert!(m.grin.is_unspent(m.refund_output.commitment()));
+
+        // Too late to redeem: the buyer can only wait for the BTC refund
+        m.run(BTC_REFUND - GRIN_REFUND - 3, true, true);
+        assert_eq!(m.buyer.state(), SwapState::BitcoinLocked);
+        m.run(3, true, true);
+        assert_eq!(m.buyer.state(), SwapState::Refunded);
+        assert_eq!(m.seller.state(), SwapState::Refunded);
+        assert!(m.seller.secret().is_none());
+        assert!(!m.grin.is_unspent(m.redeem_output.commitment()));
+    }
+
+    #[test]
+    fn test_bitcoin_refunded_when_seller_stalls() {
+        let mut m = negotiate();
+
+        // The redeem must be locked to the BTC's point
+        let other =
+            PublicKey::from_secret_key(&m.secp, &SecretKey::new(&m.secp, &mut thread_rng()))
+                .unwrap();
+        let (kernel, shares, pre_signature) = m.pre_sign_redeem(&other);
+        assert!(matches!(
+            m.buyer.store_redeem(&kernel, &shares, pre_signature),
+            Err(Error::InvalidSwap(_))
+        ));
+
+        m.run(BTC_REFUND - 1, false, true);
+        assert_eq!(m.buyer.state(), SwapState::BitcoinLocked);
+        assert!(matches!(
+            m.bitcoin.refund(m.buyer.bitcoin_lock()),
+            Err(Error::ChainRejected(_))
+        ));
+
+        m.run(2, false, true);
+        assert_eq!(m.buyer.state(), SwapState::Refunded);
+        assert_eq!(
+            m.bitcoin.status(m.buyer.bitcoin_lock()),
+            Some(LockStatus::Refunded)
+        );
+
+        // Waking up now, the seller does not fund a dead swap
+        m.run(1, true, false);
+        assert_eq!(m.seller.state(), SwapState::Refunded);
+        assert!(!m
+            .grin
+            .is_unspent(m.seller.funding().shared_output().commitment()));
+    }
+
+    #[test]
+    fn test_refund_heights_checked() {
+        let m = negotiate();
+        let point = *m.seller.bitcoin_lock().point();
+        let swap = |refund_height, grin_height, bitcoin_height| {
+            let lock = BitcoinLock::new(
+                50_000,
+                point,
+                "bc1-seller".into(),
+                "bc1-buyer".into(),
+                refund_height,
+            );
+            Swap::new(
+                Role::Seller,
+                m.seller.funding().clone(),
+                1,
+                m.redeem_output,
+                lock,
+                grin_height,
+                bitcoin_height,
+            )
+        };
+
+        // The Grin refund is 20 minutes away; the BTC refund needs an hour more
+        assert!(matches!(swap(7, 0, 0), Err(Error::InvalidSwap(_))));
+        assert!(swap(8, 0, 0).is_ok());
+
+        // Both timeouts count from the current heights
+        assert!(matches!(
+            swap(BTC_REFUND, 0, BTC_REFUND - 7),
+            Err(Error::InvalidSwap(_))
+        ));
+        assert!(matches!(
+            swap(BTC_REFUND, GRIN_REFUND, 0),
+            Err(Error::InvalidSwap(_))
+        ));
+        assert!(matches!(
+            swap(BTC_REFUND, 0, BTC_REFUND),
+            Err(Error::InvalidSwap(_))
+        ));
+    }
+}
@@ -0,0 +1,221 @@
+//! Fixtures shared by the unit tests
+//!
+//! Sessions over fresh keys and complete kernel signing runs, so each test
+//! module only sets up what is particular to it.
+
+use rand::thread_rng;
+use secp256k1zkp::{PublicKey, Secp256k1, SecretKey};
+
+use crate::error::Result;
+use crate::frost::{self, KeyPackage, PublicKeyPackage, SignatureShare, SigningPackage};
+use crate::offset::OffsetShare;
+use crate::participant::Participant;
+use crate::policy::SpendRequest;
+use crate::session::Session;
+use crate::spend::{SharedOutput, SpendBuilder};
+use crate::transaction::{KernelFeatures, Output, Transaction};
+use crate::types::{PartialSignature, ParticipantId};
+use crate::vss::trusted_dealer_keygen;
+
+/// Session over `n` fresh keys, with participant ids 1 to `n`
+pub fn keyed_session(secp: &Secp256k1, n: u32) -> (Session, Vec<SecretKey>) {
+    let keys: Vec<_> = (0..n)
+        .map(|_| SecretKey::new(secp, &mut thread_rng()))
+        .collect();
+    let participants = keys
+        .iter()
+        .zip(1..)
+        .map(|(k, i)| {
+            let pk = PublicKey::from_secret_key(secp, k).unwrap();
+            Participant::new(ParticipantId::new(i), pk)
+        })
+        .collect();
+    (Session::new(participants), keys)
+}
+
+/// Offset shares, partial signatures, revealed nonces and aggregate nonce
+pub type CoSigned = (
+    Vec<OffsetShare>,
+    Vec<PartialSignature>,
+    Vec<(PublicKey, PublicKey)>,
+    PublicKey,
+);
+
+/// Partial signatures, revealed nonces and aggregate nonce
+pub type Signed = (
+    Vec<PartialSignature>,
+    Vec<(PublicKey, PublicKey)>,
+    PublicKey,
+);
+
+/// Run all three rounds over `message`, pre-signing if `adaptor_point` is given
+pub fn sign_all(
+    session: &Session,
+    secret_keys: &[SecretKey],
+    message: &[u8; 32],
+    adaptor_point: Option<&PublicKey>,
+) -> Signed {
+    let rounds: Vec<_> = secret_keys
+        .iter()
+        .map(|_| session.round1_generate_nonces().unwrap())
+        .collect();
+    let revealed: Vec<_> = rounds.iter().map(|r| *r.public_nonces()).collect();
+    let agg_nonce = session
+        .round2_aggregate_nonces(
+            &rounds.iter().map(|r| *r.commitment()).collect::<Vec<_>>(),
+            &revealed,
+        )
+        .unwrap();
+    let agg_pubkey = session.aggregate_pubkeys().unwrap();
+    let partials = secret_keys
+        .iter()
+        .zip(&rounds)
+        .map(|(x, r)| {
+            adaptor_point.map_or_else(
+                || {
+                    session.round3_partial_sign(
+                        message,
+                        x,
+                        r.secret_nonce(),
+                        &agg_nonce,
+                        &agg_pubkey,
+                    )
+                },
+                |point| {
+                    session.round3_pre_sign(
+                        message,
+                        x,
+                        r.secret_nonce(),
+                        &agg_nonce,
+                        point,
+                        &agg_pubkey,
+                    )
+                },
+            )
+        })
+        .collect::<Result<_>>()
+        .unwrap();
+    (partials, revealed, agg_nonce)
+}
+
+/// Run a kernel session over `message`, with each signer's offset share
+pub fn co_sign(
+    session: &Session,
+    excess_keys: &[SecretKey],
+    blindings: &[SecretKey],
+    message: &[u8; 32],
+    adaptor_point: Option<&PublicKey>,
+) -> CoSigned {
+    let shares = session
+        .participants()
+        .iter()
+        .zip(excess_keys.iter().zip(blindings))
+        .map(|(p, (x, b))| OffsetShare::generate(session, p.id(), x, b).unwrap())
+        .collect();
+    let (partials, revealed, agg_nonce) = sign_all(session, excess_keys, message, adaptor_point);
+    (shares, partials, revealed, agg_nonce)
+}
+
+/// Co-sign a spend's kernel in `session` and finalize it
+pub fn finalize_spend(
+    session: &Session,
+    excess_keys: &[SecretKey],
+    builder: &SpendBuilder,
+    blindings: &[SecretKey],
+) -> Result<Transaction> {
+    let (shares, partials, revealed, agg_nonce) = co_sign(
+        session,
+        excess_keys,
+        blindings,
+        &builder.kernel_message(),
+        None,
+    );
+    builder.finalize(session, &shares, &partials, &revealed, &agg_nonce)
+}
+
+/// Request to spend a 1 000 shared output of `session`, `amount` of it leaving
+///
+/// The rest returns as change that the request has already opened.
+pub fn spend_request(
+    secp: &Secp256k1,
+    session: &Session,
+    features: KernelFeatures,
+    amount: u64,
+    counterparty: &str,
+) -> SpendRequest {
+    let blind = SecretKey::new(secp, &mut thread_rng());
+    let change = 1_000 - amount;
+    let spend = SpendBuilder::new(
+        SharedOutput::from_session(session, 1_000).unwrap(),
+        features,
+    )
+    .output(Output::create(secp, change, &blind).unwrap());
+    SpendRequest::new(session, &spend, counterparty)
+        .unwrap()
+        .change(secp, change, &blind)
+        .unwrap()
+}
+
+/// Spend a single-signer shared output into outputs of `values` plus the fee
+pub fn finished_tx(secp: &Secp256k1, features: KernelFeatures, values: &[u64]) -> Transaction {
+    let (funding, funding_keys) = keyed_session(secp, 1);
+    let (kernel, excess_keys) = keyed_session(secp, 1);
+    let total = values.iter().sum::<u64>() + features.fee();
+    let shared = SharedOutput::from_session(&funding, total).unwrap();
+
+    let blinds: Vec<_> = values
+        .iter()
+        .map(|_| SecretKey::new(secp, &mut thread_rng()))
+        .collect();
+    let builder = values
+        .iter()
+        .zip(&blinds)
+        .fold(SpendBuilder::new(shared, features), |b, (v, blind)| {
+            b.output(Output::create(secp, *v, blind).unwrap())
+        });
+    let blinding = shared
+        .spend_blinding(&funding, &funding_keys[0], &blinds)
+        .unwrap();
+    finalize_spend(&kernel, &excess_keys, &builder, &[blinding]).unwrap()
+}
+
+/// Deal a random key `t`-of-`n` to participants 1 to `n`
+pub fn split(secp: &Secp256k1, t: usize, n: u32) -> (Vec<KeyPackage>, PublicKeyPackage) {
+    let ids: Vec<_> = (1..=n).map(ParticipantId::new).collect();
+    let secret = SecretKey::new(secp, &mut thread_rng());
+    let (shares, public_keys) = trusted_dealer_keygen(secp, &secret, t, &ids).unwrap();
+    let keys = shares
+        .into_iter()
+        .map(|s| s.into_key_package(secp).unwrap())
+        .collect();
+    (keys, public_keys)
+}
+
+/// Run both FROST rounds for `signers` over `message`
+pub fn sign_with(
+    secp: &Secp256k1,
+    signers: &[&KeyPackage],
+    message: [u8; 32],
+) -> Result<(SigningPackage, Vec<SignatureShare>)> {
+    let nonces: Vec<_> = signers
+        .iter()
+        .map(|k| frost::commit(secp, k))
+        .collect::<Result<_>>()?;
+    let package = SigningPackage::new(message, nonces.iter().map(|n| *n.commitments()).collect())?;
+    let shares = signers
+        .iter()
+        .zip(nonces)
+        .map(|(k, n)| frost::sign(secp, &package, n, k))
+        .collect::<Result<_>>()?;
+    Ok((package, shares))
+}
+
+/// Sign a fixed message with `signers` and aggregate under `public_keys`
+pub fn try_sign(
+    secp: &Secp256k1,
+    signers: &[&KeyPackage],
+    public_keys: &PublicKeyPackage,
+) -> Result<()> {
+    let (package, shares) = sign_with(secp, signers, [7u8; 32])?;
+    frost::aggregate(secp, &package, &shares, public_keys).map(|_| ())
+}
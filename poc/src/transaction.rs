@@ -0,0 +1,760 @@
+//! Grin transaction building blocks
+//!
+//! Minimal mirrors of the types in `grin_core::core::transaction`, holding
+//! exactly what a multisig spend needs: inputs and outputs as Pedersen
+//! commitments, kernels with their co-signed excess signature and the
+//! transaction offset.
+//!
+//! # Kernel Message
+//! ```text
+//! Plain:             blake2b-256(0 || fee)
+//! Coinbase:          blake2b-256(1)
+//! HeightLocked:      blake2b-256(2 || fee || lock_height)
+//! NoRecentDuplicate: blake2b-256(3 || fee || relative_height)
+//! ```
+//! Integers are big-endian; `relative_height` is a `u16`, everything else a `u64`.
+
+use blake2::digest::consts::U32;
+use blake2::{Blake2b, Digest};
+use rand::thread_rng;
+use secp256k1zkp::constants::{MAX_PROOF_SIZE, PEDERSEN_COMMITMENT_SIZE};
+use secp256k1zkp::pedersen::{Commitment, RangeProof};
+use secp256k1zkp::{Secp256k1, SecretKey, Signature};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::ser::{hex_commitment, hex_rangeproof, hex_seckey, hex_signature};
+
+/// Blake2b with a 256-bit output, as used for Grin hashes
+type Blake2b256 = Blake2b<U32>;
+
+/// Consensus weight of an input
+pub const INPUT_WEIGHT: u64 = 1;
+
+/// Consensus weight of an output
+pub const OUTPUT_WEIGHT: u64 = 21;
+
+/// Consensus weight of a kernel
+pub const KERNEL_WEIGHT: u64 = 3;
+
+/// Maximum weight of a block
+pub const MAX_BLOCK_WEIGHT: u64 = 40_000;
+
+/// Maximum weight of a transaction, leaving room for the coinbase output and kernel
+pub const MAX_TX_WEIGHT: u64 = MAX_BLOCK_WEIGHT - OUTPUT_WEIGHT - KERNEL_WEIGHT;
+
+/// Output features
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutputFeatures {
+    /// Plain output
+    Plain,
+    /// Coinbase output
+    Coinbase,
+}
+
+impl OutputFeatures {
+    /// Feature byte used in Grin's serialization
+    pub const fn as_u8(self) -> u8 {
+        match self {
+            Self::Plain => 0,
+            Self::Coinbase => 1,
+        }
+    }
+
+    fn read(reader: &mut ByteReader<'_>) -> Result<Self> {
+        match reader.u8()? {
+            0 => Ok(Self::Plain),
+            1 => Ok(Self::Coinbase),
+            other => Err(Error::Serialization(format!(
+                "unknown output features {other}"
+            ))),
+        }
+    }
+}
+
+/// Kernel features, carrying the fee and any feature specific data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KernelFeatures {
+    /// Plain kernel
+    Plain {
+        /// Transaction fee
+        fee: u64,
+    },
+    /// Coinbase kernel (no fee)
+    Coinbase,
+    /// Kernel that is not valid before `lock_height`
+    HeightLocked {
+        /// Transaction fee
+        fee: u64,
+        /// Absolute block height the kernel is locked until
+        lock_height: u64,
+    },
+    /// No-recent-duplicate kernel, relatively locked to an earlier identical excess
+    NoRecentDuplicate {
+        /// Transaction fee
+        fee: u64,
+        /// Blocks that must pass since an earlier kernel with the same excess
+        relative_height: u16,
+    },
+}
+
+impl KernelFeatures {
+    /// Feature byte used in Grin's serialization
+    pub const fn as_u8(&self) -> u8 {
+        match self {
+            Self::Plain { .. } => 0,
+            Self::Coinbase => 1,
+            Self::HeightLocked { .. } => 2,
+            Self::NoRecentDuplicate { .. } => 3,
+        }
+    }
+
+    /// Fee paid by this kernel
+    pub const fn fee(&self) -> u64 {
+        match self {
+            Self::Plain { fee }
+            | Self::HeightLocked { fee, .. }
+            | Self::NoRecentDuplicate { fee, .. } => *fee,
+            Self::Coinbase => 0,
+        }
+    }
+
+    /// Message the kernel excess signature commits to
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use grin_multisig_poc::transaction::KernelFeatures;
+    ///
+    /// let plain = KernelFeatures::Plain { fee: 2 }.kernel_sig_msg();
+    /// let locked = KernelFeatures::HeightLocked { fee: 2, lock_height: 100 }.kernel_sig_msg();
+    /// assert_ne!(plain, locked);
+    /// ```
+    pub fn kernel_sig_msg(&self) -> [u8; 32] {
+        let mut hasher = Blake2b256::new();
+        hasher.update([self.as_u8()]);
+        match self {
+            Self::Plain { fee } => hasher.update(fee.to_be_bytes()),
+            Self::Coinbase => {}
+            Self::HeightLocked { fee, lock_height } => {
+                hasher.update(fee.to_be_bytes());
+                hasher.update(lock_height.to_be_bytes());
+            }
+            Self::NoRecentDuplicate {
+                fee,
+                relative_height,
+            } => {
+                hasher.update(fee.to_be_bytes());
+                hasher.update(relative_height.to_be_bytes());
+            }
+        }
+        hasher.finalize().into()
+    }
+
+    /// Fixed-size encoding used when hashing kernels
+    fn write_v1(&self, buf: &mut Vec<u8>) {
+        buf.push(self.as_u8());
+        buf.extend_from_slice(&self.fee().to_be_bytes());
+        let data = match self {
+            Self::Plain { .. } | Self::Coinbase => 0,
+            Self::HeightLocked { lock_height, .. } => *lock_height,
+            Self::NoRecentDuplicate {
+                relative_height, ..
+            } => u64::from(*relative_height),
+        };
+        buf.extend_from_slice(&data.to_be_bytes());
+    }
+
+    /// Variable-size encoding used on the wire
+    fn write_v2(&self, buf: &mut Vec<u8>) {
+        buf.push(self.as_u8());
+        match self {
+            Self::Plain { fee } => buf.extend_from_slice(&fee.to_be_bytes()),
+            Self::Coinbase => {}
+            Self::HeightLocked { fee, lock_height } => {
+                buf.extend_from_slice(&fee.to_be_bytes());
+                buf.extend_from_slice(&lock_height.to_be_bytes());
+            }
+            Self::NoRecentDuplicate {
+                fee,
+                relative_height,
+            } => {
+                buf.extend_from_slice(&fee.to_be_bytes());
+                buf.extend_from_slice(&relative_height.to_be_bytes());
+            }
+        }
+    }
+
+    fn read_v2(reader: &mut ByteReader<'_>) -> Result<Self> {
+        match reader.u8()? {
+            0 => Ok(Self::Plain { fee: reader.u64()? }),
+            1 => Ok(Self::Coinbase),
+            2 => Ok(Self::HeightLocked {
+                fee: reader.u64()?,
+                lock_height: reader.u64()?,
+            }),
+            3 => Ok(Self::NoRecentDuplicate {
+                fee: reader.u64()?,
+                relative_height: reader.u16()?,
+            }),
+            other => Err(Error::Serialization(format!(
+                "unknown kernel features {other}"
+            ))),
+        }
+    }
+}
+
+/// A transaction input, spending an existing output commitment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Input {
+    /// Features of the output being spent
+    features: OutputFeatures,
+
+    /// Commitment of the output being spent
+    #[serde(with = "hex_commitment")]
+    commit: Commitment,
+}
+
+impl Input {
+    /// Create an input spending `commit`
+    pub const fn new(features: OutputFeatures, commit: Commitment) -> Self {
+        Self { features, commit }
+    }
+
+    /// Get the features of the spent output
+    pub const fn features(&self) -> OutputFeatures {
+        self.features
+    }
+
+    /// Get the spent commitment
+    pub const fn commitment(&self) -> &Commitment {
+        &self.commit
+    }
+
+    fn write(&self, buf: &mut Vec<u8>) {
+        buf.push(self.features.as_u8());
+        buf.extend_from_slice(&self.commit.0);
+    }
+
+    fn read(reader: &mut ByteReader<'_>) -> Result<Self> {
+        Ok(Self::new(
+            OutputFeatures::read(reader)?,
+            reader.commitment()?,
+        ))
+    }
+
+    fn hash(&self) -> [u8; 32] {
+        let mut buf = Vec::new();
+        self.write(&mut buf);
+        hash(&buf)
+    }
+}
+
+/// A transaction output with its range proof
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Output {
+    /// Output features
+    features: OutputFeatures,
+
+    /// Pedersen commitment `v*H + b*G`
+    #[serde(with = "hex_commitment")]
+    commit: Commitment,
+
+    /// Bulletproof that the committed value is in range
+    #[serde(with = "hex_rangeproof")]
+    proof: RangeProof,
+}
+
+impl Output {
+    /// Assemble an output from its parts
+    pub const fn new(features: OutputFeatures, commit: Commitment, proof: RangeProof) -> Self {
+        Self {
+            features,
+            commit,
+            proof,
+        }
+    }
+
+    /// Create a plain output owned by a single party
+    ///
+    /// # Arguments
+    /// * `secp` - Context with commitment capabilities
+    /// * `value` - Output value
+    /// * `blind` - Owner's blinding factor
+    ///
+    /// # Errors
+    /// Returns `Error::Crypto` if the commitment cannot be created
+    pub fn create(secp: &Secp256k1, value: u64, blind: &SecretKey) -> Result<Self> {
+        let mut rng = thread_rng();
+        let commit = secp.commit(value, blind.clone())?;
+        let proof = secp.bullet_proof(
+            value,
+            blind.clone(),
+            SecretKey::new(secp, &mut rng),
+            SecretKey::new(secp, &mut rng),
+            None,
+            None,
+        );
+
+        Ok(Self::new(OutputFeatures::Plain, commit, proof))
+    }
+
+    /// Get the output features
+    pub const fn features(&self) -> OutputFeatures {
+        self.features
+    }
+
+    /// Get the output commitment
+    pub const fn commitment(&self) -> &Commitment {
+        &self.commit
+    }
+
+    /// Get the range proof
+    pub const fn proof(&self) -> &RangeProof {
+        &self.proof
+    }
+
+    fn write(&self, buf: &mut Vec<u8>) {
+        buf.push(self.features.as_u8());
+        buf.extend_from_slice(&self.commit.0);
+        buf.extend_from_slice(&(self.proof.plen as u64).to_be_bytes());
+        buf.extend_from_slice(self.proof.as_ref());
+    }
+
+    fn read(reader: &mut ByteReader<'_>) -> Result<Self> {
+        let features = OutputFeatures::read(reader)?;
+        let commit = reader.commitment()?;
+        let plen = usize::try_from(reader.u64()?)
+            .ok()
+            .filter(|len| *len <= MAX_PROOF_SIZE)
+            .ok_or_else(|| Error::Serialization("range proof too large".into()))?;
+        let mut proof = [0u8; MAX_PROOF_SIZE];
+        proof[..plen].copy_from_slice(reader.fixed(plen)?);
+        Ok(Self::new(features, commit, RangeProof { proof, plen }))
+    }
+
+    /// Outputs are ordered by the hash of their features and commitment
+    pub(crate) fn hash(&self) -> [u8; 32] {
+        let mut buf = vec![self.features.as_u8()];
+        buf.extend_from_slice(&self.commit.0);
+        hash(&buf)
+    }
+}
+
+/// A transaction kernel: fee, lock data and the signed excess
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxKernel {
+    /// Kernel features
+    features: KernelFeatures,
+
+    /// Kernel excess, the public key the signature is valid for
+    #[serde(with = "hex_commitment")]
+    excess: Commitment,
+
+    /// Signature over `features.kernel_sig_msg()`
+    #[serde(with = "hex_signature")]
+    excess_sig: Signature,
+}
+
+impl TxKernel {
+    /// Create a kernel from its features, excess and signature
+    pub const fn new(features: KernelFeatures, excess: Commitment, excess_sig: Signature) -> Self {
+        Self {
+            features,
+            excess,
+            excess_sig,
+        }
+    }
+
+    /// Get the kernel features
+    pub const fn features(&self) -> &KernelFeatures {
+        &self.features
+    }
+
+    /// Get the kernel excess
+    pub const fn excess(&self) -> &Commitment {
+        &self.excess
+    }
+
+    /// Get the excess signature
+    pub const fn excess_sig(&self) -> &Signature {
+        &self.excess_sig
+    }
+
+    fn write(&self, buf: &mut Vec<u8>) {
+        self.features.write_v2(buf);
+        buf.extend_from_slice(&self.excess.0);
+        buf.extend_from_slice(&self.excess_sig.to_raw_data());
+    }
+
+    fn read(reader: &mut ByteReader<'_>) -> Result<Self> {
+        let features = KernelFeatures::read_v2(reader)?;
+        let excess = reader.commitment()?;
+        let mut sig = [0u8; 64];
+        sig.copy_from_slice(reader.fixed(64)?);
+        Ok(Self::new(features, excess, Signature::from_raw_data(&sig)?))
+    }
+
+    /// Kernels are hashed with the fixed-size feature encoding
+    fn hash(&self) -> [u8; 32] {
+        let mut buf = Vec::new();
+        self.features.write_v1(&mut buf);
+        buf.extend_from_slice(&self.excess.0);
+        buf.extend_from_slice(&self.excess_sig.to_raw_data());
+        hash(&buf)
+    }
+}
+
+/// Inputs, outputs and kernels of a transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TransactionBody {
+    inputs: Vec<Input>,
+    outputs: Vec<Output>,
+    kernels: Vec<TxKernel>,
+}
+
+/// A complete Grin transaction
+///
+/// Serializes to the JSON layout of `grin_core::core::Transaction`, which is
+/// what a node's `push_transaction` expects, and to Grin's binary layout
+/// (protocol version 2, inputs carrying their features):
+///
+/// ```text
+/// offset (32) || n_inputs u64 || n_outputs u64 || n_kernels u64
+/// input:  features u8 || commit (33)
+/// output: features u8 || commit (33) || proof_len u64 || proof
+/// kernel: features u8 || [fee u64] || [lock_height u64 | relative_height u16]
+///         || excess (33) || signature (64)
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transaction {
+    /// Kernel offset
+    #[serde(with = "hex_seckey")]
+    offset: SecretKey,
+
+    /// Inputs, outputs and kernels
+    body: TransactionBody,
+}
+
+impl Transaction {
+    /// Assemble a transaction from its parts
+    ///
+    /// Inputs, outputs and kernels are sorted by hash, as Grin requires.
+    pub fn new(
+        offset: SecretKey,
+        mut inputs: Vec<Input>,
+        mut outputs: Vec<Output>,
+        mut kernels: Vec<TxKernel>,
+    ) -> Self {
+        inputs.sort_by_cached_key(Input::hash);
+        outputs.sort_by_cached_key(Output::hash);
+        kernels.sort_by_cached_key(TxKernel::hash);
+        Self {
+            offset,
+            body: TransactionBody {
+                inputs,
+                outputs,
+                kernels,
+            },
+        }
+    }
+
+    /// Get the kernel offset
+    pub const fn offset(&self) -> &SecretKey {
+        &self.offset
+    }
+
+    /// Get the inputs
+    pub fn inputs(&self) -> &[Input] {
+        &self.body.inputs
+    }
+
+    /// Get the outputs
+    pub fn outputs(&self) -> &[Output] {
+        &self.body.outputs
+    }
+
+    /// Get the kernels
+    pub fn kernels(&self) -> &[TxKernel] {
+        &self.body.kernels
+    }
+
+    /// Consensus weight: `inputs*1 + outputs*21 + kernels*3`
+    pub const fn weight(&self) -> u64 {
+        weight_by_iok(
+            self.body.inputs.len() as u64,
+            self.body.outputs.len() as u64,
+            self.body.kernels.len() as u64,
+        )
+    }
+
+    /// Total fee paid by the transaction's kernels
+    pub fn fee(&self) -> u64 {
+        self.body.kernels.iter().fold(0, |total, kernel| {
+            total.saturating_add(kernel.features.fee())
+        })
+    }
+
+    /// Serialize to Grin's transaction JSON
+    ///
+    /// # Errors
+    /// Returns `Error::Serialization` if serialization fails
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Import a transaction from Grin's transaction JSON
+    ///
+    /// # Errors
+    /// Returns `Error::Serialization` if the JSON is malformed or the inputs,
+    /// outputs or kernels are not sorted and unique
+    pub fn from_json(json: &str) -> Result<Self> {
+        let tx: Self = serde_json::from_str(json)?;
+        tx.verify_sorted()?;
+        Ok(tx)
+    }
+
+    /// JSON-RPC request for a node's foreign API `push_transaction`
+    ///
+    /// # Arguments
+    /// * `fluff` - Skip the Dandelion stem phase and broadcast immediately
+    ///
+    /// # Errors
+    /// Returns `Error::Serialization` if serialization fails
+    pub fn push_request(&self, fluff: bool) -> Result<String> {
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "push_transaction",
+            "id": 1,
+            "params": [self, fluff],
+        });
+        Ok(serde_json::to_string(&request)?)
+    }
+
+    /// Serialize to Grin's binary transaction layout
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&self.offset.0);
+        for count in [
+            self.body.inputs.len(),
+            self.body.outputs.len(),
+            self.body.kernels.len(),
+        ] {
+            buf.extend_from_slice(&(count as u64).to_be_bytes());
+        }
+        for input in &self.body.inputs {
+            input.write(&mut buf);
+        }
+        for output in &self.body.outputs {
+            output.write(&mut buf);
+        }
+        for kernel in &self.body.kernels {
+            kernel.write(&mut buf);
+        }
+        buf
+    }
+
+    /// Import a transaction from Grin's binary transaction layout
+    ///
+    /// # Errors
+    /// Returns `Error::Serialization` if the data is truncated, malformed,
+    /// heavier than a block, or not sorted and unique
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = ByteReader::new(bytes);
+        let offset = SecretKey::from_slice(&Secp256k1::without_caps(), reader.fixed(32)?)?;
+        let (n_inputs, n_outputs, n_kernels) = (reader.u64()?, reader.u64()?, reader.u64()?);
+        if weight_by_iok(n_inputs, n_outputs, n_kernels) > MAX_BLOCK_WEIGHT {
+            return Err(Error::Serialization("transaction too heavy".into()));
+        }
+
+        let inputs = (0..n_inputs)
+            .map(|_| Input::read(&mut reader))
+            .collect::<Result<_>>()?;
+        let outputs = (0..n_outputs)
+            .map(|_| Output::read(&mut reader))
+            .collect::<Result<_>>()?;
+        let kernels = (0..n_kernels)
+            .map(|_| TxKernel::read(&mut reader))
+            .collect::<Result<_>>()?;
+        if !reader.is_empty() {
+            return Err(Error::Serialization(
+                "trailing bytes after transaction".into(),
+            ));
+        }
+
+        let tx = Self {
+            offset,
+            body: TransactionBody {
+                inputs,
+                outputs,
+                kernels,
+            },
+        };
+        tx.verify_sorted()?;
+        Ok(tx)
+    }
+
+    fn verify_sorted(&self) -> Result<()> {
+        let sorted = |hashes: Vec<[u8; 32]>| hashes.windows(2).all(|w| w[0] < w[1]);
+        if sorted(self.body.inputs.iter().map(Input::hash).collect())
+            && sorted(self.body.outputs.iter().map(Output::hash).collect())
+            && sorted(self.body.kernels.iter().map(TxKernel::hash).collect())
+        {
+            Ok(())
+        } else {
+            Err(Error::Serialization(
+                "inputs, outputs and kernels must be sorted and unique".into(),
+            ))
+        }
+    }
+}
+
+/// Consensus weight of a body with the given number of inputs, outputs and kernels
+const fn weight_by_iok(inputs: u64, outputs: u64, kernels: u64) -> u64 {
+    inputs
+        .saturating_mul(INPUT_WEIGHT)
+        .saturating_add(outputs.saturating_mul(OUTPUT_WEIGHT))
+        .saturating_add(kernels.saturating_mul(KERNEL_WEIGHT))
+}
+
+/// Grin hash of a serialized item, used to order inputs, outputs and kernels
+fn hash(bytes: &[u8]) -> [u8; 32] {
+    Blake2b256::digest(bytes).into()
+}
+
+/// Cursor over binary transaction data
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> ByteReader<'a> {
+    const fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    const fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    fn fixed(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() < len {
+            return Err(Error::Serialization("unexpected end of data".into()));
+        }
+        let (head, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.fixed(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let mut buf = [0u8; 2];
+        buf.copy_from_slice(self.fixed(2)?);
+        Ok(u16::from_be_bytes(buf))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(self.fixed(8)?);
+        Ok(u64::from_be_bytes(buf))
+    }
+
+    fn commitment(&mut self) -> Result<Commitment> {
+        Ok(Commitment::from_vec(
+            self.fixed(PEDERSEN_COMMITMENT_SIZE)?.to_vec(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::finished_tx;
+    use crate::validation::validate;
+    use secp256k1zkp::ContextFlag;
+
+    /// Spend a 100-coin shared output into 60 and 38 coins with a height-locked kernel
+    fn spend(secp: &Secp256k1) -> Transaction {
+        let features = KernelFeatures::HeightLocked {
+            fee: 2,
+            lock_height: 1_000,
+        };
+        finished_tx(secp, features, &[60, 38])
+    }
+
+    #[test]
+    fn test_json_layout_and_roundtrip() {
+        let secp = Secp256k1::with_caps(ContextFlag::Commit);
+        let tx = spend(&secp);
+
+        let json = tx.to_json().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["offset"].as_str().unwrap().len(), 64);
+        assert_eq!(value["body"]["inputs"][0]["features"], "Plain");
+        assert_eq!(
+            value["body"]["inputs"][0]["commit"].as_str().unwrap().len(),
+            66
+        );
+        assert_eq!(value["body"]["outputs"].as_array().unwrap().len(), 2);
+        assert!(value["body"]["outputs"][0]["proof"].is_string());
+        assert_eq!(
+            value["body"]["kernels"][0]["features"],
+            serde_json::json!({"HeightLocked": {"fee": 2, "lock_height": 1000}})
+        );
+        assert_eq!(
+            value["body"]["kernels"][0]["excess_sig"]
+                .as_str()
+                .unwrap()
+                .len(),
+            128
+        );
+
+        let imported = Transaction::from_json(&json).unwrap();
+        assert_eq!(imported.to_json().unwrap(), json);
+        assert!(validate(&secp, &imported).is_valid());
+
+        let request: serde_json::Value =
+            serde_json::from_str(&tx.push_request(true).unwrap()).unwrap();
+        assert_eq!(request["method"], "push_transaction");
+        assert_eq!(request["params"][0], value);
+        assert_eq!(request["params"][1], true);
+    }
+
+    #[test]
+    fn test_binary_roundtrip() {
+        let secp = Secp256k1::with_caps(ContextFlag::Commit);
+        let tx = spend(&secp);
+
+        let bytes = tx.to_bytes();
+        let imported = Transaction::from_bytes(&bytes).unwrap();
+        assert_eq!(imported.to_bytes(), bytes);
+        assert_eq!(imported.to_json().unwrap(), tx.to_json().unwrap());
+        assert!(validate(&secp, &imported).is_valid());
+
+        assert!(matches!(
+            Transaction::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(Error::Serialization(_))
+        ));
+        let mut trailing = bytes;
+        trailing.push(0);
+        assert!(matches!(
+            Transaction::from_bytes(&trailing),
+            Err(Error::Serialization(_))
+        ));
+    }
+
+    #[test]
+    fn test_unsorted_import_rejected() {
+        let secp = Secp256k1::with_caps(ContextFlag::Commit);
+        let tx = spend(&secp);
+
+        let mut value: serde_json::Value = serde_json::from_str(&tx.to_json().unwrap()).unwrap();
+        value["body"]["outputs"].as_array_mut().unwrap().reverse();
+        assert!(matches!(
+            Transaction::from_json(&value.to_string()),
+            Err(Error::Serialization(_))
+        ));
+    }
+}
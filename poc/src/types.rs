@@ -1,9 +1,12 @@
 //! Type definitions for `MuSig2` protocol
 
 use blake2::{Blake2b512, Digest};
-use secp256k1zkp::{PublicKey, Secp256k1};
+use secp256k1zkp::{PublicKey, Secp256k1, SecretKey};
 use serde::{Deserialize, Serialize};
 
+use crate::error::Result;
+use crate::ser::hex_array;
+
 /// Key aggregation coefficient (32 bytes)
 ///
 /// Coefficients are computed as `a_i = H(L || X_i)` where:
@@ -31,6 +34,14 @@ impl Coefficient {
     pub const fn as_bytes(&self) -> &[u8; 32] {
         &self.0
     }
+
+    /// Interpret the coefficient as a scalar modulo the curve order
+    ///
+    /// # Errors
+    /// Returns `Error::Crypto` if the bytes are zero or exceed the curve order
+    pub fn to_secret_key(&self, secp: &Secp256k1) -> Result<SecretKey> {
+        Ok(SecretKey::from_slice(secp, &self.0)?)
+    }
 }
 
 impl AsRef<[u8]> for Coefficient {
@@ -59,8 +70,8 @@ impl From<[u8; 32]> for Coefficient {
 /// let commitment = NonceCommitment::new([0u8; 64]);
 /// assert_eq!(commitment.as_bytes().len(), 64);
 /// ```
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct NonceCommitment([u8; 64]);
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NonceCommitment(#[serde(with = "hex_array")] [u8; 64]);
 
 impl NonceCommitment {
     /// Create a new nonce commitment
@@ -169,6 +180,48 @@ impl From<[u8; 32]> for Challenge {
     }
 }
 
+/// Partial signature (64 bytes)
+///
+/// Produced by each participant in Round 3 and laid out as `R_i.x || s_i`,
+/// the same compact form Grin uses for kernel signatures, where:
+/// - `R_i` is the participant's public nonce
+/// - `s_i = r_i + c * a_i * x_i` is the participant's signature scalar
+///
+/// # Example
+///
+/// ```rust
+/// use grin_multisig_poc::PartialSignature;
+///
+/// let partial = PartialSignature::new([0u8; 64]);
+/// assert_eq!(partial.as_bytes().len(), 64);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartialSignature(#[serde(with = "hex_array")] [u8; 64]);
+
+impl PartialSignature {
+    /// Create a new partial signature from raw bytes
+    pub const fn new(bytes: [u8; 64]) -> Self {
+        Self(bytes)
+    }
+
+    /// Get the raw bytes
+    pub const fn as_bytes(&self) -> &[u8; 64] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for PartialSignature {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<[u8; 64]> for PartialSignature {
+    fn from(bytes: [u8; 64]) -> Self {
+        Self(bytes)
+    }
+}
+
 /// Participant identifier
 ///
 /// Unique identifier for each participant in the `MuSig2` protocol.
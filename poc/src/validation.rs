@@ -0,0 +1,314 @@
+//! Local validation of a finished transaction
+//!
+//! Every signer can check a transaction independently before it is broadcast.
+//! Checks run in a fixed order and stop at the first failure, which the
+//! report names. As in Grin, the cheap weight check runs first:
+//!
+//! 1. **Weight**: the transaction is no heavier than `MAX_TX_WEIGHT`
+//! 2. **Balance**: `sum(outputs) + fee*H - sum(inputs) = sum(excess) + offset*G`
+//! 3. **Kernel signatures**: each excess signature verifies under Grin rules
+//! 4. **Range proofs**: each output's Bulletproof verifies
+
+use std::fmt;
+
+use secp256k1zkp::{aggsig, Message, Secp256k1};
+
+use crate::error::{Error, Result};
+use crate::transaction::{Transaction, MAX_TX_WEIGHT};
+
+/// A single validation check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Check {
+    /// Transaction weight limit
+    Weight,
+    /// Commitments, fee, kernel excesses and offset balance
+    Balance,
+    /// Excess signature of the kernel at `kernel`
+    KernelSignature {
+        /// Index of the kernel
+        kernel: usize,
+    },
+    /// Range proof of the output at `output`
+    RangeProof {
+        /// Index of the output
+        output: usize,
+    },
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Weight => write!(f, "weight"),
+            Self::Balance => write!(f, "balance"),
+            Self::KernelSignature { kernel } => write!(f, "kernel {kernel} signature"),
+            Self::RangeProof { output } => write!(f, "output {output} range proof"),
+        }
+    }
+}
+
+/// The check that failed and why
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    /// Failing check
+    check: Check,
+
+    /// Human readable reason
+    reason: String,
+}
+
+impl Failure {
+    /// Get the failing check
+    pub const fn check(&self) -> Check {
+        self.check
+    }
+
+    /// Get the reason the check failed
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.check, self.reason)
+    }
+}
+
+/// Outcome of validating a transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// Checks that passed, in the order they ran
+    passed: Vec<Check>,
+
+    /// First failing check, if any
+    failure: Option<Failure>,
+}
+
+impl ValidationReport {
+    /// Whether every check passed
+    pub const fn is_valid(&self) -> bool {
+        self.failure.is_none()
+    }
+
+    /// Get the checks that passed, in the order they ran
+    pub fn passed(&self) -> &[Check] {
+        &self.passed
+    }
+
+    /// Get the first failing check, if any
+    pub const fn failure(&self) -> Option<&Failure> {
+        self.failure.as_ref()
+    }
+
+    /// Convert the report into a `Result`
+    ///
+    /// # Errors
+    /// Returns `Error::ValidationFailed` naming the first failing check
+    pub fn into_result(self) -> Result<()> {
+        self.failure.map_or(Ok(()), |failure| {
+            Err(Error::ValidationFailed(failure.to_string()))
+        })
+    }
+
+    fn run(&mut self, check: Check, outcome: std::result::Result<(), String>) -> bool {
+        match outcome {
+            Ok(()) => {
+                self.passed.push(check);
+                true
+            }
+            Err(reason) => {
+                self.failure = Some(Failure { check, reason });
+                false
+            }
+        }
+    }
+}
+
+/// Validate a finished transaction
+///
+/// # Arguments
+/// * `secp` - Context with commitment capabilities
+/// * `tx` - Transaction to validate
+///
+/// # Returns
+/// Report listing the passed checks and the first failing one
+pub fn validate(secp: &Secp256k1, tx: &Transaction) -> ValidationReport {
+    let mut report = ValidationReport {
+        passed: Vec::new(),
+        failure: None,
+    };
+
+    let weight = tx.weight();
+    let outcome = if weight > MAX_TX_WEIGHT {
+        Err(format!("weight {weight} exceeds {MAX_TX_WEIGHT}"))
+    } else {
+        Ok(())
+    };
+    if !report.run(Check::Weight, outcome) {
+        return report;
+    }
+
+    if !report.run(Check::Balance, check_balance(secp, tx)) {
+        return report;
+    }
+
+    for (kernel, tx_kernel) in tx.kernels().iter().enumerate() {
+        let outcome = Message::from_slice(&tx_kernel.features().kernel_sig_msg())
+            .and_then(|msg| Ok((msg, tx_kernel.excess().to_pubkey(secp)?)))
+            .map_err(|e| e.to_string())
+            .and_then(|(msg, excess)| {
+                aggsig::verify_single(
+                    secp,
+                    tx_kernel.excess_sig(),
+                    &msg,
+                    None,
+                    &excess,
+                    Some(&excess),
+                    None,
+                    false,
+                )
+                .then_some(())
+                .ok_or_else(|| "signature does not verify against the excess".to_string())
+            });
+        if !report.run(Check::KernelSignature { kernel }, outcome) {
+            return report;
+        }
+    }
+
+    for (output, tx_output) in tx.outputs().iter().enumerate() {
+        let outcome = secp
+            .verify_bullet_proof(*tx_output.commitment(), *tx_output.proof(), None)
+            .map(|_| ())
+            .map_err(|e| e.to_string());
+        if !report.run(Check::RangeProof { output }, outcome) {
+            return report;
+        }
+    }
+
+    report
+}
+
+fn check_balance(secp: &Secp256k1, tx: &Transaction) -> std::result::Result<(), String> {
+    if tx.kernels().is_empty() {
+        return Err("transaction has no kernels".into());
+    }
+
+    let mut positive: Vec<_> = tx.outputs().iter().map(|o| *o.commitment()).collect();
+    let fee = tx.fee();
+    if fee > 0 {
+        positive.push(secp.commit_value(fee).map_err(|e| e.to_string())?);
+    }
+
+    let mut negative: Vec<_> = tx.inputs().iter().map(|i| *i.commitment()).collect();
+    negative.extend(tx.kernels().iter().map(|k| *k.excess()));
+    negative.push(
+        secp.commit(0, tx.offset().clone())
+            .map_err(|e| e.to_string())?,
+    );
+
+    if secp.verify_commit_sum(positive, negative) {
+        Ok(())
+    } else {
+        Err("sum(outputs) + fee*H - sum(inputs) != sum(excess) + offset*G".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::finished_tx;
+    use crate::transaction::{KernelFeatures, Output, TxKernel, OUTPUT_WEIGHT};
+    use rand::thread_rng;
+    use secp256k1zkp::{ContextFlag, SecretKey};
+
+    /// Spend a 10-coin shared output into 9 coins with a fee of 1
+    fn spend(secp: &Secp256k1) -> Transaction {
+        finished_tx(secp, KernelFeatures::Plain { fee: 1 }, &[9])
+    }
+
+    #[test]
+    fn test_valid_transaction_passes_every_check() {
+        let secp = Secp256k1::with_caps(ContextFlag::Commit);
+        let tx = spend(&secp);
+
+        let report = validate(&secp, &tx);
+        assert!(report.is_valid());
+        assert_eq!(
+            report.passed(),
+            &[
+                Check::Weight,
+                Check::Balance,
+                Check::KernelSignature { kernel: 0 },
+                Check::RangeProof { output: 0 },
+            ]
+        );
+        assert!(report.into_result().is_ok());
+    }
+
+    #[test]
+    fn test_report_names_first_failing_check() {
+        let secp = Secp256k1::with_caps(ContextFlag::Commit);
+        let tx = spend(&secp);
+
+        // Raising the fee breaks the balance and the kernel signature
+        let kernel = tx.kernels()[0];
+        let tampered = Transaction::new(
+            tx.offset().clone(),
+            tx.inputs().to_vec(),
+            tx.outputs().to_vec(),
+            vec![TxKernel::new(
+                KernelFeatures::Plain { fee: 2 },
+                *kernel.excess(),
+                *kernel.excess_sig(),
+            )],
+        );
+        let report = validate(&secp, &tampered);
+        assert_eq!(report.failure().unwrap().check(), Check::Balance);
+        assert_eq!(report.passed(), &[Check::Weight]);
+
+        // A proof for another commitment fails only the range proof check
+        let other = Output::create(&secp, 9, &SecretKey::new(&secp, &mut thread_rng())).unwrap();
+        let output = tx.outputs()[0];
+        let swapped = Transaction::new(
+            tx.offset().clone(),
+            tx.inputs().to_vec(),
+            vec![Output::new(
+                output.features(),
+                *output.commitment(),
+                *other.proof(),
+            )],
+            tx.kernels().to_vec(),
+        );
+        let report = validate(&secp, &swapped);
+        assert_eq!(
+            report.failure().unwrap().check(),
+            Check::RangeProof { output: 0 }
+        );
+        assert!(matches!(
+            report.into_result(),
+            Err(Error::ValidationFailed(msg)) if msg.starts_with("output 0 range proof")
+        ));
+    }
+
+    #[test]
+    fn test_overweight_transaction_rejected() {
+        let secp = Secp256k1::with_caps(ContextFlag::Commit);
+        let tx = spend(&secp);
+
+        // Weight is checked first, so the padding never reaches the balance check
+        let count = usize::try_from(MAX_TX_WEIGHT / OUTPUT_WEIGHT + 1).unwrap();
+        let mut outputs = tx.outputs().to_vec();
+        outputs.extend(std::iter::repeat_n(tx.outputs()[0], count));
+        let padded = Transaction::new(
+            tx.offset().clone(),
+            tx.inputs().to_vec(),
+            outputs,
+            tx.kernels().to_vec(),
+        );
+
+        assert!(padded.weight() > MAX_TX_WEIGHT);
+        let report = validate(&secp, &padded);
+        assert_eq!(report.failure().unwrap().check(), Check::Weight);
+        assert!(report.passed().is_empty());
+    }
+}
@@ -0,0 +1,414 @@
+//! Shamir secret sharing with Feldman verifiable commitments
+//!
+//! A dealer hides the secret in the constant term of a random polynomial of
+//! degree `t - 1`, hands participant `i` the evaluation `f(i)` and publishes
+//! commitments to every coefficient, so each recipient can check its share
+//! without learning anything about the others.
+//!
+//! # Algorithm
+//! ```text
+//! f(x)  = a_0 + a_1*x + ... + a_{t-1}*x^{t-1},   a_0 = secret
+//! C_k   = a_k * G                                 (Feldman commitments)
+//! s_i   = f(i)
+//! check:  s_i * G == sum(C_k * i^k)
+//! ```
+
+use std::collections::BTreeMap;
+
+use rand::thread_rng;
+use secp256k1zkp::{PublicKey, Secp256k1, SecretKey};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::frost::{KeyPackage, PublicKeyPackage};
+use crate::scalar;
+use crate::ser::{hex_pubkey_vec, hex_seckey};
+use crate::types::ParticipantId;
+
+/// A secret polynomial over the curve's scalar field
+#[derive(Debug, Clone)]
+pub(crate) struct Polynomial {
+    /// Coefficients `a_0 .. a_{t-1}`
+    coefficients: Vec<SecretKey>,
+}
+
+impl Polynomial {
+    /// Random polynomial of degree `threshold - 1` with the given constant term
+    pub(crate) fn random(secp: &Secp256k1, constant: SecretKey, threshold: usize) -> Self {
+        let mut rng = thread_rng();
+        let mut coefficients = Vec::with_capacity(threshold);
+        coefficients.push(constant);
+        coefficients.extend((1..threshold).map(|_| SecretKey::new(secp, &mut rng)));
+        Self { coefficients }
+    }
+
+    /// Constant term `a_0`
+    pub(crate) fn constant(&self) -> &SecretKey {
+        &self.coefficients[0]
+    }
+
+    /// Evaluate `f(i)` at a participant's share index
+    pub(crate) fn evaluate(
+        &self,
+        secp: &Secp256k1,
+        participant: ParticipantId,
+    ) -> Result<SecretKey> {
+        let x = scalar::share_index(secp, participant)?;
+        let mut coefficients = self.coefficients.iter().rev();
+        let mut value = coefficients.next().cloned().ok_or(Error::NoParticipants)?;
+        for coefficient in coefficients {
+            value.mul_assign(secp, &x)?;
+            value.add_assign(secp, coefficient)?;
+        }
+        Ok(value)
+    }
+
+    /// Feldman commitment to every coefficient
+    pub(crate) fn commit(&self, secp: &Secp256k1) -> Result<VssCommitment> {
+        let points = self
+            .coefficients
+            .iter()
+            .map(|a| PublicKey::from_secret_key(secp, a))
+            .collect::<std::result::Result<_, _>>()?;
+        Ok(VssCommitment(points))
+    }
+}
+
+/// Public Feldman commitments `C_k = a_k * G` to a sharing polynomial
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VssCommitment(#[serde(with = "hex_pubkey_vec")] Vec<PublicKey>);
+
+impl VssCommitment {
+    /// Create commitments from already known points
+    pub const fn new(points: Vec<PublicKey>) -> Self {
+        Self(points)
+    }
+
+    /// Get the coefficient commitments
+    pub fn points(&self) -> &[PublicKey] {
+        &self.0
+    }
+
+    /// Pointwise sum of commitments to polynomials of equal degree
+    ///
+    /// The result commits to the sum of the polynomials.
+    ///
+    /// # Errors
+    /// Returns `Error::NoParticipants` if `commitments` is empty
+    /// Returns `Error::MalformedMessage` if the degrees differ
+    pub fn sum(secp: &Secp256k1, commitments: &[&Self]) -> Result<Self> {
+        let first = commitments.first().ok_or(Error::NoParticipants)?;
+        let threshold = first.threshold();
+        if let Some(other) = commitments.iter().find(|c| c.threshold() != threshold) {
+            return Err(Error::MalformedMessage(format!(
+                "commitments of {} and {} coefficients summed",
+                threshold,
+                other.threshold()
+            )));
+        }
+
+        let points = (0..threshold)
+            .map(|k| {
+                PublicKey::from_combination(secp, commitments.iter().map(|c| &c.0[k]).collect())
+            })
+            .collect::<std::result::Result<_, _>>()?;
+        Ok(Self(points))
+    }
+
+    /// Threshold implied by the polynomial degree
+    pub const fn threshold(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Public key of the shared secret, `C_0`
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidThreshold` if the commitment is empty
+    pub fn group_public_key(&self) -> Result<PublicKey> {
+        self.0.first().copied().ok_or(Error::InvalidThreshold {
+            threshold: 0,
+            participants: 0,
+        })
+    }
+
+    /// Expected public share `f(i) * G = sum(C_k * i^k)`
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidShareIndex` if `participant` is 0
+    /// Returns `Error::InvalidThreshold` if the commitment is empty
+    pub fn verifying_share(
+        &self,
+        secp: &Secp256k1,
+        participant: ParticipantId,
+    ) -> Result<PublicKey> {
+        let x = scalar::share_index(secp, participant)?;
+        let mut points = self.0.iter().rev();
+        let mut value = *points.next().ok_or(Error::InvalidThreshold {
+            threshold: 0,
+            participants: 0,
+        })?;
+        for point in points {
+            value.mul_assign(secp, &x)?;
+            value = PublicKey::from_combination(secp, vec![&value, point])?;
+        }
+        Ok(value)
+    }
+}
+
+/// A participant's share of a dealt secret, with the dealer's commitments
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecretShare {
+    /// Recipient
+    participant: ParticipantId,
+
+    /// Share `f(i)`
+    #[serde(with = "hex_seckey")]
+    value: SecretKey,
+
+    /// Dealer's commitments to `f`
+    commitment: VssCommitment,
+}
+
+impl SecretShare {
+    /// Create a share from already known values
+    pub const fn new(
+        participant: ParticipantId,
+        value: SecretKey,
+        commitment: VssCommitment,
+    ) -> Self {
+        Self {
+            participant,
+            value,
+            commitment,
+        }
+    }
+
+    /// Get the recipient
+    pub const fn participant(&self) -> ParticipantId {
+        self.participant
+    }
+
+    /// Get the share value
+    pub const fn value(&self) -> &SecretKey {
+        &self.value
+    }
+
+    /// Get the dealer's commitments
+    pub const fn commitment(&self) -> &VssCommitment {
+        &self.commitment
+    }
+
+    /// Check the share against the dealer's commitments
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidSecretShare` if `f(i) * G` does not match the commitments
+    pub fn verify(&self, secp: &Secp256k1) -> Result<()> {
+        let expected = self.commitment.verifying_share(secp, self.participant)?;
+        if PublicKey::from_secret_key(secp, &self.value)? == expected {
+            Ok(())
+        } else {
+            Err(Error::InvalidSecretShare {
+                participant: self.participant,
+            })
+        }
+    }
+
+    /// Verify the share and turn it into FROST key material
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidSecretShare` if the share does not match the commitments
+    pub fn into_key_package(self, secp: &Secp256k1) -> Result<KeyPackage> {
+        self.verify(secp)?;
+        KeyPackage::new(
+            secp,
+            self.participant,
+            self.value,
+            self.commitment.group_public_key()?,
+            self.commitment.threshold(),
+        )
+    }
+}
+
+/// Split a secret key into `t`-of-`n` Shamir shares with Feldman commitments
+///
+/// # Arguments
+/// * `secp` - Secp256k1 context
+/// * `secret` - Key to split; it becomes the group secret
+/// * `min_signers` - Threshold `t`
+/// * `participants` - Recipients; their IDs are the share indices
+///
+/// # Returns
+/// One share per participant and the group's public key package
+///
+/// # Errors
+/// Returns `Error::InvalidThreshold` unless `1 <= t <= n`
+/// Returns `Error::DuplicateParticipant` if a participant is listed twice
+/// Returns `Error::InvalidShareIndex` if a participant ID is 0
+pub fn trusted_dealer_keygen(
+    secp: &Secp256k1,
+    secret: &SecretKey,
+    min_signers: usize,
+    participants: &[ParticipantId],
+) -> Result<(Vec<SecretShare>, PublicKeyPackage)> {
+    check_threshold(min_signers, participants)?;
+
+    let polynomial = Polynomial::random(secp, secret.clone(), min_signers);
+    let commitment = polynomial.commit(secp)?;
+
+    let mut shares = Vec::with_capacity(participants.len());
+    let mut verifying_shares = BTreeMap::new();
+    for &participant in participants {
+        let value = polynomial.evaluate(secp, participant)?;
+        verifying_shares.insert(participant, PublicKey::from_secret_key(secp, &value)?);
+        shares.push(SecretShare::new(participant, value, commitment.clone()));
+    }
+
+    let public_keys = PublicKeyPackage::new(
+        verifying_shares,
+        commitment.group_public_key()?,
+        min_signers,
+    );
+    Ok((shares, public_keys))
+}
+
+/// Check `1 <= t <= n` and that participant IDs are unique, non-zero share indices
+pub(crate) fn check_threshold(min_signers: usize, participants: &[ParticipantId]) -> Result<()> {
+    if min_signers == 0 || min_signers > participants.len() {
+        return Err(Error::InvalidThreshold {
+            threshold: min_signers,
+            participants: participants.len(),
+        });
+    }
+    for (i, &participant) in participants.iter().enumerate() {
+        if participant.inner() == 0 {
+            return Err(Error::InvalidShareIndex { participant });
+        }
+        if participants[..i].contains(&participant) {
+            return Err(Error::DuplicateParticipant { participant });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::try_sign;
+
+    fn ids(n: u32) -> Vec<ParticipantId> {
+        (1..=n).map(ParticipantId::new).collect()
+    }
+
+    #[test]
+    fn test_dealt_shares_verify_and_sign() {
+        let secp = Secp256k1::new();
+        let secret = SecretKey::new(&secp, &mut thread_rng());
+        let (shares, public_keys) = trusted_dealer_keygen(&secp, &secret, 2, &ids(3)).unwrap();
+
+        assert_eq!(
+            *public_keys.group_public_key(),
+            PublicKey::from_secret_key(&secp, &secret).unwrap()
+        );
+        for share in &shares {
+            assert!(share.verify(&secp).is_ok());
+            assert_eq!(
+                share
+                    .commitment()
+                    .verifying_share(&secp, share.participant())
+                    .unwrap(),
+                *public_keys.verifying_share(share.participant()).unwrap()
+            );
+        }
+
+        // Participants 1 and 3 sign for the dealt key
+        let keys: Vec<_> = [&shares[0], &shares[2]]
+            .iter()
+            .map(|s| (*s).clone().into_key_package(&secp).unwrap())
+            .collect();
+        assert!(try_sign(&secp, &[&keys[0], &keys[1]], &public_keys).is_ok());
+    }
+
+    #[test]
+    fn test_tampered_share_rejected() {
+        let secp = Secp256k1::new();
+        let secret = SecretKey::new(&secp, &mut thread_rng());
+        let (shares, _) = trusted_dealer_keygen(&secp, &secret, 3, &ids(5)).unwrap();
+
+        // A share handed to the wrong participant
+        let misdelivered = SecretShare::new(
+            ParticipantId::new(2),
+            shares[0].value().clone(),
+            shares[0].commitment().clone(),
+        );
+        assert_eq!(
+            misdelivered.verify(&secp),
+            Err(Error::InvalidSecretShare {
+                participant: ParticipantId::new(2)
+            })
+        );
+
+        // A commitment the dealer did not use for this share
+        let (other, _) = trusted_dealer_keygen(&secp, &secret, 3, &ids(5)).unwrap();
+        let inconsistent = SecretShare::new(
+            ParticipantId::new(1),
+            shares[0].value().clone(),
+            other[0].commitment().clone(),
+        );
+        assert!(matches!(
+            inconsistent.into_key_package(&secp),
+            Err(Error::InvalidSecretShare { .. })
+        ));
+    }
+
+    #[test]
+    fn test_invalid_dealer_parameters() {
+        let secp = Secp256k1::new();
+        let secret = SecretKey::new(&secp, &mut thread_rng());
+
+        assert_eq!(
+            trusted_dealer_keygen(&secp, &secret, 4, &ids(3)).unwrap_err(),
+            Error::InvalidThreshold {
+                threshold: 4,
+                participants: 3
+            }
+        );
+        assert!(matches!(
+            trusted_dealer_keygen(&secp, &secret, 0, &ids(3)),
+            Err(Error::InvalidThreshold { .. })
+        ));
+        assert!(matches!(
+            trusted_dealer_keygen(
+                &secp,
+                &secret,
+                2,
+                &[ParticipantId::new(1), ParticipantId::new(1)]
+            ),
+            Err(Error::DuplicateParticipant { .. })
+        ));
+        assert!(matches!(
+            trusted_dealer_keygen(&secp, &secret, 1, &[ParticipantId::new(0)]),
+            Err(Error::InvalidShareIndex { .. })
+        ));
+    }
+
+    #[test]
+    fn test_sum_rejects_mixed_degrees() {
+        let secp = Secp256k1::new();
+        let point = |_| {
+            PublicKey::from_secret_key(&secp, &SecretKey::new(&secp, &mut thread_rng())).unwrap()
+        };
+        let linear = VssCommitment::new((0..2).map(point).collect());
+        let quadratic = VssCommitment::new((0..3).map(point).collect());
+
+        assert_eq!(
+            VssCommitment::sum(&secp, &[&linear, &linear])
+                .unwrap()
+                .threshold(),
+            2
+        );
+        assert!(matches!(
+            VssCommitment::sum(&secp, &[&linear, &quadratic]),
+            Err(Error::MalformedMessage(_))
+        ));
+    }
+}